@@ -0,0 +1,44 @@
+//! Timing loop shared by the benchmarks
+// Each benchmark uses part of the ROM builder
+#![allow(dead_code)]
+
+use std::time::Instant;
+
+use backend::system::System;
+
+#[path = "../../tests/common/rom.rs"]
+mod rom;
+
+pub use rom::RomBuilder;
+
+const WARMUP_STEPS: u64 = 1_000_000;
+const STEPS: u64 = 50_000_000;
+const RUNS: usize = 5;
+
+/// Runs `rom` from power on and prints the median, min and max throughput in millions of
+/// instructions per second (MIPS)
+pub fn measure_mips(name: &str, rom: &[u8]) {
+    let mut results = Vec::with_capacity(RUNS);
+    for _ in 0..RUNS {
+        let mut system = System::new(None, rom.to_vec()).unwrap();
+        for _ in 0..WARMUP_STEPS {
+            system.step();
+        }
+        let start = Instant::now();
+        for _ in 0..STEPS {
+            system.step();
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        results.push(STEPS as f64 / elapsed / 1_000_000.0);
+    }
+    results.sort_by(|a, b| a.total_cmp(b));
+    println!(
+        "{}: median {:.2} MIPS (min {:.2}, max {:.2}) over {} runs of {} instructions",
+        name,
+        results[RUNS / 2],
+        results[0],
+        results[RUNS - 1],
+        RUNS,
+        STEPS
+    );
+}
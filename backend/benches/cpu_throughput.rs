@@ -0,0 +1,51 @@
+//! Measures raw interpreter throughput in millions of instructions per second (MIPS).
+//!
+//! Runs a synthetic CPU-bound ROM by default, set `GB_BENCH_ROM` to benchmark a real ROM instead.
+//! Run with `cargo bench --bench cpu_throughput`.
+//!
+//! Medians of 5 runs of 50M instructions, release build, on the same machine:
+//! - match based opcode lookup: 9.8 - 10.6 MIPS
+//! - 256-entry opcode tables: 10.9 - 11.4 MIPS
+//! - page table for memory handlers: 47.5 MIPS
+
+mod common;
+
+use common::{measure_mips, RomBuilder};
+
+/// Tight loop mixing loads, ALU, CB-prefixed, stack and control flow instructions
+fn synthetic_rom() -> Vec<u8> {
+    #[rustfmt::skip]
+    let program = [
+        0xF3,                   // $0150: DI
+        0x31, 0xFE, 0xDF,       //        LD SP,$DFFE
+        0x21, 0x00, 0xC0,       //        LD HL,$C000
+        0x7E,                   // $0157: LD A,(HL)
+        0x80,                   //        ADD A,B
+        0x22,                   //        LD (HL+),A
+        0x04,                   //        INC B
+        0xA9,                   //        XOR C
+        0x4F,                   //        LD C,A
+        0xCB, 0x37,             //        SWAP A
+        0x07,                   //        RLCA
+        0x1B,                   //        DEC DE
+        0x7C,                   //        LD A,H
+        0xE6, 0xC1,             //        AND $C1
+        0xF6, 0xC0,             //        OR $C0
+        0x67,                   //        LD H,A
+        0xCD, 0x00, 0x02,       //        CALL $0200
+        0x18, 0xEB,             //        JR $0157
+    ];
+    RomBuilder::new(&program)
+        .bytes(0x200, &[0xC5, 0xC1, 0xC9]) // PUSH BC; POP BC; RET
+        .build()
+}
+
+fn main() {
+    let rom = match std::env::var("GB_BENCH_ROM") {
+        Ok(path) => {
+            std::fs::read(&path).unwrap_or_else(|e| panic!("Unable to open {}: {}", path, e))
+        }
+        Err(_) => synthetic_rom(),
+    };
+    measure_mips("cpu_throughput", &rom);
+}
@@ -0,0 +1,249 @@
+//! Generates the opcode tables of `cpu::instructions` from `instructions.yml`
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+const INSTRUCTIONS: &str = "instructions.yml";
+const PREFIX: i64 = 0xCB00;
+
+struct Entry {
+    code: i64,
+    operator: String,
+    operands: Vec<String>,
+    bits: i64,
+    size: i64,
+    // Cycles when a conditional branch is not taken (or the only timing)
+    cycles: i64,
+    // Cycles when a conditional branch is taken
+    conditional_cycles: Option<i64>,
+    flags: [String; 4],
+}
+
+fn scalar(value: &Yaml) -> String {
+    match value {
+        Yaml::String(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        _ => panic!("Unexpected value {:?} in {}", value, INSTRUCTIONS),
+    }
+}
+
+fn integer(entry: &Yaml, key: &str) -> i64 {
+    match entry[key].as_i64() {
+        Some(value) => value,
+        None => panic!("Missing integer '{}' in {:?}", key, entry),
+    }
+}
+
+fn parse(entry: &Yaml) -> Entry {
+    let operands = match entry["operands"].as_vec() {
+        Some(operands) => operands.iter().map(scalar).collect(),
+        None => panic!("Missing operands in {:?}", entry),
+    };
+    let (cycles, conditional_cycles) = match &entry["time"] {
+        Yaml::Integer(cycles) => (*cycles, None),
+        Yaml::Array(times) => match times.as_slice() {
+            [Yaml::Integer(taken), Yaml::Integer(not_taken)] => (*not_taken, Some(*taken)),
+            _ => panic!("Invalid conditional timing in {:?}", entry),
+        },
+        _ => panic!("Missing time in {:?}", entry),
+    };
+    Entry {
+        code: integer(entry, "code"),
+        operator: scalar(&entry["operator"]),
+        operands,
+        bits: integer(entry, "bits"),
+        size: integer(entry, "size"),
+        cycles,
+        conditional_cycles,
+        flags: ["z", "n", "h", "c"].map(|flag| scalar(&entry[flag])),
+    }
+}
+
+fn operand_expression(operand: &str) -> String {
+    match operand {
+        "(0xff00+a8)" => return "DMem(Imem8)".to_string(),
+        "(0xff00+c)" => return "DMem(Reg8::C)".to_string(),
+        _ => {}
+    }
+    if let Some(inner) = operand.strip_prefix('(').and_then(|o| o.strip_suffix(')')) {
+        return format!("Mem({})", operand_expression(inner));
+    }
+    match operand {
+        "af" | "bc" | "de" | "hl" | "sp" | "pc" => format!("Reg16::{}", operand.to_uppercase()),
+        "a" | "b" | "c" | "d" | "e" | "h" | "l" => format!("Reg8::{}", operand.to_uppercase()),
+        "r8" | "d8" => "Imem8".to_string(),
+        "d16" | "a16" => "Imem16".to_string(),
+        "nc" => "Condition::NotCarry".to_string(),
+        "cf" => "Condition::Carry".to_string(),
+        "nz" => "Condition::NotZero".to_string(),
+        "z" => "Condition::Zero".to_string(),
+        // Bit indexes
+        _ => operand.to_string(),
+    }
+}
+
+fn execute(entry: &Entry) -> String {
+    let operator = entry.operator.as_str();
+    let mut params: Vec<String> = entry
+        .operands
+        .iter()
+        .map(|o| operand_expression(o))
+        .collect();
+    let function = match operator {
+        "stop" => return "{ cpu.stop(); Timing::Normal }".to_string(),
+        "ldhl" => return "ldhl(cpu)".to_string(),
+        "reti" => return "reti(cpu)".to_string(),
+        "add" if entry.operands[0] == "sp" => return "add_sp(cpu)".to_string(),
+        "rst" => {
+            let vector: u8 = entry.operands[0].parse().expect("Invalid RST vector");
+            return format!("rst(cpu, 0x{:02X})", vector);
+        }
+        // Loads with post increment/decrement of HL
+        "ldi" | "ldd" => {
+            params.push("Reg16::HL".to_string());
+            operator.to_string()
+        }
+        "inc" | "dec" | "add" if entry.bits == 16 => format!("{}16", operator),
+        "sub" | "and" | "xor" | "or" => {
+            params.insert(0, "Reg8::A".to_string());
+            operator.to_string()
+        }
+        "jr" | "jp" | "call" | "ret" => {
+            if !params.iter().any(|p| p.starts_with("Condition::")) {
+                params.insert(0, "Condition::Unconditional".to_string());
+            }
+            operator.to_string()
+        }
+        _ => operator.to_string(),
+    };
+    params.insert(0, "cpu".to_string());
+    format!("{}({})", function, params.join(", "))
+}
+
+/// Mnemonic with operand placeholders, see `Instruction::mnemonic`
+fn mnemonic(entry: &Entry) -> String {
+    let mut name = entry.operator.to_uppercase();
+    let mut operands = Vec::new();
+    match entry.operator.as_str() {
+        "ldhl" => return "LD HL,SP+r8".to_string(),
+        "stop" => return "STOP".to_string(),
+        "rst" => {
+            let vector: u8 = entry.operands[0].parse().expect("Invalid RST vector");
+            return format!("RST ${:02X}", vector);
+        }
+        "ldi" | "ldd" => name = "LD".to_string(),
+        _ => {}
+    }
+    for operand in entry.operands.iter() {
+        let operand = match operand.as_str() {
+            "(hl)" if entry.operator == "ldi" => "(HL+)".to_string(),
+            "(hl)" if entry.operator == "ldd" => "(HL-)".to_string(),
+            "(0xff00+a8)" => {
+                name = "LDH".to_string();
+                "(a8)".to_string()
+            }
+            "(0xff00+c)" => "($FF00+C)".to_string(),
+            "cf" => "C".to_string(),
+            "d8" | "d16" | "a8" | "a16" | "r8" | "(a16)" => operand.clone(),
+            _ => operand.to_uppercase(),
+        };
+        operands.push(operand);
+    }
+    if operands.is_empty() {
+        name
+    } else {
+        format!("{} {}", name, operands.join(","))
+    }
+}
+
+fn flag_effect(flag: &str) -> &'static str {
+    match flag {
+        "-" => "FlagEffect::Unaffected",
+        "0" => "FlagEffect::Reset",
+        "1" => "FlagEffect::Set",
+        _ => "FlagEffect::Affected",
+    }
+}
+
+fn instruction(entry: &Entry) -> String {
+    let conditional_cycles = match entry.conditional_cycles {
+        Some(cycles) => format!("Some({})", cycles),
+        None => "None".to_string(),
+    };
+    let [zero, subtract, half_carry, carry] = entry.flags.each_ref().map(|f| flag_effect(f));
+    format!(
+        "Instruction {{
+            c_cycles: {},
+            conditional_c_cycles: {},
+            size: {},
+            mnemonic: \"{}\",
+            illegal: false,
+            flags: FlagEffects {{
+                zero: {},
+                subtract: {},
+                half_carry: {},
+                carry: {},
+            }},
+            execute: |cpu: &mut Cpu| {},
+        }}",
+        entry.cycles,
+        conditional_cycles,
+        entry.size,
+        mnemonic(entry),
+        zero,
+        subtract,
+        half_carry,
+        carry,
+        execute(entry),
+    )
+}
+
+fn table(name: &str, entries: &[&Entry], offset: i64) -> String {
+    let mut slots = vec![String::from("ILLEGAL"); 256];
+    for entry in entries {
+        slots[(entry.code - offset) as usize] = match entry.operator.as_str() {
+            "nop" => String::from("NOP"),
+            // Handled by the fetch loop
+            "prefix" => continue,
+            _ => instruction(entry),
+        };
+    }
+    let mut table = format!("pub static {}: [Instruction; 256] = [\n", name);
+    for (opcode, slot) in slots.iter().enumerate() {
+        writeln!(table, "    // 0x{:02X}\n    {},", opcode, slot).unwrap();
+    }
+    table.push_str("];\n");
+    table
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", INSTRUCTIONS);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let source = fs::read_to_string(INSTRUCTIONS)
+        .unwrap_or_else(|e| panic!("Unable to read {}: {}", INSTRUCTIONS, e));
+    let documents = YamlLoader::load_from_str(&source)
+        .unwrap_or_else(|e| panic!("Invalid YAML in {}: {}", INSTRUCTIONS, e));
+    let entries: Vec<Entry> = match documents.first().and_then(|d| d.as_vec()) {
+        Some(entries) => entries.iter().map(parse).collect(),
+        None => panic!("{} should contain a list of instructions", INSTRUCTIONS),
+    };
+
+    let unprefixed: Vec<&Entry> = entries.iter().filter(|e| e.code < PREFIX).collect();
+    let prefixed: Vec<&Entry> = entries.iter().filter(|e| e.code >= PREFIX).collect();
+
+    let mut generated =
+        String::from("// Generated by build.rs from instructions.yml, do not edit\n\n");
+    generated.push_str(&table("UNPREFIXED_INSTRUCTIONS", &unprefixed, 0));
+    generated.push('\n');
+    generated.push_str(&table("PREFIXED_INSTRUCTIONS", &prefixed, PREFIX));
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is not set");
+    let destination = Path::new(&out_dir).join("instructions.rs");
+    fs::write(&destination, generated)
+        .unwrap_or_else(|e| panic!("Unable to write {}: {}", destination.display(), e));
+}
@@ -0,0 +1,348 @@
+//! State left by the boot ROM of each model when it hands over to the cartridge, used to
+//! start directly at the entry point when no boot ROM is given
+
+use crate::cpu::registers::{Flags, Registers};
+use crate::memory::mmu::Mmu;
+use crate::system::Model;
+
+const ENTRY_POINT: u16 = 0x0100;
+const LOGO: usize = 0x0104;
+const LOGO_SIZE: usize = 48;
+const TITLE: usize = 0x0134;
+const CGB_FLAG: usize = 0x0143;
+const NEW_LICENSEE: usize = 0x0144;
+const OLD_LICENSEE: usize = 0x014B;
+const HEADER_CHECKSUM: usize = 0x014D;
+
+// Logo tiles start at tile 1, the ® tile follows them
+const LOGO_TILES: u16 = 0x8010;
+const REGISTERED_TILE: u8 = 0x19;
+const REGISTERED: [u8; 8] = [0x3C, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x3C];
+const LOGO_MAP_TOP: u16 = 0x9904;
+const LOGO_MAP_BOTTOM: u16 = 0x9924;
+const REGISTERED_MAP: u16 = 0x9910;
+
+const WHITE: u16 = 0x7FFF;
+const KEY0_DMG_COMPATIBILITY: u8 = 0x04;
+
+/// I/O registers common to every model, APU registers as left by the boot beep
+const IO_REGISTERS: [(u16, u8); 30] = [
+    (0xFF05, 0x00), // TIMA
+    (0xFF06, 0x00), // TMA
+    (0xFF07, 0x00), // TAC
+    (0xFF0F, 0x01), // IF, the last VBlank is still pending
+    (0xFF10, 0x80), // NR10
+    (0xFF11, 0xBF), // NR11
+    (0xFF12, 0xF3), // NR12
+    (0xFF13, 0xFF), // NR13
+    (0xFF14, 0xBF), // NR14
+    (0xFF16, 0x3F), // NR21
+    (0xFF17, 0x00), // NR22
+    (0xFF18, 0xFF), // NR23
+    (0xFF19, 0xBF), // NR24
+    (0xFF1A, 0x7F), // NR30
+    (0xFF1B, 0xFF), // NR31
+    (0xFF1C, 0x9F), // NR32
+    (0xFF1D, 0xFF), // NR33
+    (0xFF1E, 0xBF), // NR34
+    (0xFF20, 0xFF), // NR41
+    (0xFF21, 0x00), // NR42
+    (0xFF22, 0x00), // NR43
+    (0xFF23, 0xBF), // NR44
+    (0xFF24, 0x77), // NR50
+    (0xFF25, 0xF3), // NR51
+    (0xFF40, 0x91), // LCDC
+    (0xFF41, 0x00), // STAT, no interrupt source
+    (0xFF47, 0xFC), // BGP
+    (0xFF48, 0xFF), // OBP0
+    (0xFF49, 0xFF), // OBP1
+    (0xFFFF, 0x00), // IE
+];
+
+/// Title checksums of the Nintendo games the CGB boot ROM colors, the ones after
+/// `AMBIGUOUS_CHECKSUMS` are shared by several games and told apart by `FOURTH_LETTERS`
+#[rustfmt::skip]
+const TITLE_CHECKSUMS: [u8; 94] = [
+    0x00, 0x88, 0x16, 0x36, 0xD1, 0xDB, 0xF2, 0x3C, 0x8C, 0x92, 0x3D, 0x5C, 0x58, 0xC9, 0x3E, 0x70,
+    0x1D, 0x59, 0x69, 0x19, 0x35, 0xA8, 0x14, 0xAA, 0x75, 0x95, 0x99, 0x34, 0x6F, 0x15, 0xFF, 0x97,
+    0x4B, 0x90, 0x17, 0x10, 0x39, 0xF7, 0xF6, 0xA2, 0x49, 0x4E, 0x43, 0x68, 0xE0, 0x8B, 0xF0, 0xCE,
+    0x0C, 0x29, 0xE8, 0xB7, 0x86, 0x9A, 0x52, 0x01, 0x9D, 0x71, 0x9C, 0xBD, 0x5D, 0x6D, 0x67, 0x3F,
+    0x6B,
+    0xB3, 0x46, 0x28, 0xA5, 0xC6, 0xD3, 0x27, 0x61, 0x18, 0x66, 0x6A, 0xBF, 0x0D, 0xF4, 0xB3, 0x46,
+    0x28, 0xA5, 0xC6, 0xD3, 0x27, 0x61, 0x18, 0x66, 0x6A, 0xBF, 0x0D, 0xF4, 0xB3,
+];
+const AMBIGUOUS_CHECKSUMS: usize = 65;
+const FOURTH_LETTERS: &[u8; 29] = b"BEFAARBEKEK R-URAR INAILICE R";
+
+/// Index in `PALETTE_COMBINATIONS` for each of `TITLE_CHECKSUMS`
+#[rustfmt::skip]
+const CHECKSUM_PALETTES: [u8; 94] = [
+    0, 4, 5, 35, 34, 3, 31, 15, 10, 5, 19, 36, 7, 37, 30, 44,
+    21, 32, 31, 20, 5, 33, 13, 14, 5, 29, 5, 18, 9, 3, 2, 26,
+    25, 25, 41, 42, 26, 45, 42, 45, 36, 38, 26, 42, 30, 41, 34, 34,
+    5, 42, 6, 5, 33, 25, 42, 42, 40, 2, 16, 25, 42, 42, 5, 0,
+    39,
+    36, 22, 25, 6, 32, 12, 36, 11, 39, 18, 39, 24, 31, 50, 17, 46,
+    6, 27, 0, 47, 41, 41, 0, 0, 19, 34, 23, 18, 29,
+];
+
+/// Offsets in `COMPATIBILITY_COLORS` of the OBJ0, OBJ1 and BG palettes, 4 colors each.
+/// A few combinations start in the middle of a palette.
+#[rustfmt::skip]
+const PALETTE_COMBINATIONS: [[u8; 3]; 51] = [
+    [16, 16, 116], [72, 72, 72], [80, 80, 80], [96, 96, 96], [36, 36, 36], [0, 0, 0],
+    [108, 108, 108], [20, 20, 20], [48, 48, 48], [104, 104, 104], [64, 32, 32], [16, 112, 112],
+    [16, 8, 8], [12, 16, 16], [16, 116, 116], [112, 16, 112], [8, 68, 8], [64, 64, 32],
+    [16, 16, 28], [16, 16, 72], [16, 16, 80], [76, 76, 36], [15, 15, 44], [68, 68, 8],
+    [16, 16, 8], [16, 16, 12], [112, 112, 0], [12, 12, 0], [0, 0, 4], [72, 88, 72],
+    [80, 88, 80], [96, 88, 96], [64, 88, 32], [68, 16, 52], [111, 0, 56], [111, 16, 60],
+    [76, 88, 36], [64, 112, 40], [16, 92, 112], [68, 88, 8], [16, 0, 8], [16, 112, 12],
+    [112, 12, 0], [12, 112, 16], [84, 112, 16], [12, 112, 0], [100, 12, 112], [0, 112, 32],
+    [16, 12, 112], [112, 12, 24], [16, 112, 116],
+];
+
+/// RGB555 colors of the compatibility palettes, 4 per palette
+#[rustfmt::skip]
+const COMPATIBILITY_COLORS: [u16; 120] = [
+    0x7FFF, 0x32BF, 0x00D0, 0x0000,
+    0x639F, 0x4279, 0x15B0, 0x04CB,
+    0x7FFF, 0x6E31, 0x454A, 0x0000,
+    0x7FFF, 0x1BEF, 0x0200, 0x0000,
+    0x7FFF, 0x421F, 0x1CF2, 0x0000,
+    0x7FFF, 0x5294, 0x294A, 0x0000,
+    0x7FFF, 0x03FF, 0x012F, 0x0000,
+    0x7FFF, 0x03EF, 0x01D6, 0x0000,
+    0x7FFF, 0x42B5, 0x3DC8, 0x0000,
+    0x7E74, 0x03FF, 0x0180, 0x0000,
+    0x67FF, 0x77AC, 0x1A13, 0x2D6B,
+    0x7ED6, 0x4BFF, 0x2175, 0x0000,
+    0x53FF, 0x4A5F, 0x7E52, 0x0000,
+    0x4FFF, 0x7ED2, 0x3A4C, 0x1CE0,
+    0x03ED, 0x7FFF, 0x255F, 0x0000,
+    0x036A, 0x021F, 0x03FF, 0x7FFF,
+    0x7FFF, 0x01DF, 0x0112, 0x0000,
+    0x231F, 0x035F, 0x00F2, 0x0009,
+    0x7FFF, 0x03EA, 0x011F, 0x0000,
+    0x299F, 0x001A, 0x000C, 0x0000,
+    0x7FFF, 0x027F, 0x001F, 0x0000,
+    0x7FFF, 0x03E0, 0x0206, 0x0120,
+    0x7FFF, 0x7EEB, 0x001F, 0x7C00,
+    0x7FFF, 0x3FFF, 0x7E00, 0x001F,
+    0x7FFF, 0x03FF, 0x001F, 0x0000,
+    0x03FF, 0x001F, 0x000C, 0x0000,
+    0x7FFF, 0x033F, 0x0193, 0x0000,
+    0x0000, 0x4200, 0x037F, 0x7FFF,
+    0x7FFF, 0x7E8C, 0x7C00, 0x0000,
+    0x7FFF, 0x1BEF, 0x6180, 0x0000,
+];
+
+fn is_cgb_cartridge(rom: &[u8]) -> bool {
+    rom[CGB_FLAG] & 0x80 != 0
+}
+
+/// The DMG and MGB boot ROMs leave the flags of the header checksum verification
+fn checksum_flags(rom: &[u8]) -> u8 {
+    if rom[HEADER_CHECKSUM] == 0 {
+        0x80
+    } else {
+        0xB0
+    }
+}
+
+/// The CGB boot ROM sums the title of Nintendo games to pick their compatibility palette
+fn title_checksum(rom: &[u8]) -> u8 {
+    let nintendo = rom[OLD_LICENSEE] == 0x01
+        || (rom[OLD_LICENSEE] == 0x33 && rom[NEW_LICENSEE..NEW_LICENSEE + 2] == *b"01");
+    if !nintendo {
+        return 0;
+    }
+    rom[TITLE..TITLE + 16]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// BG, OBJ0 and OBJ1 palettes the CGB boot ROM picks for a DMG game
+pub fn compatibility_palettes(rom: &[u8]) -> [[u16; 4]; 3] {
+    let checksum = title_checksum(rom);
+    let index = TITLE_CHECKSUMS
+        .iter()
+        .enumerate()
+        .position(|(index, game)| {
+            *game == checksum
+                && (index < AMBIGUOUS_CHECKSUMS
+                    || FOURTH_LETTERS[index - AMBIGUOUS_CHECKSUMS] == rom[TITLE + 3])
+        })
+        .unwrap_or(0);
+    let [obj0, obj1, bg] = PALETTE_COMBINATIONS[CHECKSUM_PALETTES[index] as usize];
+    let palette = |offset: u8| {
+        let offset = offset as usize;
+        let mut colors = [0; 4];
+        colors.copy_from_slice(&COMPATIBILITY_COLORS[offset..offset + 4]);
+        colors
+    };
+    [palette(bg), palette(obj0), palette(obj1)]
+}
+
+/// Writes of `colors` to palette RAM through a specification and data register pair
+fn palette_writes(spec: u16, colors: &[u16]) -> impl Iterator<Item = (u16, u8)> + '_ {
+    let start = (spec, 0x80); // Index 0, auto increment
+    std::iter::once(start).chain(
+        colors
+            .iter()
+            .flat_map(move |color| color.to_le_bytes().map(|byte| (spec + 1, byte))),
+    )
+}
+
+/// CPU registers at the cartridge entry point
+pub fn registers(model: Model, rom: &[u8]) -> Registers {
+    // A, F, B, C, D, E, H, L
+    let values: [u8; 8] = match model {
+        Model::Dmg0 => [0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03],
+        Model::Dmg => [
+            0x01,
+            checksum_flags(rom),
+            0x00,
+            0x13,
+            0x00,
+            0xD8,
+            0x01,
+            0x4D,
+        ],
+        Model::Mgb => [
+            0xFF,
+            checksum_flags(rom),
+            0x00,
+            0x13,
+            0x00,
+            0xD8,
+            0x01,
+            0x4D,
+        ],
+        Model::Sgb => [0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60],
+        Model::Sgb2 => [0xFF, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60],
+        Model::Cgb | Model::Agb if is_cgb_cartridge(rom) => {
+            [0x11, 0x80, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D]
+        }
+        Model::Cgb | Model::Agb => {
+            let b = title_checksum(rom);
+            let hl = if b == 0x43 || b == 0x58 {
+                [0x99, 0x1A]
+            } else {
+                [0x00, 0x7C]
+            };
+            [0x11, 0x80, b, 0x00, 0x00, 0x08, hl[0], hl[1]]
+        }
+    };
+    let [a, f, b, c, d, e, h, l] = values;
+    let mut registers = Registers {
+        a,
+        b,
+        c,
+        d,
+        e,
+        f: Flags::from(f),
+        h,
+        l,
+        sp: 0xFFFE,
+        pc: ENTRY_POINT,
+    };
+    // The AGB boot ROM ends with an extra INC B
+    if model == Model::Agb {
+        registers.b = registers.b.wrapping_add(1);
+        registers.f = Flags {
+            zero: registers.b == 0,
+            subtract: false,
+            half_carry: registers.b & 0x0F == 0,
+            carry: false,
+        };
+    }
+    registers
+}
+
+/// VBlank line and dot of the LCD at the entry point, LY 0 being the end of the last line.
+/// The dots are approximate, only the line the boot ROM hands over in is known.
+pub fn lcd_position(model: Model) -> (u8, u32) {
+    match model {
+        // STAT reads $81
+        Model::Dmg0 => (0x91, 200),
+        // STAT reads $85, with the LY=LYC flag
+        _ => (0x00, 400),
+    }
+}
+
+/// Value of the 16 bits divider counter (DIV being its upper byte) at the entry point,
+/// it depends on how long the boot ROM ran
+pub fn div_counter(model: Model, rom: &[u8]) -> u16 {
+    match model {
+        Model::Dmg0 => 0x1830,
+        Model::Dmg | Model::Mgb => 0xABCC,
+        Model::Sgb | Model::Sgb2 => 0xD85C,
+        // The compatibility palette selection takes longer
+        Model::Cgb | Model::Agb if !is_cgb_cartridge(rom) => 0x267C,
+        Model::Cgb | Model::Agb => 0x1EA0,
+    }
+}
+
+/// I/O registers at the entry point, written in order through the devices handling them
+pub fn io_registers(model: Model, rom: &[u8]) -> Vec<(u16, u8)> {
+    let mut registers = IO_REGISTERS.to_vec();
+    let (sc, nr52) = match model {
+        Model::Cgb | Model::Agb => (0x7F, 0xF1),
+        Model::Sgb | Model::Sgb2 => (0x7E, 0xF0),
+        _ => (0x7E, 0xF1),
+    };
+    registers.push((0xFF02, sc));
+    registers.push((0xFF26, nr52));
+    if !model.is_cgb() {
+        return registers;
+    }
+    // Palettes are only accessible until KEY0 selects the DMG compatibility mode
+    if is_cgb_cartridge(rom) {
+        registers.extend(palette_writes(0xFF68, &[WHITE; 32]));
+        registers.push((0xFF4C, rom[CGB_FLAG]));
+    } else {
+        let [bg, obj0, obj1] = compatibility_palettes(rom);
+        registers.extend(palette_writes(0xFF68, &bg));
+        registers.extend(palette_writes(0xFF6A, &[obj0, obj1].concat()));
+        registers.push((0xFF4C, KEY0_DMG_COMPATIBILITY));
+    }
+    // Unmaps the boot ROM, locking KEY0
+    registers.push((0xFF50, 0x11));
+    registers
+}
+
+/// Row of a logo tile: the upper 4 bits of `bits`, each bit doubled
+fn logo_row(bits: u8) -> u8 {
+    (0..4).fold(0, |row, bit| {
+        let pixel = (bits >> (7 - bit)) & 1;
+        row << 2 | pixel << 1 | pixel
+    })
+}
+
+/// Draws the logo of the cartridge header in VRAM, like the DMG and SGB boot ROMs
+pub fn load_logo(model: Model, mmu: &mut Mmu, rom: &[u8]) {
+    if model.is_cgb() {
+        // VRAM is cleared before the cartridge starts
+        return;
+    }
+    let mut address = LOGO_TILES;
+    for byte in rom[LOGO..LOGO + LOGO_SIZE].iter() {
+        for nibble in [*byte, *byte << 4] {
+            let row = logo_row(nibble);
+            // Second bitplane left to 0
+            for _ in 0..2 {
+                mmu.write(address, row);
+                address += 2;
+            }
+        }
+    }
+    for row in REGISTERED {
+        mmu.write(address, row);
+        address += 2;
+    }
+    for tile in 0..12 {
+        mmu.write(LOGO_MAP_TOP + tile as u16, tile + 1);
+        mmu.write(LOGO_MAP_BOTTOM + tile as u16, tile + 13);
+    }
+    mmu.write(REGISTERED_MAP, REGISTERED_TILE);
+}
@@ -1,10 +1,11 @@
 use std::cell::RefMut;
 
-use super::instructions::{Instruction, Opcode, Timing, NOP};
+use super::instructions::{Timing, PREFIXED_INSTRUCTIONS, UNPREFIXED_INSTRUCTIONS};
 use super::interrupt::InterruptController;
 use super::registers::{Reg16, Reg8, Registers};
 use crate::memory::mmu::Mmu;
 use crate::util::bit_operations::*;
+use crate::util::state::{StateError, StateReader, StateWriter};
 
 pub struct Imem8;
 pub struct Imem16;
@@ -175,6 +176,8 @@ pub struct Cpu {
     pub registers: Registers,
     ime: bool,
     pub halted: bool,
+    /// Set by illegal opcodes, nothing runs anymore until a reset
+    pub locked: bool,
     mmu: Mmu,
 }
 
@@ -182,32 +185,48 @@ impl Cpu {
     pub fn new(mmu: Mmu) -> Cpu {
         Cpu {
             registers: Registers::new(),
-            ime: true,
+            ime: false,
             halted: false,
+            locked: false,
             mmu,
         }
     }
 
+    /// Saves the registers and the memory
+    pub fn save_state(&self, state: &mut StateWriter) {
+        let registers = &self.registers;
+        state.u16(registers.read_u16(Reg16::AF));
+        state.u16(registers.read_u16(Reg16::BC));
+        state.u16(registers.read_u16(Reg16::DE));
+        state.u16(registers.read_u16(Reg16::HL));
+        state.u16(registers.sp);
+        state.u16(registers.pc);
+        state.bool(self.ime);
+        state.bool(self.halted);
+        state.bool(self.locked);
+        self.mmu.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        for register in [Reg16::AF, Reg16::BC, Reg16::DE, Reg16::HL, Reg16::SP, Reg16::PC] {
+            let value = state.u16()?;
+            self.registers.write_u16(register, value);
+        }
+        self.ime = state.bool()?;
+        self.halted = state.bool()?;
+        self.locked = state.bool()?;
+        self.mmu.load_state(state)
+    }
+
     pub fn execute_instruction(&mut self) -> u8 {
-        if self.halted {
+        if self.halted || self.locked {
             return 4;
         }
 
         let opcode = self.fetch_u8();
-        let op = match opcode {
-            0xCB => Opcode::Prefixed(self.fetch_u8()),
-            _ => Opcode::Unprefixed(opcode),
-        };
-        let instruction= match Instruction::get_instruction(op) {
-            Some(instruction) => instruction,
-            None => {
-                eprintln!(
-                    "Unknown opcode 0x{:04X} at address 0x{:04X}",
-                    opcode,
-                    self.registers.pc - 1
-                );
-                &NOP
-            }
+        let instruction = match opcode {
+            0xCB => &PREFIXED_INSTRUCTIONS[self.fetch_u8() as usize],
+            _ => &UNPREFIXED_INSTRUCTIONS[opcode as usize],
         };
 
         #[cfg(feature="debug")]
@@ -226,11 +245,13 @@ impl Cpu {
     }
 
     pub fn handle_interrupts(&mut self, interrupt_controller: RefMut<'_, InterruptController>) -> u8 {
+        // Interrupts can't wake up a locked CPU
+        if self.locked {
+            return 0;
+        }
         // TODO: implement halt bug
-        if self.halted {
-            if let Some(_) = interrupt_controller.peek() {
-                self.halted = false;
-            }
+        if self.halted && interrupt_controller.peek().is_some() {
+            self.halted = false;
         }
         if !self.ime {
             return 0;
@@ -249,6 +270,18 @@ impl Cpu {
         self.ime = active;
     }
 
+    pub fn interrupts_enabled(&self) -> bool {
+        self.ime
+    }
+
+    pub fn mmu(&self) -> &Mmu {
+        &self.mmu
+    }
+
+    pub fn mmu_mut(&mut self) -> &mut Mmu {
+        &mut self.mmu
+    }
+
     #[allow(unused)]
     fn interrupt(&mut self, value: u8) {
         self.set_interrupts(false);
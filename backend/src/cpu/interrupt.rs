@@ -3,6 +3,7 @@ use std::{cell::RefCell, rc::Rc};
 use crate::{
     is_bit_set,
     memory::mmu::{MemoryHandler, MemoryRead, MemoryWrite, Mmu},
+    util::state::{StateError, StateReader, StateWriter},
 };
 
 const VBLANK: u8 = 0;
@@ -48,7 +49,7 @@ impl std::convert::From<u8> for Interrupts {
 impl std::convert::From<&Interrupts> for u8 {
     fn from(value: &Interrupts) -> Self {
         let mut res = 0;
-        res |= (value.vblank as u8) << 0;
+        res |= value.vblank as u8;
         res |= (value.lcd as u8) << 1;
         res |= (value.timer as u8) << 2;
         res |= (value.serial as u8) << 3;
@@ -61,6 +62,7 @@ pub struct InterruptRequest {
     request: Rc<RefCell<Interrupts>>,
 }
 
+#[allow(unused)]
 impl InterruptRequest {
     pub fn new(request: Rc<RefCell<Interrupts>>) -> InterruptRequest {
         Self { request }
@@ -124,6 +126,17 @@ impl InterruptController {
         InterruptRequest::new(self.flags.clone())
     }
 
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.enable.borrow().get());
+        state.u8(self.flags.borrow().get());
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.enable.borrow_mut().set(state.u8()?);
+        self.flags.borrow_mut().set(state.u8()?);
+        Ok(())
+    }
+
     pub fn peek(&self) -> Option<u8> {
         self.check(false)
     }
@@ -1,6 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod cpu;
-mod instructions;
+pub mod instructions;
 pub mod interrupt;
-mod registers;
+pub mod registers;
 mod operations;
 pub mod timer;
\ No newline at end of file
@@ -12,6 +12,13 @@ pub fn nop() -> Timing {
     Timing::Normal
 }
 
+pub fn illegal(cpu: &mut Cpu) -> Timing {
+    // PC stays on the opcode that locked up the CPU
+    cpu.registers.pc = cpu.registers.pc.wrapping_sub(1);
+    cpu.locked = true;
+    Timing::Normal
+}
+
 pub fn ld<T: UpperHex, D: Dst<T>, S: Src<T>>(cpu: &mut Cpu, dst: D, src: S) -> Timing {
     let val: T = src.read(cpu);
     dst.write(cpu, val);
@@ -194,7 +201,6 @@ pub fn add16<D: Dst<u16> + Src<u16> + Copy, S: Src<u16>>(cpu: &mut Cpu, dest: D,
 
     cpu.registers.f.carry = carry;
     cpu.registers.f.half_carry = is_bit_set!((a ^ b ^ result), 12);
-;
     cpu.registers.f.subtract = false;
 
     Timing::Normal
@@ -329,7 +335,7 @@ pub fn sbc<D: Dst<u8> + Src<u8> + Copy, S: Src<u8>>(cpu: &mut Cpu, dest: D, src:
     let c = cpu.registers.f.carry as u8;
     let (result, carry) = a.overflowing_sub(b);
     let (result, carry_c) = result.overflowing_sub(c);
-    dest.write(cpu, result as u8);
+    dest.write(cpu, result);
 
     cpu.registers.f.zero = result == 0;
     cpu.registers.f.subtract = true;
@@ -497,7 +503,7 @@ pub fn srl<L: Dst<u8> + Src<u8> + Copy>(cpu: &mut Cpu, loc: L) -> Timing {
 
 pub fn swap<L: Dst<u8> + Src<u8> + Copy>(cpu: &mut Cpu, loc: L) -> Timing {
     let value = loc.read(cpu);
-    let result = (value << 4) | (value >> 4);
+    let result = value.rotate_left(4);
     loc.write(cpu, result);
 
     cpu.registers.f.zero = result == 0;
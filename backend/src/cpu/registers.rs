@@ -85,18 +85,19 @@ pub struct Registers {
 }
 
 impl Registers {
+    /// Power on state, the boot ROM initializes everything
     pub fn new() -> Registers {
         Registers {
-            a: 0x11,
+            a: 0x00,
             b: 0x00,
             c: 0x00,
-            d: 0xFF,
-            e: 0x56,
-            f: (Flags::from(0x80)),
+            d: 0x00,
+            e: 0x00,
+            f: (Flags::from(0x00)),
             h: 0x00,
-            l: 0x0D,
-            pc: 0x100,
-            sp: 0xfffe,
+            l: 0x00,
+            pc: 0x0000,
+            sp: 0x0000,
         }
     }
 
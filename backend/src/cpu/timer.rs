@@ -1,8 +1,9 @@
 use super::interrupt::InterruptRequest;
+use crate::util::state::{StateError, StateReader, StateWriter};
 use crate::{is_bit_set, memory::mmu::{MemoryHandler, MemoryRead, MemoryWrite}};
 const TAC_ENABLE: u8 = 2;
-const DIV_CLOCKS: u16 = 256;
-const CLOCKS: [u16; 4] = [
+// TIMA ticks on the falling edges of a bit of the divider counter, so every this many clocks
+const CLOCKS: [u32; 4] = [
     1024,
     16,
     64,
@@ -11,12 +12,10 @@ const CLOCKS: [u16; 4] = [
 
 pub struct Timer {
     interrupt_request: InterruptRequest,
-    div: u8,  // address 0xFF04
+    counter: u16, // DIV, address 0xFF04, is its upper byte
     tima: u8, // address 0xFF05
     tma: u8,  // address 0xFF06
     tac: u8,  // address 0xFF07
-    div_clocks: u16,
-    tima_clocks: u32,
     overflowed: bool,
 }
 
@@ -24,22 +23,38 @@ impl Timer {
     pub fn new(interrupt_request: InterruptRequest) -> Self {
         Self {
             interrupt_request,
-            div: 0,
+            counter: 0,
             tima: 0,
             tma: 0,
             tac: 0,
-            div_clocks: 0,
-            tima_clocks: 0,
             overflowed: false,
         }
     }
+    /// Sets the internal 16 bits counter, DIV being its upper byte
+    pub fn set_div_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u16(self.counter);
+        state.u8(self.tima);
+        state.u8(self.tma);
+        state.u8(self.tac);
+        state.bool(self.overflowed);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.counter = state.u16()?;
+        self.tima = state.u8()?;
+        self.tma = state.u8()?;
+        self.tac = state.u8()?;
+        self.overflowed = state.bool()?;
+        Ok(())
+    }
+
     pub fn step(&mut self, elapsed_cycles: u16) {
-        // Handle divider timer
-        self.div_clocks += elapsed_cycles;
-        if self.div_clocks >= DIV_CLOCKS {
-            self.div = self.div.wrapping_add(1);
-            self.div_clocks -= DIV_CLOCKS;
-        }
+        let old = self.counter as u32;
+        self.counter = self.counter.wrapping_add(elapsed_cycles);
         if self.overflowed {
             self.tima = self.tma;
             self.overflowed = false;
@@ -50,21 +65,29 @@ impl Timer {
             return;
         }
 
-        self.tima_clocks += elapsed_cycles as u32;
-        let frequency = CLOCKS[(self.tac & 0b011) as usize] as u32;
-        while self.tima_clocks >= frequency{
-            let (tima, overflowed) = self.tima.overflowing_add(1);
-            self.tima = tima;
-            self.overflowed |= overflowed;
-            self.tima_clocks -= frequency;
+        // Falling edges of the selected bit between the old and new counter values
+        let frequency = self.frequency();
+        let ticks = (old + elapsed_cycles as u32) / frequency - old / frequency;
+        for _ in 0..ticks {
+            self.increment_tima();
         }
     }
+
+    fn frequency(&self) -> u32 {
+        CLOCKS[(self.tac & 0b011) as usize]
+    }
+
+    fn increment_tima(&mut self) {
+        let (tima, overflowed) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        self.overflowed |= overflowed;
+    }
 }
 
 impl MemoryHandler for Timer {
     fn read(&self, _: &crate::memory::mmu::Mmu, address: u16) -> crate::memory::mmu::MemoryRead {
         match address {
-            0xFF04 => MemoryRead::Replace(self.div),
+            0xFF04 => MemoryRead::Replace((self.counter >> 8) as u8),
             0xFF05 => MemoryRead::Replace(self.tima),
             0xFF06 => MemoryRead::Replace(self.tma),
             0xFF07 => MemoryRead::Replace(self.tac),
@@ -80,18 +103,18 @@ impl MemoryHandler for Timer {
     ) -> crate::memory::mmu::MemoryWrite {
         match address {
             0xFF04 => {
-                self.div = 0;
+                // Resetting the counter is a falling edge if the selected bit was set
+                if is_bit_set!(self.tac, TAC_ENABLE)
+                    && self.counter as u32 & (self.frequency() / 2) != 0
+                {
+                    self.increment_tima();
+                }
+                self.counter = 0;
                 return MemoryWrite::Replace(0);
             },
             0xFF05 => self.tima = value,
             0xFF06 => self.tma = value,
-            0xFF07 => {
-                let old = self.tac;
-                self.tac = value & 0b111;
-                if is_bit_set!(self.tac, TAC_ENABLE) && is_bit_set!(old, TAC_ENABLE) {
-                    self.tima_clocks = 0;
-                }
-            },
+            0xFF07 => self.tac = value & 0b111,
             _ => {}
         }
         MemoryWrite::Pass
@@ -0,0 +1,423 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::cpu::registers::{Reg16, Reg8};
+use crate::memory::mbc::BankInfo;
+use crate::memory::mmu::{MemoryHandler, MemoryRead, MemoryWrite, Mmu};
+use crate::system::System;
+
+use super::disassembler::{self, DisassembledInstruction};
+use super::ram_search::{Filter, RamSearch, Size};
+
+// Instructions executed between two checks of the interrupt flag while running
+const INTERRUPT_POLL_INTERVAL: u64 = 4096;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Register {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+}
+
+impl Register {
+    pub fn is_16bit(&self) -> bool {
+        matches!(
+            self,
+            Register::AF | Register::BC | Register::DE | Register::HL | Register::SP | Register::PC
+        )
+    }
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "a" => Ok(Register::A),
+            "f" => Ok(Register::F),
+            "b" => Ok(Register::B),
+            "c" => Ok(Register::C),
+            "d" => Ok(Register::D),
+            "e" => Ok(Register::E),
+            "h" => Ok(Register::H),
+            "l" => Ok(Register::L),
+            "af" => Ok(Register::AF),
+            "bc" => Ok(Register::BC),
+            "de" => Ok(Register::DE),
+            "hl" => Ok(Register::HL),
+            "sp" => Ok(Register::SP),
+            "pc" => Ok(Register::PC),
+            _ => Err(format!("Unknown register '{}'", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchKind {
+    fn matches(&self, access: Access) -> bool {
+        match self {
+            WatchKind::Read => access == Access::Read,
+            WatchKind::Write => access == Access::Write,
+            WatchKind::ReadWrite => true,
+        }
+    }
+}
+
+impl fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchKind::Read => write!(f, "r"),
+            WatchKind::Write => write!(f, "w"),
+            WatchKind::ReadWrite => write!(f, "rw"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct WatchHit {
+    pub address: u16,
+    pub access: Access,
+    /// Written value, `None` for reads
+    pub value: Option<u8>,
+    /// Address of the instruction that triggered the watchpoint
+    pub pc: u16,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum StopReason {
+    Step,
+    Breakpoint(u16),
+    Watchpoint(WatchHit),
+    StepLimit,
+    /// The interrupt flag was set while running
+    Interrupted,
+}
+
+pub struct InterruptState {
+    pub ime: bool,
+    pub halted: bool,
+    /// Locked up by an illegal opcode
+    pub locked: bool,
+    pub enable: u8,
+    pub flags: u8,
+}
+
+#[derive(Default)]
+struct WatchState {
+    watches: BTreeMap<u16, WatchKind>,
+    hits: Vec<(u16, Access, Option<u8>)>,
+    armed: bool,
+}
+
+impl WatchState {
+    fn record(&mut self, address: u16, access: Access, value: Option<u8>) {
+        if !self.armed {
+            return;
+        }
+        if let Some(kind) = self.watches.get(&address) {
+            if kind.matches(access) {
+                self.hits.push((address, access, value));
+            }
+        }
+    }
+}
+
+/// Memory spy registered in front of the other handlers of a watched address
+struct WatchSpy(Rc<RefCell<WatchState>>);
+
+impl MemoryHandler for WatchSpy {
+    fn read(&self, _: &Mmu, address: u16) -> MemoryRead {
+        self.0.borrow_mut().record(address, Access::Read, None);
+        MemoryRead::Pass
+    }
+
+    fn write(&mut self, _: &Mmu, address: u16, value: u8) -> MemoryWrite {
+        self.0
+            .borrow_mut()
+            .record(address, Access::Write, Some(value));
+        MemoryWrite::Pass
+    }
+}
+
+pub struct Debugger {
+    system: System,
+    breakpoints: BTreeSet<u16>,
+    watch_state: Rc<RefCell<WatchState>>,
+    // Addresses that already have a spy installed in the MMU
+    spied: BTreeSet<u16>,
+    search: Option<RamSearch>,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Debugger {
+    pub fn new(system: System) -> Self {
+        Self {
+            system,
+            breakpoints: BTreeSet::new(),
+            watch_state: Rc::new(RefCell::new(WatchState {
+                armed: true,
+                ..Default::default()
+            })),
+            spied: BTreeSet::new(),
+            search: None,
+            interrupt: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn system(&self) -> &System {
+        &self.system
+    }
+
+    pub fn system_mut(&mut self) -> &mut System {
+        &mut self.system
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &u16> {
+        self.breakpoints.iter()
+    }
+
+    pub fn add_watchpoint(&mut self, address: u16, kind: WatchKind) {
+        if self.spied.insert(address) {
+            let spy = WatchSpy(self.watch_state.clone());
+            self.system
+                .cpu_mut()
+                .mmu_mut()
+                .insert_handler((address, address), spy);
+        }
+        self.watch_state.borrow_mut().watches.insert(address, kind);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: u16) -> bool {
+        // The spy stays in the MMU but ignores addresses that are not watched anymore
+        self.watch_state
+            .borrow_mut()
+            .watches
+            .remove(&address)
+            .is_some()
+    }
+
+    pub fn watchpoints(&self) -> Vec<(u16, WatchKind)> {
+        self.watch_state
+            .borrow()
+            .watches
+            .iter()
+            .map(|(address, kind)| (*address, *kind))
+            .collect()
+    }
+
+    /// Executes a single instruction (and any interrupt dispatch that follows it)
+    pub fn step(&mut self) -> StopReason {
+        let pc = self.pc();
+        self.watch_state.borrow_mut().hits.clear();
+        self.system.step();
+
+        let hit = self.watch_state.borrow_mut().hits.drain(..).next();
+        match hit {
+            Some((address, access, value)) => StopReason::Watchpoint(WatchHit {
+                address,
+                access,
+                value,
+                pc,
+            }),
+            None => StopReason::Step,
+        }
+    }
+
+    /// Steps over `CALL` and `RST` instructions, otherwise behaves like `step`
+    pub fn step_over(&mut self) -> StopReason {
+        let pc = self.pc();
+        let instruction = self.disassemble(pc);
+        if !(instruction.text.starts_with("CALL") || instruction.text.starts_with("RST")) {
+            return self.step();
+        }
+        let return_address = pc.wrapping_add(instruction.size());
+        let sp = self.read_register(Register::SP);
+        match self.step() {
+            StopReason::Step => {}
+            reason => return reason,
+        }
+        loop {
+            let pc = self.pc();
+            if pc == return_address && self.read_register(Register::SP) >= sp {
+                return StopReason::Step;
+            }
+            if self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+            match self.step() {
+                StopReason::Step => {}
+                reason => return reason,
+            }
+        }
+    }
+
+    /// Flag stopping `resume` when set, from a signal handler or another thread
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, `limit` instructions were executed or
+    /// the interrupt flag is set. The instruction at the current PC is always executed, even
+    /// if it has a breakpoint.
+    pub fn resume(&mut self, limit: Option<u64>) -> StopReason {
+        // An interrupt while stopped is not for this run
+        self.interrupt.store(false, Ordering::Relaxed);
+        let mut executed: u64 = 0;
+        loop {
+            if limit.is_some_and(|limit| executed >= limit) {
+                return StopReason::StepLimit;
+            }
+            if executed.is_multiple_of(INTERRUPT_POLL_INTERVAL)
+                && self.interrupt.swap(false, Ordering::Relaxed)
+            {
+                return StopReason::Interrupted;
+            }
+            match self.step() {
+                StopReason::Step => {}
+                reason => return reason,
+            }
+            executed += 1;
+            let pc = self.pc();
+            if self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+        }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.system.cpu().registers.pc
+    }
+
+    pub fn read_register(&self, register: Register) -> u16 {
+        let registers = &self.system.cpu().registers;
+        match register {
+            Register::A => registers.read_u8(Reg8::A) as u16,
+            Register::F => registers.read_u8(Reg8::F) as u16,
+            Register::B => registers.read_u8(Reg8::B) as u16,
+            Register::C => registers.read_u8(Reg8::C) as u16,
+            Register::D => registers.read_u8(Reg8::D) as u16,
+            Register::E => registers.read_u8(Reg8::E) as u16,
+            Register::H => registers.read_u8(Reg8::H) as u16,
+            Register::L => registers.read_u8(Reg8::L) as u16,
+            Register::AF => registers.read_u16(Reg16::AF),
+            Register::BC => registers.read_u16(Reg16::BC),
+            Register::DE => registers.read_u16(Reg16::DE),
+            Register::HL => registers.read_u16(Reg16::HL),
+            Register::SP => registers.read_u16(Reg16::SP),
+            Register::PC => registers.read_u16(Reg16::PC),
+        }
+    }
+
+    pub fn write_register(&mut self, register: Register, value: u16) {
+        let registers = &mut self.system.cpu_mut().registers;
+        let byte = value as u8;
+        match register {
+            Register::A => registers.write_u8(Reg8::A, byte),
+            Register::F => registers.write_u8(Reg8::F, byte),
+            Register::B => registers.write_u8(Reg8::B, byte),
+            Register::C => registers.write_u8(Reg8::C, byte),
+            Register::D => registers.write_u8(Reg8::D, byte),
+            Register::E => registers.write_u8(Reg8::E, byte),
+            Register::H => registers.write_u8(Reg8::H, byte),
+            Register::L => registers.write_u8(Reg8::L, byte),
+            Register::AF => registers.write_u16(Reg16::AF, value),
+            Register::BC => registers.write_u16(Reg16::BC, value),
+            Register::DE => registers.write_u16(Reg16::DE, value),
+            Register::HL => registers.write_u16(Reg16::HL, value),
+            Register::SP => registers.write_u16(Reg16::SP, value),
+            Register::PC => registers.write_u16(Reg16::PC, value),
+        }
+    }
+
+    /// Reads memory through the MMU without triggering watchpoints
+    pub fn peek(&self, address: u16) -> u8 {
+        self.watch_state.borrow_mut().armed = false;
+        let value = self.system.cpu().mmu().read(address);
+        self.watch_state.borrow_mut().armed = true;
+        value
+    }
+
+    /// Writes memory through the MMU without triggering watchpoints
+    pub fn poke(&mut self, address: u16, value: u8) {
+        self.watch_state.borrow_mut().armed = false;
+        self.system.cpu_mut().mmu_mut().write(address, value);
+        self.watch_state.borrow_mut().armed = true;
+    }
+
+    /// Decodes the instruction at `address` without triggering watchpoints
+    pub fn disassemble(&self, address: u16) -> DisassembledInstruction {
+        let bytes: Vec<u8> = (0..3)
+            .map(|offset| self.peek(address.wrapping_add(offset)))
+            .collect();
+        disassembler::decode(&bytes, address)
+    }
+
+    pub fn interrupt_state(&self) -> InterruptState {
+        let cpu = self.system.cpu();
+        InterruptState {
+            ime: cpu.interrupts_enabled(),
+            halted: cpu.halted,
+            locked: cpu.locked,
+            enable: self.peek(0xFFFF),
+            flags: self.peek(0xFF0F),
+        }
+    }
+
+    pub fn bank_info(&self) -> BankInfo {
+        self.system.bank_info()
+    }
+
+    /// Starts a RAM search from the current memory, replacing the previous one
+    pub fn start_search(&mut self, size: Size) -> usize {
+        let search = RamSearch::new(&self.system, size);
+        let candidates = search.candidates().len();
+        self.search = Some(search);
+        candidates
+    }
+
+    /// Narrows down the RAM search, returns how many candidates are left if one is started
+    pub fn filter_search(&mut self, filter: Filter) -> Option<usize> {
+        let search = self.search.as_mut()?;
+        Some(search.filter(&self.system, filter))
+    }
+
+    pub fn ram_search(&self) -> Option<&RamSearch> {
+        self.search.as_ref()
+    }
+}
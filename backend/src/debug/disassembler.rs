@@ -0,0 +1,172 @@
+use std::fmt;
+
+use crate::cpu::instructions::{
+    FlagEffect, FlagEffects, PREFIXED_INSTRUCTIONS, UNPREFIXED_INSTRUCTIONS,
+};
+use crate::memory::mmu::Mmu;
+use crate::util::bit_operations::bytes_to_word;
+
+const ROM_BANK_SIZE: usize = 0x4000;
+
+pub struct DisassembledInstruction {
+    pub address: u16,
+    /// Raw encoding, its length is the instruction size
+    pub bytes: Vec<u8>,
+    pub text: String,
+    /// Effects on Z, N, H and C like opcode tables write them: `-` unaffected, `0` reset,
+    /// `1` set, or the flag letter when it depends on the result
+    pub flags: String,
+}
+
+impl DisassembledInstruction {
+    pub fn size(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+}
+
+impl fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+fn signed(offset: i8) -> String {
+    if offset < 0 {
+        format!("-${:02X}", offset.unsigned_abs())
+    } else {
+        format!("${:02X}", offset)
+    }
+}
+
+fn flag_effects(flags: &FlagEffects) -> String {
+    [
+        (flags.zero, 'Z'),
+        (flags.subtract, 'N'),
+        (flags.half_carry, 'H'),
+        (flags.carry, 'C'),
+    ]
+    .iter()
+    .map(|(effect, letter)| match effect {
+        FlagEffect::Unaffected => '-',
+        FlagEffect::Reset => '0',
+        FlagEffect::Set => '1',
+        FlagEffect::Affected => *letter,
+    })
+    .collect()
+}
+
+fn format_operands(mnemonic: &str, bytes: &[u8], address: u16) -> String {
+    let byte = || bytes[1];
+    let word = || bytes_to_word(bytes[2], bytes[1]);
+
+    if mnemonic.contains("d16") {
+        mnemonic.replace("d16", &format!("${:04X}", word()))
+    } else if mnemonic.contains("a16") {
+        mnemonic.replace("a16", &format!("${:04X}", word()))
+    } else if mnemonic.contains("d8") {
+        mnemonic.replace("d8", &format!("${:02X}", byte()))
+    } else if mnemonic.contains("a8") {
+        mnemonic.replace("a8", &format!("$FF{:02X}", byte()))
+    } else if mnemonic.starts_with("JR") {
+        let offset = byte() as i8;
+        let target = address.wrapping_add(2).wrapping_add(offset as u16);
+        mnemonic.replace("r8", &format!("${:04X}", target))
+    } else if mnemonic.contains("+r8") {
+        let offset = signed(byte() as i8);
+        match offset.strip_prefix('-') {
+            Some(_) => mnemonic.replace("+r8", &offset),
+            None => mnemonic.replace("r8", &offset),
+        }
+    } else if mnemonic.contains("r8") {
+        mnemonic.replace("r8", &signed(byte() as i8))
+    } else {
+        mnemonic.to_string()
+    }
+}
+
+/// Decodes the instruction at the start of `bytes`, `address` being where it is mapped
+/// (used to resolve relative jumps). Unknown or truncated encodings are decoded as `DB`.
+pub fn decode(bytes: &[u8], address: u16) -> DisassembledInstruction {
+    let data_byte = |bytes: &[u8]| DisassembledInstruction {
+        address,
+        bytes: bytes[..1].to_vec(),
+        text: format!("DB ${:02X}", bytes[0]),
+        flags: String::from("----"),
+    };
+    if bytes.is_empty() {
+        return DisassembledInstruction {
+            address,
+            bytes: Vec::new(),
+            text: String::new(),
+            flags: String::new(),
+        };
+    }
+
+    let instruction = match bytes[0] {
+        0xCB if bytes.len() > 1 => &PREFIXED_INSTRUCTIONS[bytes[1] as usize],
+        0xCB => return data_byte(bytes),
+        opcode => &UNPREFIXED_INSTRUCTIONS[opcode as usize],
+    };
+    if instruction.illegal {
+        return data_byte(bytes);
+    }
+    let size = instruction.size as usize;
+    if bytes.len() < size {
+        return data_byte(bytes);
+    }
+
+    DisassembledInstruction {
+        address,
+        bytes: bytes[..size].to_vec(),
+        text: format_operands(instruction.mnemonic, bytes, address),
+        flags: flag_effects(&instruction.flags),
+    }
+}
+
+/// Decodes the instruction at `address` as currently mapped by the MMU
+pub(crate) fn disassemble(mmu: &Mmu, address: u16) -> DisassembledInstruction {
+    let bytes: Vec<u8> = (0..3)
+        .map(|offset| mmu.read(address.wrapping_add(offset)))
+        .collect();
+    decode(&bytes, address)
+}
+
+/// Linear sweep over a raw byte slice
+pub struct Disassembler<'a> {
+    bytes: &'a [u8],
+    base_address: u16,
+    offset: usize,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(bytes: &'a [u8], base_address: u16) -> Self {
+        Self {
+            bytes,
+            base_address,
+            offset: 0,
+        }
+    }
+
+    /// Disassembles a whole ROM bank, mapped where the cartridge would expose it
+    /// (0x0000 for bank 0, 0x4000 for the others)
+    pub fn rom_bank(rom: &'a [u8], bank: usize) -> Self {
+        let start = (bank * ROM_BANK_SIZE).min(rom.len());
+        let end = (start + ROM_BANK_SIZE).min(rom.len());
+        let base_address = if bank == 0 { 0x0000 } else { 0x4000 };
+        Self::new(&rom[start..end], base_address)
+    }
+}
+
+impl Iterator for Disassembler<'_> {
+    type Item = DisassembledInstruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        let address = self.base_address.wrapping_add(self.offset as u16);
+        let instruction = decode(&self.bytes[self.offset..], address);
+        self.offset += instruction.bytes.len();
+        Some(instruction)
+    }
+}
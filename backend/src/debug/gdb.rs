@@ -0,0 +1,424 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use super::debugger::{Access, Debugger, Register, StopReason, WatchKind};
+
+/// Target description sent to the client, registers are listed in `g` packet order
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <architecture>sm83</architecture>
+  <feature name="org.gnu.gdb.sm83.core">
+    <reg name="af" bitsize="16" type="uint16" regnum="0"/>
+    <reg name="bc" bitsize="16" type="uint16"/>
+    <reg name="de" bitsize="16" type="uint16"/>
+    <reg name="hl" bitsize="16" type="uint16"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+const REGISTERS: [Register; 6] = [
+    Register::AF,
+    Register::BC,
+    Register::DE,
+    Register::HL,
+    Register::SP,
+    Register::PC,
+];
+
+const SIGTRAP: u8 = 5;
+const SIGINT: u8 = 2;
+// Instructions executed between two checks for a client interrupt while running
+const POLL_INTERVAL: u64 = 4096;
+
+enum Packet {
+    Command(String),
+    Interrupt,
+}
+
+struct Connection {
+    stream: TcpStream,
+    buffer: VecDeque<u8>,
+    no_ack: bool,
+}
+
+impl Connection {
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0u8; 1024];
+        let read = self.stream.read(&mut chunk)?;
+        self.buffer.extend(&chunk[..read]);
+        Ok(read > 0)
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.buffer.is_empty() && !self.fill()? {
+            return Ok(None);
+        }
+        Ok(self.buffer.pop_front())
+    }
+
+    /// Returns `None` once the client closed the connection
+    fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            match self.next_byte()? {
+                None => return Ok(None),
+                Some(0x03) => return Ok(Some(Packet::Interrupt)),
+                Some(b'$') => {}
+                // Acks and line noise
+                Some(_) => continue,
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.next_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+            let mut checksum = [0u8; 2];
+            for digit in checksum.iter_mut() {
+                *digit = match self.next_byte()? {
+                    Some(byte) => byte,
+                    None => return Ok(None),
+                };
+            }
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+            let actual = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            if !self.no_ack {
+                if expected != Some(actual) {
+                    self.stream.write_all(b"-")?;
+                    continue;
+                }
+                self.stream.write_all(b"+")?;
+            }
+            return Ok(Some(Packet::Command(
+                String::from_utf8_lossy(&data).into_owned(),
+            )));
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.push(b'$');
+        for &byte in data.as_bytes() {
+            match byte {
+                b'$' | b'#' | b'}' | b'*' => packet.extend([b'}', byte ^ 0x20]),
+                _ => packet.push(byte),
+            }
+        }
+        let checksum = packet[1..]
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        packet.extend(format!("#{:02x}", checksum).as_bytes());
+        self.stream.write_all(&packet)?;
+        self.stream.flush()
+    }
+
+    /// Non-blocking check for a Ctrl-C sent by the client while the target runs
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let result = self.fill();
+        self.stream.set_nonblocking(false)?;
+        match result {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        match self.buffer.iter().position(|&byte| byte == 0x03) {
+            Some(index) => {
+                self.buffer.remove(index);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+fn parse_hex(value: &str) -> Option<u16> {
+    u16::from_str_radix(value, 16).ok()
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Works on bytes, the packet may hold anything including multibyte characters
+fn decode_hex_bytes(value: &[u8]) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    value
+        .chunks(2)
+        .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn encode_register(value: u16) -> String {
+    // Registers are transferred in target (little endian) byte order
+    format!("{:02x}{:02x}", value as u8, (value >> 8) as u8)
+}
+
+fn decode_register(value: &[u8]) -> Option<u16> {
+    let bytes = decode_hex_bytes(value)?;
+    match bytes.as_slice() {
+        [lsb, msb] => Some(((*msb as u16) << 8) | *lsb as u16),
+        _ => None,
+    }
+}
+
+fn stop_reply(reason: StopReason) -> String {
+    match reason {
+        StopReason::Breakpoint(_) => format!("T{:02x}swbreak:;", SIGTRAP),
+        StopReason::Watchpoint(hit) => {
+            let kind = match hit.access {
+                Access::Write => "watch",
+                Access::Read => "rwatch",
+            };
+            format!("T{:02x}{}:{:04x};", SIGTRAP, kind, hit.address)
+        }
+        StopReason::Step | StopReason::StepLimit => format!("S{:02x}", SIGTRAP),
+        StopReason::Interrupted => format!("S{:02x}", SIGINT),
+    }
+}
+
+struct Session<'a> {
+    connection: Connection,
+    debugger: &'a mut Debugger,
+    last_stop: String,
+}
+
+impl Session<'_> {
+    /// Handles one command, returns `Ok(false)` when the session is over
+    fn handle(&mut self, command: &str) -> io::Result<bool> {
+        let reply = match command.chars().next() {
+            Some('?') => self.last_stop.clone(),
+            Some('g') => REGISTERS
+                .iter()
+                .map(|register| encode_register(self.debugger.read_register(*register)))
+                .collect(),
+            Some('G') => self.write_registers(&command[1..]),
+            Some('p') => match parse_hex(&command[1..]).and_then(|n| REGISTERS.get(n as usize)) {
+                Some(register) => encode_register(self.debugger.read_register(*register)),
+                None => "E01".to_string(),
+            },
+            Some('P') => self.write_register(&command[1..]),
+            Some('m') => self.read_memory(&command[1..]),
+            Some('M') => self.write_memory(&command[1..]),
+            Some('Z') | Some('z') => self.breakpoint(command),
+            Some('c') => {
+                if let Some(address) = parse_hex(&command[1..]) {
+                    self.debugger.write_register(Register::PC, address);
+                }
+                self.resume()?
+            }
+            Some('s') => {
+                if let Some(address) = parse_hex(&command[1..]) {
+                    self.debugger.write_register(Register::PC, address);
+                }
+                self.last_stop = stop_reply(self.debugger.step());
+                self.last_stop.clone()
+            }
+            Some('H') => "OK".to_string(),
+            Some('k') => return Ok(false),
+            Some('D') => {
+                self.connection.send("OK")?;
+                return Ok(false);
+            }
+            Some('q') | Some('Q') => self.query(command),
+            // Unsupported packets get an empty reply
+            _ => String::new(),
+        };
+        self.connection.send(&reply)?;
+        Ok(true)
+    }
+
+    fn query(&mut self, command: &str) -> String {
+        if command.starts_with("qSupported") {
+            "PacketSize=4000;qXfer:features:read+;swbreak+;QStartNoAckMode+".to_string()
+        } else if command == "QStartNoAckMode" {
+            // Acknowledged one last time by the packet reader before switching off
+            self.connection.no_ack = true;
+            "OK".to_string()
+        } else if let Some(args) = command.strip_prefix("qXfer:features:read:target.xml:") {
+            let range = args.split_once(',').and_then(|(offset, length)| {
+                Some((
+                    usize::from_str_radix(offset, 16).ok()?,
+                    usize::from_str_radix(length, 16).ok()?,
+                ))
+            });
+            match range {
+                Some((offset, length)) => {
+                    let start = offset.min(TARGET_XML.len());
+                    let end = (start + length).min(TARGET_XML.len());
+                    let more = if end < TARGET_XML.len() { 'm' } else { 'l' };
+                    format!("{}{}", more, &TARGET_XML[start..end])
+                }
+                None => "E01".to_string(),
+            }
+        } else if command == "qAttached" {
+            "1".to_string()
+        } else if command == "qC" {
+            "QC1".to_string()
+        } else if command == "qfThreadInfo" {
+            "m1".to_string()
+        } else if command == "qsThreadInfo" {
+            "l".to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn write_registers(&mut self, data: &str) -> String {
+        if data.len() < REGISTERS.len() * 4 {
+            return "E01".to_string();
+        }
+        for (register, value) in REGISTERS.iter().zip(data.as_bytes().chunks(4)) {
+            match decode_register(value) {
+                Some(value) => self.debugger.write_register(*register, value),
+                None => return "E01".to_string(),
+            }
+        }
+        "OK".to_string()
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let parsed = args.split_once('=').and_then(|(n, value)| {
+            Some((
+                REGISTERS.get(parse_hex(n)? as usize)?,
+                decode_register(value.as_bytes())?,
+            ))
+        });
+        match parsed {
+            Some((register, value)) => {
+                self.debugger.write_register(*register, value);
+                "OK".to_string()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn read_memory(&mut self, args: &str) -> String {
+        let range = args
+            .split_once(',')
+            .and_then(|(address, length)| Some((parse_hex(address)?, parse_hex(length)?)));
+        match range {
+            Some((address, length)) => (0..length)
+                .map(|i| format!("{:02x}", self.debugger.peek(address.wrapping_add(i))))
+                .collect(),
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let parsed = args.split_once(':').and_then(|(range, data)| {
+            let (address, length) = range.split_once(',')?;
+            let data = decode_hex_bytes(data.as_bytes())?;
+            if data.len() != parse_hex(length)? as usize {
+                return None;
+            }
+            Some((parse_hex(address)?, data))
+        });
+        match parsed {
+            Some((address, data)) => {
+                for (i, byte) in data.into_iter().enumerate() {
+                    self.debugger.poke(address.wrapping_add(i as u16), byte);
+                }
+                "OK".to_string()
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn breakpoint(&mut self, command: &str) -> String {
+        let insert = command.starts_with('Z');
+        let mut args = command[1..].split(',');
+        let (kind, address) = match (args.next(), args.next().and_then(parse_hex)) {
+            (Some(kind), Some(address)) => (kind, address),
+            _ => return "E01".to_string(),
+        };
+        let watch = match kind {
+            "0" | "1" => {
+                if insert {
+                    self.debugger.add_breakpoint(address);
+                } else {
+                    self.debugger.remove_breakpoint(address);
+                }
+                return "OK".to_string();
+            }
+            "2" => WatchKind::Write,
+            "3" => WatchKind::Read,
+            "4" => WatchKind::ReadWrite,
+            _ => return String::new(),
+        };
+        if insert {
+            self.debugger.add_watchpoint(address, watch);
+        } else {
+            self.debugger.remove_watchpoint(address);
+        }
+        "OK".to_string()
+    }
+
+    fn resume(&mut self) -> io::Result<String> {
+        loop {
+            match self.debugger.resume(Some(POLL_INTERVAL)) {
+                StopReason::StepLimit => {
+                    if self.connection.interrupted()? {
+                        self.last_stop = format!("S{:02x}", SIGINT);
+                        return Ok(self.last_stop.clone());
+                    }
+                }
+                reason => {
+                    self.last_stop = stop_reply(reason);
+                    return Ok(self.last_stop.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Waits for a single GDB remote client on `listener` and serves it until it detaches, kills
+/// the target or disconnects. Bind the listener to port 0 to let the system pick a free port.
+pub fn serve(debugger: &mut Debugger, listener: TcpListener) -> io::Result<()> {
+    println!(
+        "Waiting for a GDB connection on localhost:{}",
+        listener.local_addr()?.port()
+    );
+    let (stream, address) = listener.accept()?;
+    println!("GDB client connected from {}", address);
+    stream.set_nodelay(true)?;
+
+    let mut session = Session {
+        connection: Connection {
+            stream,
+            buffer: VecDeque::new(),
+            no_ack: false,
+        },
+        debugger,
+        last_stop: format!("S{:02x}", SIGTRAP),
+    };
+    loop {
+        match session.connection.read_packet()? {
+            None => return Ok(()),
+            // The target is already stopped
+            Some(Packet::Interrupt) => {
+                let reply = session.last_stop.clone();
+                session.connection.send(&reply)?;
+            }
+            Some(Packet::Command(command)) => {
+                if !session.handle(&command)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -1 +1,8 @@
-pub mod blaarg_spy;
\ No newline at end of file
+#[cfg(feature = "blaarg")]
+pub mod blaarg_spy;
+pub mod debugger;
+pub mod disassembler;
+pub mod gdb;
+pub mod ram_search;
+pub mod repl;
+pub mod trace;
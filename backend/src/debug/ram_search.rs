@@ -0,0 +1,183 @@
+//! Narrows down the RAM locations holding a value by comparing successive snapshots, to find
+//! addresses for cheats and test fixtures
+
+use std::fmt;
+
+use crate::system::System;
+
+const WRAM: u16 = 0xC000;
+const WRAM_SIZE: usize = 0x2000;
+const HRAM: u16 = 0xFF80;
+const HRAM_SIZE: usize = 0x7F;
+const CART_RAM: u16 = 0xA000;
+const CART_RAM_BANK_SIZE: usize = 0x2000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Region {
+    Wram,
+    Hram,
+    /// All the banks of the cartridge RAM, whichever is mapped
+    CartRam,
+}
+
+const REGIONS: [Region; 3] = [Region::Wram, Region::Hram, Region::CartRam];
+
+impl Region {
+    fn read(self, system: &System) -> Vec<u8> {
+        let mmu = system.cpu().mmu();
+        match self {
+            Region::Wram => (0..WRAM_SIZE as u16)
+                .map(|offset| mmu.read_backing(WRAM + offset))
+                .collect(),
+            Region::Hram => (0..HRAM_SIZE as u16)
+                .map(|offset| mmu.read_backing(HRAM + offset))
+                .collect(),
+            Region::CartRam => mmu.cart_ram().to_vec(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub region: Region,
+    pub offset: usize,
+}
+
+impl Location {
+    /// Address of the location when mapped
+    pub fn address(&self) -> u16 {
+        match self.region {
+            Region::Wram => WRAM + self.offset as u16,
+            Region::Hram => HRAM + self.offset as u16,
+            Region::CartRam => CART_RAM + (self.offset % CART_RAM_BANK_SIZE) as u16,
+        }
+    }
+
+    /// Cartridge RAM bank of the location, if it is in cartridge RAM
+    pub fn bank(&self) -> Option<usize> {
+        (self.region == Region::CartRam).then_some(self.offset / CART_RAM_BANK_SIZE)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${:04X}", self.address())?;
+        if let Some(bank) = self.bank() {
+            write!(f, " (bank {})", bank)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Size {
+    Byte,
+    /// Little endian 16-bit values
+    Word,
+}
+
+impl Size {
+    fn bytes(self) -> usize {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+        }
+    }
+}
+
+/// Comparison of the current value of a location with its value at the previous search
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+    /// The current value is this one
+    Value(u16),
+}
+
+impl Filter {
+    fn matches(self, previous: u16, value: u16) -> bool {
+        match self {
+            Filter::Equal => value == previous,
+            Filter::Changed => value != previous,
+            Filter::Increased => value > previous,
+            Filter::Decreased => value < previous,
+            Filter::Value(expected) => value == expected,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub location: Location,
+    /// At the last search
+    pub value: u16,
+    /// At the search before, the same as `value` before the first filter
+    pub previous: u16,
+}
+
+pub struct RamSearch {
+    size: Size,
+    candidates: Vec<Candidate>,
+}
+
+impl RamSearch {
+    /// Snapshots the RAM of `system`, every location is a candidate
+    pub fn new(system: &System, size: Size) -> Self {
+        let mut candidates = Vec::new();
+        for region in REGIONS {
+            let memory = region.read(system);
+            for offset in 0..(memory.len() + 1).saturating_sub(size.bytes()) {
+                let location = Location { region, offset };
+                let value = Self::value(&memory, offset, size);
+                candidates.push(Candidate {
+                    location,
+                    value,
+                    previous: value,
+                });
+            }
+        }
+        Self { size, candidates }
+    }
+
+    fn value(memory: &[u8], offset: usize, size: Size) -> u16 {
+        match size {
+            Size::Byte => memory[offset] as u16,
+            Size::Word => u16::from_le_bytes([memory[offset], memory[offset + 1]]),
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// Keeps the candidates whose current value passes `filter`, returns how many are left
+    pub fn filter(&mut self, system: &System, filter: Filter) -> usize {
+        let memories = REGIONS.map(|region| region.read(system));
+        let size = self.size;
+        self.candidates.retain_mut(|candidate| {
+            let region = REGIONS
+                .iter()
+                .position(|&region| region == candidate.location.region)
+                .unwrap();
+            // The cartridge RAM is the only region whose size can change, on a new game
+            let memory = &memories[region];
+            if candidate.location.offset + size.bytes() > memory.len() {
+                return false;
+            }
+            let value = Self::value(memory, candidate.location.offset, size);
+            if !filter.matches(candidate.value, value) {
+                return false;
+            }
+            candidate.previous = candidate.value;
+            candidate.value = value;
+            true
+        });
+        self.candidates.len()
+    }
+}
@@ -0,0 +1,418 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+use super::debugger::{Access, Debugger, Register, StopReason, WatchKind};
+use super::ram_search::{Filter, Size};
+
+const HELP: &str = "Commands (addresses and values are hexadecimal, counts are decimal):
+  break|b <addr>             set a breakpoint
+  delete <addr>              remove a breakpoint
+  watch|w <addr> [r|w|rw]    set a watchpoint (default: w)
+  unwatch <addr>             remove a watchpoint
+  info|i                     list breakpoints and watchpoints
+  step|s [count]             execute instructions
+  next|n                     step over CALL/RST
+  continue|c [limit]         run until a breakpoint or watchpoint (Ctrl-C interrupts)
+  regs|r                     show registers
+  x <addr> [len]             dump memory (default: 64 bytes)
+  disasm|dis [addr] [count]  disassemble (default: PC, 10 instructions)
+  set <reg> <value>          set a register (a, f, b, c, ..., af, bc, de, hl, sp, pc)
+  bank                       show cartridge banking state
+  int                        show interrupt state
+  trace <file>|off           log executed instructions (Gameboy Doctor format)
+  search new [8|16]          snapshot WRAM, HRAM and cartridge RAM (default: 8-bit)
+  search eq|ne|gt|lt         keep values equal, changed, increased, decreased since last search
+  search value <value>       keep locations holding value
+  search list [count]        show the candidates left (default: 20)
+  help|h                     show this help
+  quit|q                     exit the debugger
+An empty line repeats the last step, next or continue command.";
+
+// Commands an empty line repeats, the others may have side effects like truncating a file
+const REPEATABLE: [&str; 6] = ["step", "s", "next", "n", "continue", "c"];
+
+fn parse_hex(value: &str) -> Result<u16, String> {
+    let digits = value
+        .strip_prefix('$')
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid hexadecimal value '{}'", value))
+}
+
+fn parse_count(value: Option<&str>, default: u64) -> Result<u64, String> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid count '{}'", value)),
+        None => Ok(default),
+    }
+}
+
+fn required<'a>(value: Option<&'a str>, name: &str) -> Result<&'a str, String> {
+    value.ok_or_else(|| format!("Missing argument <{}>", name))
+}
+
+fn print_location<W: Write>(debugger: &Debugger, output: &mut W) -> io::Result<()> {
+    let pc = debugger.pc();
+    writeln!(output, "${:04X}: {}", pc, debugger.disassemble(pc))
+}
+
+fn print_stop<W: Write>(debugger: &Debugger, reason: StopReason, output: &mut W) -> io::Result<()> {
+    match reason {
+        StopReason::Step => {}
+        StopReason::StepLimit => writeln!(output, "Stopped: instruction limit reached")?,
+        StopReason::Interrupted => writeln!(output, "Interrupted")?,
+        StopReason::Breakpoint(address) => writeln!(output, "Breakpoint at ${:04X}", address)?,
+        StopReason::Watchpoint(hit) => match (hit.access, hit.value) {
+            (Access::Write, Some(value)) => writeln!(
+                output,
+                "Watchpoint: write ${:02X} to ${:04X} by instruction at ${:04X}",
+                value, hit.address, hit.pc
+            )?,
+            _ => writeln!(
+                output,
+                "Watchpoint: read from ${:04X} by instruction at ${:04X}",
+                hit.address, hit.pc
+            )?,
+        },
+    }
+    print_location(debugger, output)
+}
+
+fn print_registers<W: Write>(debugger: &Debugger, output: &mut W) -> io::Result<()> {
+    let f = debugger.read_register(Register::F) as u8;
+    writeln!(
+        output,
+        "AF: ${:04X}  BC: ${:04X}  DE: ${:04X}  HL: ${:04X}  SP: ${:04X}  PC: ${:04X}",
+        debugger.read_register(Register::AF),
+        debugger.read_register(Register::BC),
+        debugger.read_register(Register::DE),
+        debugger.read_register(Register::HL),
+        debugger.read_register(Register::SP),
+        debugger.read_register(Register::PC),
+    )?;
+    writeln!(
+        output,
+        "Flags: {}{}{}{}",
+        if f & 0x80 != 0 { 'Z' } else { '-' },
+        if f & 0x40 != 0 { 'N' } else { '-' },
+        if f & 0x20 != 0 { 'H' } else { '-' },
+        if f & 0x10 != 0 { 'C' } else { '-' },
+    )
+}
+
+fn dump_memory<W: Write>(
+    debugger: &Debugger,
+    address: u16,
+    length: u64,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut offset = 0;
+    while offset < length {
+        let line_address = address.wrapping_add(offset as u16);
+        let line_length = (length - offset).min(16);
+        let bytes: Vec<u8> = (0..line_length)
+            .map(|i| debugger.peek(line_address.wrapping_add(i as u16)))
+            .collect();
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let ascii: String = bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(
+            output,
+            "${:04X}: {:<47}  {}",
+            line_address,
+            hex.join(" "),
+            ascii
+        )?;
+        offset += line_length;
+    }
+    Ok(())
+}
+
+enum CommandError {
+    Usage(String),
+    Io(io::Error),
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Usage(message)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(e: io::Error) -> Self {
+        CommandError::Io(e)
+    }
+}
+
+fn search<W: Write>(
+    debugger: &mut Debugger,
+    filter: &str,
+    arg: Option<&str>,
+    output: &mut W,
+) -> Result<(), CommandError> {
+    let filter = match filter {
+        "new" => {
+            let size = match arg.unwrap_or("8") {
+                "8" => Size::Byte,
+                "16" => Size::Word,
+                other => return Err(format!("Invalid search size '{}'", other).into()),
+            };
+            let candidates = debugger.start_search(size);
+            writeln!(output, "{} candidates", candidates)?;
+            return Ok(());
+        }
+        "list" => {
+            let search = debugger
+                .ram_search()
+                .ok_or_else(|| String::from("No search started, use 'search new'"))?;
+            let digits = match search.size() {
+                Size::Byte => 2,
+                Size::Word => 4,
+            };
+            let count = parse_count(arg, 20)? as usize;
+            for candidate in search.candidates().iter().take(count) {
+                writeln!(
+                    output,
+                    "{}: ${:0digits$X} (was ${:0digits$X})",
+                    candidate.location,
+                    candidate.value,
+                    candidate.previous,
+                    digits = digits
+                )?;
+            }
+            if search.candidates().len() > count {
+                writeln!(output, "... {} more", search.candidates().len() - count)?;
+            }
+            return Ok(());
+        }
+        "eq" => Filter::Equal,
+        "ne" => Filter::Changed,
+        "gt" => Filter::Increased,
+        "lt" => Filter::Decreased,
+        "value" => {
+            let value = parse_hex(required(arg, "value")?)?;
+            let byte = debugger
+                .ram_search()
+                .is_some_and(|search| search.size() == Size::Byte);
+            if byte && value > 0xFF {
+                return Err(format!("Value ${:X} does not fit in an 8-bit search", value).into());
+            }
+            Filter::Value(value)
+        }
+        other => return Err(format!("Invalid search filter '{}'", other).into()),
+    };
+    let candidates = debugger
+        .filter_search(filter)
+        .ok_or_else(|| String::from("No search started, use 'search new'"))?;
+    writeln!(output, "{} candidates", candidates)?;
+    Ok(())
+}
+
+fn execute<W: Write>(
+    debugger: &mut Debugger,
+    command: &str,
+    arg1: Option<&str>,
+    arg2: Option<&str>,
+    output: &mut W,
+) -> Result<(), CommandError> {
+    match command {
+        "break" | "b" => {
+            let address = parse_hex(required(arg1, "addr")?)?;
+            debugger.add_breakpoint(address);
+            writeln!(output, "Breakpoint set at ${:04X}", address)?;
+        }
+        "delete" => {
+            let address = parse_hex(required(arg1, "addr")?)?;
+            if !debugger.remove_breakpoint(address) {
+                return Err(format!("No breakpoint at ${:04X}", address).into());
+            }
+        }
+        "watch" | "w" => {
+            let address = parse_hex(required(arg1, "addr")?)?;
+            let kind = match arg2.unwrap_or("w") {
+                "r" => WatchKind::Read,
+                "w" => WatchKind::Write,
+                "rw" => WatchKind::ReadWrite,
+                other => return Err(format!("Invalid watchpoint kind '{}'", other).into()),
+            };
+            debugger.add_watchpoint(address, kind);
+            writeln!(output, "Watchpoint ({}) set at ${:04X}", kind, address)?;
+        }
+        "unwatch" => {
+            let address = parse_hex(required(arg1, "addr")?)?;
+            if !debugger.remove_watchpoint(address) {
+                return Err(format!("No watchpoint at ${:04X}", address).into());
+            }
+        }
+        "info" | "i" => {
+            for address in debugger.breakpoints() {
+                writeln!(output, "Breakpoint ${:04X}", address)?;
+            }
+            for (address, kind) in debugger.watchpoints() {
+                writeln!(output, "Watchpoint ${:04X} ({})", address, kind)?;
+            }
+        }
+        "step" | "s" => {
+            let mut reason = StopReason::Step;
+            for _ in 0..parse_count(arg1, 1)? {
+                reason = debugger.step();
+                if !matches!(reason, StopReason::Step) {
+                    break;
+                }
+            }
+            print_stop(debugger, reason, output)?;
+        }
+        "next" | "n" => {
+            let reason = debugger.step_over();
+            print_stop(debugger, reason, output)?;
+        }
+        "continue" | "c" => {
+            let limit = match arg1 {
+                Some(limit) => Some(parse_count(Some(limit), 0)?),
+                None => None,
+            };
+            let reason = debugger.resume(limit);
+            print_stop(debugger, reason, output)?;
+        }
+        "regs" | "r" => print_registers(debugger, output)?,
+        "x" => {
+            let address = parse_hex(required(arg1, "addr")?)?;
+            let length = parse_count(arg2, 64)?;
+            dump_memory(debugger, address, length, output)?;
+        }
+        "disasm" | "dis" => {
+            let mut address = match arg1 {
+                Some(address) => parse_hex(address)?,
+                None => debugger.pc(),
+            };
+            for _ in 0..parse_count(arg2, 10)? {
+                let instruction = debugger.disassemble(address);
+                let marker = if address == debugger.pc() { '>' } else { ' ' };
+                let bytes: Vec<String> = instruction
+                    .bytes
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect();
+                writeln!(
+                    output,
+                    "{} ${:04X}: {:<8}  {}",
+                    marker,
+                    address,
+                    bytes.join(" "),
+                    instruction
+                )?;
+                address = address.wrapping_add(instruction.size());
+            }
+        }
+        "set" => {
+            let register: Register = required(arg1, "reg")?.parse()?;
+            let value = parse_hex(required(arg2, "value")?)?;
+            if !register.is_16bit() && value > 0xFF {
+                return Err(format!("Value ${:X} does not fit in an 8-bit register", value).into());
+            }
+            debugger.write_register(register, value);
+        }
+        "bank" => {
+            let info = debugger.bank_info();
+            writeln!(
+                output,
+                "MBC: {}  ROM bank: {}  RAM bank: {} ({})  Boot ROM: {}",
+                info.mbc,
+                info.rom_bank,
+                info.ram_bank,
+                if info.ram_enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                },
+                if info.boot_rom_mapped {
+                    "mapped"
+                } else {
+                    "unmapped"
+                },
+            )?;
+        }
+        "int" => {
+            let state = debugger.interrupt_state();
+            writeln!(
+                output,
+                "IME: {}  HALT: {}  LOCKED: {}  IE: ${:02X}  IF: ${:02X}",
+                state.ime as u8,
+                state.halted as u8,
+                state.locked as u8,
+                state.enable,
+                state.flags
+            )?;
+        }
+        "trace" => match required(arg1, "file")? {
+            "off" => {
+                debugger.system_mut().set_trace(None);
+                writeln!(output, "Trace disabled")?;
+            }
+            path => {
+                let file = File::create(path)
+                    .map_err(|e| format!("Unable to create {}: {}", path, e))?;
+                debugger
+                    .system_mut()
+                    .set_trace(Some(Box::new(BufWriter::new(file))));
+                writeln!(output, "Tracing to {}", path)?;
+            }
+        },
+        "search" => search(debugger, required(arg1, "filter")?, arg2, output)?,
+        "help" | "h" => writeln!(output, "{}", HELP)?,
+        _ => return Err(format!("Unknown command '{}', type 'help' for a list", command).into()),
+    }
+    Ok(())
+}
+
+/// Runs the debugger command loop until `quit` or the end of `input`
+pub fn run<R: BufRead, W: Write>(
+    debugger: &mut Debugger,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    print_location(debugger, &mut output)?;
+    let mut last_command = String::new();
+    let mut lines = input.lines();
+    loop {
+        write!(output, "(gb) ")?;
+        output.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        let line = match line.trim() {
+            "" => last_command.clone(),
+            line => line.to_string(),
+        };
+        let mut args = line.split_whitespace();
+        let command = match args.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        last_command = if REPEATABLE.contains(&command) {
+            line.clone()
+        } else {
+            String::new()
+        };
+        if command == "quit" || command == "q" {
+            return Ok(());
+        }
+        match execute(debugger, command, args.next(), args.next(), &mut output) {
+            Ok(()) => {}
+            Err(CommandError::Usage(message)) => writeln!(output, "Error: {}", message)?,
+            Err(CommandError::Io(e)) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Write};
+
+use crate::cpu::cpu::Cpu;
+use crate::cpu::registers::{Reg16, Reg8};
+
+/// Writes one line per executed instruction in the Gameboy Doctor format:
+/// `A:00 F:00 B:00 C:00 D:00 E:00 H:00 L:00 SP:0000 PC:0000 PCMEM:00,00,00,00`,
+/// the state being logged before the instruction executes.
+pub struct Tracer {
+    output: Box<dyn Write>,
+}
+
+impl Tracer {
+    pub fn new(output: Box<dyn Write>) -> Self {
+        Self { output }
+    }
+
+    pub(crate) fn log(&mut self, cpu: &Cpu) -> io::Result<()> {
+        let registers = &cpu.registers;
+        let pc = registers.read_u16(Reg16::PC);
+        let mmu = cpu.mmu();
+        writeln!(
+            self.output,
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            registers.read_u8(Reg8::A),
+            registers.read_u8(Reg8::F),
+            registers.read_u8(Reg8::B),
+            registers.read_u8(Reg8::C),
+            registers.read_u8(Reg8::D),
+            registers.read_u8(Reg8::E),
+            registers.read_u8(Reg8::H),
+            registers.read_u8(Reg8::L),
+            registers.read_u16(Reg16::SP),
+            pc,
+            mmu.read_backing(pc),
+            mmu.read_backing(pc.wrapping_add(1)),
+            mmu.read_backing(pc.wrapping_add(2)),
+            mmu.read_backing(pc.wrapping_add(3)),
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
@@ -1,11 +1,574 @@
 use crate::cpu::interrupt::InterruptRequest;
+use crate::is_bit_set;
+use crate::memory::mmu::{Backing, MemoryHandler, MemoryRead, MemoryWrite, Mmu, VRAM_BANK_1};
+use crate::util::state::{StateError, StateReader, StateWriter};
 
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const DOTS_PER_LINE: u32 = 456;
+const OAM_SCAN_DOTS: u32 = 80;
+const TRANSFER_DOTS: u32 = 172;
+const LINES: u8 = 154;
+// LY only reads 153 at the start of the last line, then already 0
+const LAST_LINE_LY_DOTS: u32 = 4;
+const SPRITES_PER_LINE: usize = 10;
+
+// LCDC bits
+const BG_ENABLE: u8 = 0;
+const OBJ_ENABLE: u8 = 1;
+const OBJ_SIZE: u8 = 2;
+const BG_TILE_MAP: u8 = 3;
+const TILE_DATA: u8 = 4;
+const WINDOW_ENABLE: u8 = 5;
+const WINDOW_TILE_MAP: u8 = 6;
+const LCD_ENABLE: u8 = 7;
+
+// STAT bits
+const HBLANK_INTERRUPT: u8 = 3;
+const VBLANK_INTERRUPT: u8 = 4;
+const OAM_INTERRUPT: u8 = 5;
+const LYC_INTERRUPT: u8 = 6;
+
+// OAM attribute bits
+const OBJ_PALETTE: u8 = 4;
+const X_FLIP: u8 = 5;
+const Y_FLIP: u8 = 6;
+const BG_PRIORITY: u8 = 7;
+const CGB_OBJ_PALETTE: u8 = 0b111;
+
+// KEY0 bits
+const DMG_COMPATIBILITY: u8 = 2;
+// Palette specification bits
+const AUTO_INCREMENT: u8 = 7;
+const PALETTE_INDEX: u8 = 0x3F;
+/// BG palettes are followed by the OBJ palettes in palette RAM
+const OBJ_PALETTES: usize = 8;
+const PALETTE_RAM_SIZE: usize = 128;
+
+/// RGB values of the 4 DMG shades, from lightest to darkest
+pub(crate) const DMG_COLORS: [[u8; 3]; 4] = [
+    [0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x00, 0x00, 0x00],
+];
+
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum Mode {
-    OAM,
-    
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    Transfer = 3,
+}
+
+struct Sprite {
+    x: i16,
+    y: i16,
+    tile: u8,
+    attributes: u8,
 }
 
+/// Scanline renderer: each line is drawn in one go at the end of its pixel transfer
 pub struct Ppu {
     interrupt_request: InterruptRequest,
     clock: u32,
-}
\ No newline at end of file
+    mode: Mode,
+    lcdc: u8,
+    // Interrupt enable bits of STAT
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    dma: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    // Line of the window to draw next, only advances when the window is visible
+    window_line: u8,
+    // STAT interrupts are requested on rising edges of the combined sources
+    stat_line: bool,
+    dma_request: Option<u8>,
+    framebuffer: Vec<u8>,
+    frames: u64,
+    // CGB hardware, which colors DMG games with its palettes
+    cgb: bool,
+    // KEY0, written by the boot ROM for DMG games and locked once it is unmapped
+    dmg_compatibility: bool,
+    key0_locked: bool,
+    vram_bank: u8,
+    bcps: u8,
+    ocps: u8,
+    // RGB555 colors, 4 per palette
+    palette_ram: [u8; PALETTE_RAM_SIZE],
+}
+
+impl Ppu {
+    pub fn new(interrupt_request: InterruptRequest, cgb: bool) -> Self {
+        Self {
+            interrupt_request,
+            clock: 0,
+            mode: Mode::HBlank,
+            lcdc: 0,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            dma: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            window_line: 0,
+            stat_line: false,
+            dma_request: None,
+            framebuffer: vec![0xFF; SCREEN_WIDTH * SCREEN_HEIGHT * 3],
+            frames: 0,
+            cgb,
+            dmg_compatibility: false,
+            key0_locked: false,
+            vram_bank: 0,
+            bcps: 0,
+            ocps: 0,
+            palette_ram: [0xFF; PALETTE_RAM_SIZE],
+        }
+    }
+
+    /// RGB888 pixels of the last frame, row by row
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    /// Number of frames completed since power on
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u32(self.clock);
+        state.u8(self.mode as u8);
+        for register in [
+            self.lcdc, self.stat, self.scy, self.scx, self.ly, self.lyc, self.dma, self.bgp,
+            self.obp0, self.obp1, self.wy, self.wx, self.window_line,
+        ] {
+            state.u8(register);
+        }
+        state.bool(self.stat_line);
+        state.bool(self.dma_request.is_some());
+        state.u8(self.dma_request.unwrap_or(0));
+        state.bytes(&self.framebuffer);
+        state.u64(self.frames);
+        state.bool(self.dmg_compatibility);
+        state.bool(self.key0_locked);
+        state.u8(self.vram_bank);
+        state.u8(self.bcps);
+        state.u8(self.ocps);
+        state.bytes(&self.palette_ram);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.clock = state.u32()?;
+        self.mode = match state.u8()? {
+            0 => Mode::HBlank,
+            1 => Mode::VBlank,
+            2 => Mode::OamScan,
+            3 => Mode::Transfer,
+            _ => return Err(StateError::NotAState),
+        };
+        for register in [
+            &mut self.lcdc, &mut self.stat, &mut self.scy, &mut self.scx, &mut self.ly,
+            &mut self.lyc, &mut self.dma, &mut self.bgp, &mut self.obp0, &mut self.obp1,
+            &mut self.wy, &mut self.wx, &mut self.window_line,
+        ] {
+            *register = state.u8()?;
+        }
+        self.stat_line = state.bool()?;
+        let dma_pending = state.bool()?;
+        let dma_source = state.u8()?;
+        self.dma_request = dma_pending.then_some(dma_source);
+        let length = self.framebuffer.len();
+        self.framebuffer.copy_from_slice(state.bytes(length)?);
+        self.frames = state.u64()?;
+        self.dmg_compatibility = state.bool()?;
+        self.key0_locked = state.bool()?;
+        self.vram_bank = state.u8()?;
+        self.bcps = state.u8()?;
+        self.ocps = state.u8()?;
+        self.palette_ram
+            .copy_from_slice(state.bytes(PALETTE_RAM_SIZE)?);
+        Ok(())
+    }
+
+    /// Puts the LCD `dots` into VBlank line `ly`, LY 0 standing for the end of the last line,
+    /// with DMA left at $FF, as the boot ROM leaves them
+    pub fn set_boot_state(&mut self, ly: u8, dots: u32) {
+        self.mode = Mode::VBlank;
+        self.ly = ly;
+        self.clock = dots;
+        self.dma = 0xFF;
+        self.update_stat_interrupt();
+    }
+
+    /// Source page of an OAM DMA started since the last call
+    pub fn take_dma(&mut self) -> Option<u8> {
+        self.dma_request.take()
+    }
+
+    #[inline]
+    pub fn step(&mut self, elapsed_cycles: u16, mmu: &Mmu) {
+        if !is_bit_set!(self.lcdc, LCD_ENABLE) {
+            return;
+        }
+        self.clock += elapsed_cycles as u32;
+        loop {
+            match self.mode {
+                Mode::OamScan if self.clock >= OAM_SCAN_DOTS => self.mode = Mode::Transfer,
+                Mode::Transfer if self.clock >= OAM_SCAN_DOTS + TRANSFER_DOTS => {
+                    self.render_line(mmu);
+                    self.mode = Mode::HBlank;
+                }
+                Mode::HBlank if self.clock >= DOTS_PER_LINE => {
+                    self.clock -= DOTS_PER_LINE;
+                    self.ly += 1;
+                    if self.ly as usize == SCREEN_HEIGHT {
+                        self.mode = Mode::VBlank;
+                        self.frames += 1;
+                        self.interrupt_request.vblank(true);
+                    } else {
+                        self.mode = Mode::OamScan;
+                    }
+                }
+                Mode::VBlank if self.ly == LINES - 1 && self.clock >= LAST_LINE_LY_DOTS => {
+                    self.ly = 0;
+                }
+                Mode::VBlank if self.clock >= DOTS_PER_LINE => {
+                    self.clock -= DOTS_PER_LINE;
+                    if self.ly == 0 {
+                        self.window_line = 0;
+                        self.mode = Mode::OamScan;
+                    } else {
+                        self.ly += 1;
+                    }
+                }
+                _ => break,
+            }
+            self.update_stat_interrupt();
+        }
+    }
+
+    fn update_stat_interrupt(&mut self) {
+        let line = (is_bit_set!(self.stat, LYC_INTERRUPT) && self.ly == self.lyc)
+            || match self.mode {
+                Mode::HBlank => is_bit_set!(self.stat, HBLANK_INTERRUPT),
+                Mode::VBlank => is_bit_set!(self.stat, VBLANK_INTERRUPT),
+                Mode::OamScan => is_bit_set!(self.stat, OAM_INTERRUPT),
+                Mode::Transfer => false,
+            };
+        if line && !self.stat_line {
+            self.interrupt_request.lcd(true);
+        }
+        self.stat_line = line;
+    }
+
+    fn read_stat(&self) -> u8 {
+        let mode = if is_bit_set!(self.lcdc, LCD_ENABLE) {
+            self.mode as u8
+        } else {
+            Mode::HBlank as u8
+        };
+        0x80 | self.stat | ((self.ly == self.lyc) as u8) << 2 | mode
+    }
+
+    fn write_lcdc(&mut self, value: u8) {
+        let was_enabled = is_bit_set!(self.lcdc, LCD_ENABLE);
+        self.lcdc = value;
+        match (was_enabled, is_bit_set!(value, LCD_ENABLE)) {
+            (true, false) => {
+                self.ly = 0;
+                self.clock = 0;
+                self.window_line = 0;
+                self.mode = Mode::HBlank;
+            }
+            (false, true) => {
+                self.mode = Mode::OamScan;
+                self.update_stat_interrupt();
+            }
+            _ => {}
+        }
+    }
+
+    /// CGB registers are only usable by CGB games
+    fn cgb_mode(&self) -> bool {
+        self.cgb && !self.dmg_compatibility
+    }
+
+    fn write_vbk(&mut self, mmu: &Mmu, value: u8) {
+        self.vram_bank = value & 0x01;
+        let backing = if self.vram_bank == 0 {
+            Backing::Memory(0x8000)
+        } else {
+            Backing::Memory(VRAM_BANK_1)
+        };
+        mmu.map((0x8000, 0x9FFF), backing);
+    }
+
+    /// Palette RAM access through a specification register (BCPS/OCPS) and its data register
+    fn read_palette(&self, spec: u8, base: usize) -> u8 {
+        self.palette_ram[base + (spec & PALETTE_INDEX) as usize]
+    }
+
+    fn write_palette(&mut self, spec: u8, base: usize, value: u8) -> u8 {
+        self.palette_ram[base + (spec & PALETTE_INDEX) as usize] = value;
+        if is_bit_set!(spec, AUTO_INCREMENT) {
+            spec & !PALETTE_INDEX | (spec.wrapping_add(1) & PALETTE_INDEX)
+        } else {
+            spec
+        }
+    }
+
+    /// RGB values of `shade` in CGB `palette` (BG palettes first, then OBJ palettes)
+    fn cgb_color(&self, palette: u8, shade: u8) -> [u8; 3] {
+        let index = palette as usize * 8 + shade as usize * 2;
+        let color = u16::from_le_bytes([self.palette_ram[index], self.palette_ram[index + 1]]);
+        [0, 5, 10].map(|shift| {
+            let component = ((color >> shift) & 0x1F) as u8;
+            component << 3 | component >> 2
+        })
+    }
+
+    /// Color index (0-3) of a pixel of the tile referenced in `map` at (`x`, `y`) in pixels
+    fn tile_map_pixel(&self, mmu: &Mmu, map: u16, x: u8, y: u8) -> u8 {
+        let tile = mmu.vram(0, map + (y as u16 / 8) * 32 + x as u16 / 8);
+        let address = if is_bit_set!(self.lcdc, TILE_DATA) {
+            0x8000 + tile as u16 * 16
+        } else {
+            0x9000u16.wrapping_add((tile as i8 as i16 * 16) as u16)
+        };
+        tile_pixel(mmu, address, x % 8, y % 8)
+    }
+
+    fn render_line(&mut self, mmu: &Mmu) {
+        let ly = self.ly;
+        let mut bg_colors = [0u8; SCREEN_WIDTH];
+        let mut shades = [0u8; SCREEN_WIDTH];
+        // CGB palette of each pixel
+        let mut palettes = [0u8; SCREEN_WIDTH];
+
+        if is_bit_set!(self.lcdc, BG_ENABLE) {
+            let bg_map = if is_bit_set!(self.lcdc, BG_TILE_MAP) {
+                0x9C00
+            } else {
+                0x9800
+            };
+            let window_map = if is_bit_set!(self.lcdc, WINDOW_TILE_MAP) {
+                0x9C00
+            } else {
+                0x9800
+            };
+            let window_visible =
+                is_bit_set!(self.lcdc, WINDOW_ENABLE) && self.wy <= ly && self.wx <= 166;
+            for x in 0..SCREEN_WIDTH {
+                let color = if window_visible && x + 7 >= self.wx as usize {
+                    let window_x = (x + 7 - self.wx as usize) as u8;
+                    self.tile_map_pixel(mmu, window_map, window_x, self.window_line)
+                } else {
+                    let bg_x = self.scx.wrapping_add(x as u8);
+                    let bg_y = self.scy.wrapping_add(ly);
+                    self.tile_map_pixel(mmu, bg_map, bg_x, bg_y)
+                };
+                bg_colors[x] = color;
+                shades[x] = self.bg_shade(color);
+            }
+            if window_visible {
+                self.window_line += 1;
+            }
+        } else {
+            shades = [self.bg_shade(0); SCREEN_WIDTH];
+        }
+
+        if is_bit_set!(self.lcdc, OBJ_ENABLE) {
+            self.render_sprites(mmu, &bg_colors, &mut shades, &mut palettes);
+        }
+
+        let row = ly as usize * SCREEN_WIDTH * 3;
+        for x in 0..SCREEN_WIDTH {
+            let color = if self.cgb {
+                self.cgb_color(palettes[x], shades[x])
+            } else {
+                DMG_COLORS[shades[x] as usize]
+            };
+            let pixel = row + x * 3;
+            self.framebuffer[pixel..pixel + 3].copy_from_slice(&color);
+        }
+    }
+
+    /// Shade of a BG color index, BGP is ignored by CGB games
+    fn bg_shade(&self, color: u8) -> u8 {
+        if self.cgb_mode() {
+            color
+        } else {
+            palette_shade(self.bgp, color)
+        }
+    }
+
+    fn render_sprites(&self, mmu: &Mmu, bg_colors: &[u8], shades: &mut [u8], palettes: &mut [u8]) {
+        let height = if is_bit_set!(self.lcdc, OBJ_SIZE) {
+            16
+        } else {
+            8
+        };
+        let ly = self.ly as i16;
+        let mut sprites: Vec<Sprite> = (0..40)
+            .map(|index| {
+                let address = 0xFE00 + index * 4;
+                Sprite {
+                    y: mmu.read(address) as i16 - 16,
+                    x: mmu.read(address + 1) as i16 - 8,
+                    tile: mmu.read(address + 2),
+                    attributes: mmu.read(address + 3),
+                }
+            })
+            .filter(|sprite| sprite.y <= ly && ly < sprite.y + height)
+            .take(SPRITES_PER_LINE)
+            .collect();
+        // Smaller X has priority, then the first one in OAM (the sort is stable)
+        sprites.sort_by_key(|sprite| sprite.x);
+
+        let mut drawn = [false; SCREEN_WIDTH];
+        for sprite in sprites.iter() {
+            let mut row = (ly - sprite.y) as u8;
+            if is_bit_set!(sprite.attributes, Y_FLIP) {
+                row = height as u8 - 1 - row;
+            }
+            let tile = if height == 16 {
+                sprite.tile & 0xFE
+            } else {
+                sprite.tile
+            };
+            let address = 0x8000 + tile as u16 * 16;
+            // DMG palettes map to the first two CGB OBJ palettes
+            let (palette, cgb_palette) = if self.cgb_mode() {
+                (None, sprite.attributes & CGB_OBJ_PALETTE)
+            } else if is_bit_set!(sprite.attributes, OBJ_PALETTE) {
+                (Some(self.obp1), 1)
+            } else {
+                (Some(self.obp0), 0)
+            };
+            for column in 0..8u8 {
+                let x = sprite.x + column as i16;
+                if !(0..SCREEN_WIDTH as i16).contains(&x) || drawn[x as usize] {
+                    continue;
+                }
+                let tile_x = if is_bit_set!(sprite.attributes, X_FLIP) {
+                    7 - column
+                } else {
+                    column
+                };
+                let color = tile_pixel(mmu, address + (row as u16 / 8) * 16, tile_x, row % 8);
+                if color == 0 {
+                    continue;
+                }
+                let x = x as usize;
+                // A higher priority sprite hides the others even when the background wins
+                drawn[x] = true;
+                if is_bit_set!(sprite.attributes, BG_PRIORITY) && bg_colors[x] != 0 {
+                    continue;
+                }
+                shades[x] = match palette {
+                    Some(palette) => palette_shade(palette, color),
+                    None => color,
+                };
+                palettes[x] = OBJ_PALETTES as u8 + cgb_palette;
+            }
+        }
+    }
+}
+
+/// Color index (0-3) of pixel (`x`, `y`) of the tile at `address` in VRAM bank 0
+fn tile_pixel(mmu: &Mmu, address: u16, x: u8, y: u8) -> u8 {
+    let low = mmu.vram(0, address + y as u16 * 2);
+    let high = mmu.vram(0, address + y as u16 * 2 + 1);
+    let bit = 7 - x;
+    ((high >> bit) & 1) << 1 | ((low >> bit) & 1)
+}
+
+fn palette_shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0x03
+}
+
+impl MemoryHandler for Ppu {
+    fn read(&self, _: &Mmu, address: u16) -> MemoryRead {
+        let value = match address {
+            0xFF40 => self.lcdc,
+            0xFF41 => self.read_stat(),
+            0xFF42 => self.scy,
+            0xFF43 => self.scx,
+            0xFF44 => self.ly,
+            0xFF45 => self.lyc,
+            0xFF46 => self.dma,
+            0xFF47 => self.bgp,
+            0xFF48 => self.obp0,
+            0xFF49 => self.obp1,
+            0xFF4A => self.wy,
+            0xFF4B => self.wx,
+            0xFF4F if self.cgb_mode() => self.vram_bank,
+            0xFF68 if self.cgb_mode() => self.bcps,
+            0xFF69 if self.cgb_mode() => self.read_palette(self.bcps, 0),
+            0xFF6A if self.cgb_mode() => self.ocps,
+            0xFF6B if self.cgb_mode() => self.read_palette(self.ocps, OBJ_PALETTES * 8),
+            0xFF4F | 0xFF68..=0xFF6B => 0xFF,
+            _ => return MemoryRead::Pass,
+        };
+        MemoryRead::Replace(value)
+    }
+
+    fn write(&mut self, mmu: &Mmu, address: u16, value: u8) -> MemoryWrite {
+        match address {
+            0xFF40 => self.write_lcdc(value),
+            0xFF41 => {
+                self.stat = value & 0x78;
+                self.update_stat_interrupt();
+            }
+            0xFF42 => self.scy = value,
+            0xFF43 => self.scx = value,
+            // LY is read only
+            0xFF44 => {}
+            0xFF45 => {
+                self.lyc = value;
+                self.update_stat_interrupt();
+            }
+            0xFF46 => {
+                self.dma = value;
+                self.dma_request = Some(value);
+            }
+            0xFF47 => self.bgp = value,
+            0xFF48 => self.obp0 = value,
+            0xFF49 => self.obp1 = value,
+            0xFF4A => self.wy = value,
+            0xFF4B => self.wx = value,
+            0xFF4C if self.cgb && !self.key0_locked => {
+                self.dmg_compatibility = is_bit_set!(value, DMG_COMPATIBILITY);
+            }
+            0xFF4F if self.cgb_mode() => self.write_vbk(mmu, value),
+            0xFF68 if self.cgb_mode() => self.bcps = value & (PALETTE_INDEX | 0x80),
+            0xFF69 if self.cgb_mode() => self.bcps = self.write_palette(self.bcps, 0, value),
+            0xFF6A if self.cgb_mode() => self.ocps = value & (PALETTE_INDEX | 0x80),
+            0xFF6B if self.cgb_mode() => {
+                self.ocps = self.write_palette(self.ocps, OBJ_PALETTES * 8, value);
+            }
+            // Unmapping the boot ROM locks KEY0, the boot ROM handler does the rest
+            0xFF50 => {
+                self.key0_locked = true;
+                return MemoryWrite::Pass;
+            }
+            0xFF4C | 0xFF4F | 0xFF68..=0xFF6B => {}
+            _ => return MemoryWrite::Pass,
+        }
+        MemoryWrite::Block
+    }
+}
@@ -0,0 +1,124 @@
+//! Runs a system without a frontend until a limit or a stop condition is reached
+
+use std::fmt;
+
+use crate::cpu::registers::Reg16;
+use crate::system::System;
+
+const JR: u8 = 0x18;
+// JR offset jumping back to the JR itself
+const SELF_JUMP: u8 = 0xFE;
+
+#[derive(Default)]
+pub struct RunOptions {
+    pub max_frames: Option<u64>,
+    pub max_cycles: Option<u64>,
+    /// Stop before executing the instruction at this address
+    pub stop_pc: Option<u16>,
+    /// Stop once the serial output contains this text
+    pub stop_serial: Option<String>,
+    /// Stop on a `JR -2` infinite loop, how most test ROMs end, or on a CPU locked up by an
+    /// illegal opcode
+    pub stop_on_loop: bool,
+}
+
+impl RunOptions {
+    pub fn has_stop_condition(&self) -> bool {
+        self.stop_pc.is_some() || self.stop_serial.is_some() || self.stop_on_loop
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExitReason {
+    FrameLimit,
+    CycleLimit,
+    Pc(u16),
+    Serial,
+    InfiniteLoop,
+}
+
+impl ExitReason {
+    /// Whether the run ended on a stop condition rather than a limit
+    pub fn is_stop_condition(&self) -> bool {
+        matches!(
+            self,
+            ExitReason::Pc(_) | ExitReason::Serial | ExitReason::InfiniteLoop
+        )
+    }
+
+    /// Whether a limit was reached before any of the stop conditions of `options`, a run
+    /// without stop conditions succeeds on its limits
+    pub fn is_limit_reached(&self, options: &RunOptions) -> bool {
+        options.has_stop_condition() && !self.is_stop_condition()
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::FrameLimit => write!(f, "frame limit reached"),
+            ExitReason::CycleLimit => write!(f, "cycle limit reached"),
+            ExitReason::Pc(pc) => write!(f, "reached PC ${:04X}", pc),
+            ExitReason::Serial => write!(f, "serial output matched"),
+            ExitReason::InfiniteLoop => write!(f, "infinite loop"),
+        }
+    }
+}
+
+pub fn run(system: &mut System, options: &RunOptions) -> ExitReason {
+    let mut serial_length = 0;
+    loop {
+        let pc = system.cpu().registers.read_u16(Reg16::PC);
+        if options.stop_pc == Some(pc) {
+            return ExitReason::Pc(pc);
+        }
+        if options.stop_on_loop {
+            let mmu = system.cpu().mmu();
+            let self_jump = mmu.read(pc) == JR && mmu.read(pc.wrapping_add(1)) == SELF_JUMP;
+            if self_jump || system.cpu().locked {
+                return ExitReason::InfiniteLoop;
+            }
+        }
+        if options
+            .max_cycles
+            .is_some_and(|limit| system.cycles() >= limit)
+        {
+            return ExitReason::CycleLimit;
+        }
+        if options
+            .max_frames
+            .is_some_and(|limit| system.frames() >= limit)
+        {
+            return ExitReason::FrameLimit;
+        }
+
+        system.step();
+
+        if let Some(pattern) = options.stop_serial.as_ref() {
+            let serial = system.serial();
+            let output = serial.output();
+            if output.len() != serial_length {
+                serial_length = output.len();
+                if output.contains(pattern.as_str()) {
+                    return ExitReason::Serial;
+                }
+            }
+        }
+    }
+}
+
+/// One line summary of the CPU registers and elapsed time
+pub fn registers(system: &System) -> String {
+    let registers = &system.cpu().registers;
+    format!(
+        "AF: ${:04X}  BC: ${:04X}  DE: ${:04X}  HL: ${:04X}  SP: ${:04X}  PC: ${:04X}  cycles: {}  frames: {}",
+        registers.read_u16(Reg16::AF),
+        registers.read_u16(Reg16::BC),
+        registers.read_u16(Reg16::DE),
+        registers.read_u16(Reg16::HL),
+        registers.read_u16(Reg16::SP),
+        registers.read_u16(Reg16::PC),
+        system.cycles(),
+        system.frames(),
+    )
+}
@@ -1,6 +1,12 @@
+mod boot;
 mod cpu;
 mod memory;
 mod graphics;
 pub mod system;
-mod debug;
-mod util;
\ No newline at end of file
+pub mod debug;
+pub mod headless;
+pub mod link;
+pub mod movie;
+pub mod netplay;
+pub mod patch;
+pub mod util;
\ No newline at end of file
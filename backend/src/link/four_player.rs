@@ -0,0 +1,281 @@
+use crate::system::System;
+
+use super::{pair, PairLink, SerialLink};
+
+const PLAYERS: usize = 4;
+
+// Ping phase
+const PING_HEADER: u8 = 0xFE;
+const ACK: u8 = 0x88;
+const PING_SIZE: usize = 4;
+// Sent 4 times by player 1 to start the transmission phase
+const START: u8 = 0xAA;
+// Sent 4 times by the adapter before the first transmission packet
+const SYNC: u8 = 0xCC;
+// Sent 4 times in a row by player 1 to go back to the ping phase
+const RESTART: u8 = 0xFF;
+
+/// The adapter clocks a byte about every 4 ms while pinging
+const PING_BYTE_CYCLES: u64 = 16384;
+/// Shortest time between two transmission bytes, the 8 bits of a transfer at 8192Hz
+const TRANSMISSION_BYTE_CYCLES: u64 = 4096;
+/// Each step of the RATE low nibble slows transmission down by about 1 ms per byte
+const RATE_STEP_CYCLES: u64 = 4194;
+const MAX_PACKET_SIZE: u8 = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Phase {
+    Ping,
+    /// Counts the sync bytes sent before transmission
+    Sync(usize),
+    Transmission,
+}
+
+/// DMG-07 four player adapter, the master of every link cable plugged in it.
+///
+/// It first pings the players with `FE ID ID ID` packets, ID being the player number (1 to 4)
+/// with the upper nibble flagging who answered the last ping with `88 88`. Player 1 sends the
+/// transmission RATE and packet SIZE (1 to 4 bytes) as the two last bytes of its answer, and
+/// starts the transmission phase by answering a whole ping with `AA`.
+///
+/// In the transmission phase, after 4 `CC` sync bytes, packets of 4 x SIZE bytes are clocked
+/// to every player: the first SIZE bytes each player sent during the previous packet, player
+/// 1 first, 0 for the missing players. Player 1 sending `FF` 4 times in a row restarts pinging.
+pub struct FourPlayerAdapter {
+    ports: Vec<PairLink>,
+    phase: Phase,
+    // Position of the next byte in its packet
+    index: usize,
+    // Players who answered the ACKs of the current and of the last ping
+    answered: [bool; PLAYERS],
+    connected: [bool; PLAYERS],
+    // Ping answer of player 1
+    answer: [u8; PING_SIZE],
+    rate: u8,
+    size: u8,
+    restart_bytes: u8,
+    // Packet sent to everyone, and the one collected for the next round
+    packet: Vec<u8>,
+    next_packet: Vec<u8>,
+    clock: u64,
+}
+
+impl FourPlayerAdapter {
+    /// The adapter and the cables to plug in the systems, player 1 first
+    pub fn new() -> (Self, [PairLink; PLAYERS]) {
+        let mut ports = Vec::with_capacity(PLAYERS);
+        let players = [(); PLAYERS].map(|_| {
+            let (adapter_end, player_end) = pair();
+            ports.push(adapter_end);
+            player_end
+        });
+        let adapter = Self {
+            ports,
+            phase: Phase::Ping,
+            index: 0,
+            answered: [false; PLAYERS],
+            connected: [false; PLAYERS],
+            answer: [0; PING_SIZE],
+            rate: 0,
+            size: 1,
+            restart_bytes: 0,
+            packet: Vec::new(),
+            next_packet: Vec::new(),
+            clock: 0,
+        };
+        (adapter, players)
+    }
+
+    /// Runs the adapter for `cycles` clock cycles
+    pub fn step(&mut self, cycles: u64) {
+        self.clock += cycles;
+        while self.clock >= self.byte_cycles() {
+            self.clock -= self.byte_cycles();
+            self.transfer();
+        }
+    }
+
+    /// Whether the players are exchanging packets rather than being pinged
+    pub fn transmitting(&self) -> bool {
+        self.phase != Phase::Ping
+    }
+
+    fn byte_cycles(&self) -> u64 {
+        match self.phase {
+            Phase::Ping => PING_BYTE_CYCLES,
+            _ => TRANSMISSION_BYTE_CYCLES + (self.rate & 0x0F) as u64 * RATE_STEP_CYCLES,
+        }
+    }
+
+    fn status(&self, player: usize) -> u8 {
+        let connected = self
+            .connected
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, connected)| {
+                mask | (*connected as u8) << (4 + i)
+            });
+        connected | (player as u8 + 1)
+    }
+
+    /// Clocks a byte out to every player
+    fn transfer(&mut self) {
+        match self.phase {
+            Phase::Ping => self.ping(),
+            Phase::Sync(count) => {
+                for port in self.ports.iter_mut() {
+                    port.exchange(SYNC);
+                }
+                self.phase = if count + 1 < PING_SIZE {
+                    Phase::Sync(count + 1)
+                } else {
+                    let length = PLAYERS * self.size as usize;
+                    self.packet = vec![0; length];
+                    self.next_packet = vec![0; length];
+                    self.index = 0;
+                    self.restart_bytes = 0;
+                    Phase::Transmission
+                };
+            }
+            Phase::Transmission => self.transmit(),
+        }
+    }
+
+    fn ping(&mut self) {
+        for player in 0..PLAYERS {
+            let byte = match self.index {
+                0 => PING_HEADER,
+                _ => self.status(player),
+            };
+            let reply = self.ports[player].exchange(byte);
+            match self.index {
+                0 => self.answered[player] = reply == ACK,
+                1 => self.answered[player] &= reply == ACK,
+                _ => {}
+            }
+            if player == 0 {
+                self.answer[self.index] = reply;
+            }
+        }
+        self.index += 1;
+        if self.index < PING_SIZE {
+            return;
+        }
+        self.index = 0;
+        // Player 1 doesn't acknowledge the ping it starts transmission with
+        if self.answer == [START; PING_SIZE] {
+            self.phase = Phase::Sync(0);
+            return;
+        }
+        self.connected = self.answered;
+        if self.connected[0] {
+            self.rate = self.answer[2];
+            self.size = self.answer[3].clamp(1, MAX_PACKET_SIZE);
+        }
+    }
+
+    fn transmit(&mut self) {
+        let size = self.size as usize;
+        let byte = self.packet[self.index];
+        for player in 0..PLAYERS {
+            let reply = self.ports[player].exchange(byte);
+            if self.index < size {
+                // Players that aren't plugged in send nothing
+                let data = if self.connected[player] { reply } else { 0 };
+                self.next_packet[player * size + self.index] = data;
+            }
+            if player == 0 {
+                self.restart_bytes = if reply == RESTART {
+                    self.restart_bytes + 1
+                } else {
+                    0
+                };
+            }
+        }
+        self.index += 1;
+        if self.restart_bytes as usize >= PING_SIZE {
+            self.phase = Phase::Ping;
+            self.index = 0;
+            return;
+        }
+        if self.index == self.packet.len() {
+            self.index = 0;
+            std::mem::swap(&mut self.packet, &mut self.next_packet);
+        }
+    }
+}
+
+/// Up to four systems of the same thread plugged in a four player adapter, run in lockstep
+/// like `LinkedSystems`
+pub struct FourPlayerSystems {
+    systems: Vec<System>,
+    adapter: FourPlayerAdapter,
+    // Cycles the adapter ran for
+    cycles: u64,
+}
+
+impl FourPlayerSystems {
+    /// Plugs `systems` in the adapter, player 1 first, replacing their links
+    pub fn new(systems: Vec<System>) -> Self {
+        assert!(
+            (1..=PLAYERS).contains(&systems.len()),
+            "The adapter has {} ports, not {}",
+            PLAYERS,
+            systems.len()
+        );
+        let (adapter, links) = FourPlayerAdapter::new();
+        let mut systems = systems;
+        for (system, link) in systems.iter_mut().zip(links) {
+            system.set_serial_link(Box::new(link));
+        }
+        Self {
+            systems,
+            adapter,
+            cycles: 0,
+        }
+    }
+
+    /// Executes an instruction on the system that is behind, then catches the adapter up
+    pub fn step(&mut self) {
+        let (behind, _) = self
+            .systems
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, system)| system.cycles())
+            .unwrap();
+        self.systems[behind].step();
+        let cycles = self.cycles();
+        self.adapter.step(cycles - self.cycles);
+        self.cycles = cycles;
+    }
+
+    /// Runs every system for `cycles` more clock cycles
+    pub fn run_for(&mut self, cycles: u64) {
+        let end = self.cycles() + cycles;
+        while self.cycles() < end {
+            self.step();
+        }
+    }
+
+    /// Clock cycles every system has run for
+    pub fn cycles(&self) -> u64 {
+        self.systems.iter().map(System::cycles).min().unwrap()
+    }
+
+    pub fn adapter(&self) -> &FourPlayerAdapter {
+        &self.adapter
+    }
+
+    /// `player` 0 is player 1
+    pub fn system(&self, player: usize) -> &System {
+        &self.systems[player]
+    }
+
+    pub fn system_mut(&mut self, player: usize) -> &mut System {
+        &mut self.systems[player]
+    }
+
+    pub fn into_systems(self) -> Vec<System> {
+        self.systems
+    }
+}
@@ -0,0 +1,60 @@
+use crate::system::System;
+
+use super::pair;
+
+/// Two systems of the same thread with a link cable between them, run in lockstep so that
+/// link play is deterministic
+pub struct LinkedSystems {
+    systems: [System; 2],
+}
+
+impl LinkedSystems {
+    /// Plugs both ends of a cable in `first` and `second`, replacing their links
+    pub fn new(mut first: System, mut second: System) -> Self {
+        let (first_link, second_link) = pair();
+        first.set_serial_link(Box::new(first_link));
+        second.set_serial_link(Box::new(second_link));
+        Self {
+            systems: [first, second],
+        }
+    }
+
+    /// Executes an instruction on the system that is behind. Neither gets more than an
+    /// instruction ahead of the other, far less than the shortest transfer (128 cycles)
+    pub fn step(&mut self) {
+        let side = if self.systems[0].cycles() <= self.systems[1].cycles() {
+            0
+        } else {
+            1
+        };
+        self.systems[side].step();
+    }
+
+    /// Runs both systems for `cycles` more clock cycles
+    pub fn run_for(&mut self, cycles: u64) {
+        let end = self.cycles() + cycles;
+        while self.cycles() < end {
+            self.step();
+        }
+    }
+
+    /// Clock cycles both systems have run for
+    pub fn cycles(&self) -> u64 {
+        self.systems[0].cycles().min(self.systems[1].cycles())
+    }
+
+    /// `side` 0 is the first system given to `new`, 1 the second
+    pub fn system(&self, side: usize) -> &System {
+        &self.systems[side]
+    }
+
+    pub fn system_mut(&mut self, side: usize) -> &mut System {
+        &mut self.systems[side]
+    }
+
+    /// Gives the systems back, still linked to each other
+    pub fn into_systems(self) -> (System, System) {
+        let [first, second] = self.systems;
+        (first, second)
+    }
+}
@@ -0,0 +1,123 @@
+//! Transports plugged into the serial port, standing for the link cable and whatever is at
+//! the other end of it
+
+mod four_player;
+mod linked;
+mod printer;
+mod tcp;
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+pub use four_player::{FourPlayerAdapter, FourPlayerSystems};
+pub use linked::LinkedSystems;
+pub use printer::Printer;
+pub use tcp::TcpLink;
+
+/// Byte shifted in when nothing drives the other end of the cable
+pub const IDLE_BYTE: u8 = 0xFF;
+
+pub trait SerialLink {
+    /// This side drives the clock: shifts `byte` out and returns the byte shifted in
+    fn exchange(&mut self, byte: u8) -> u8;
+
+    /// This side waits for the peer's clock with `byte` ready to be shifted out, returns the
+    /// byte the peer sent if it clocked a transfer since the last call
+    fn poll(&mut self, byte: u8) -> Option<u8> {
+        let _ = byte;
+        None
+    }
+
+    /// This side stopped waiting for the peer's clock: withdraws the byte made ready by
+    /// `poll`, and drops a transfer the peer clocked in the meantime
+    fn cancel(&mut self) {}
+}
+
+/// Nothing plugged in
+pub struct Disconnected;
+
+impl SerialLink for Disconnected {
+    fn exchange(&mut self, _: u8) -> u8 {
+        IDLE_BYTE
+    }
+}
+
+/// Prints every byte sent as a character, like the test ROMs expect
+pub struct StdoutLink;
+
+impl SerialLink for StdoutLink {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut stdout = std::io::stdout();
+        // Losing some output isn't worth stopping the emulation
+        let _ = stdout.write_all(&[byte]).and_then(|_| stdout.flush());
+        IDLE_BYTE
+    }
+}
+
+#[derive(Default)]
+struct Port {
+    // Byte shifted out by this side when the peer clocks a transfer, while waiting for it
+    ready: Option<u8>,
+    // Byte sent by the peer's clocked transfer, not seen yet
+    received: Option<u8>,
+}
+
+impl Port {
+    fn cancel(&mut self) {
+        self.ready = None;
+        self.received = None;
+    }
+}
+
+/// One end of a cable connecting two systems of the same process, see `pair`
+pub struct PairLink {
+    ports: Rc<RefCell<[Port; 2]>>,
+    side: usize,
+}
+
+/// Both ends of a cable, to plug in two systems
+pub fn pair() -> (PairLink, PairLink) {
+    let ports = Rc::new(RefCell::new([Port::default(), Port::default()]));
+    (
+        PairLink {
+            ports: ports.clone(),
+            side: 0,
+        },
+        PairLink { ports, side: 1 },
+    )
+}
+
+impl SerialLink for PairLink {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut ports = self.ports.borrow_mut();
+        let peer = &mut ports[1 - self.side];
+        // The byte is lost when the peer isn't waiting for a transfer
+        match peer.ready.take() {
+            Some(reply) => {
+                peer.received = Some(byte);
+                reply
+            }
+            None => IDLE_BYTE,
+        }
+    }
+
+    fn poll(&mut self, byte: u8) -> Option<u8> {
+        let mut ports = self.ports.borrow_mut();
+        let port = &mut ports[self.side];
+        match port.received.take() {
+            Some(received) => {
+                port.ready = None;
+                Some(received)
+            }
+            None => {
+                port.ready = Some(byte);
+                None
+            }
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.ports.borrow_mut()[self.side].cancel();
+    }
+}
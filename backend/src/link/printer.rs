@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::graphics::ppu::DMG_COLORS;
+use crate::util::png;
+
+use super::SerialLink;
+
+const MAGIC: [u8; 2] = [0x88, 0x33];
+// Sent in place of the first of the two bytes following the checksum
+const DEVICE_ID: u8 = 0x81;
+
+// Commands
+const INIT: u8 = 0x01;
+const PRINT: u8 = 0x02;
+const DATA: u8 = 0x04;
+const BREAK: u8 = 0x08;
+const STATUS: u8 = 0x0F;
+
+// Status bits
+const CHECKSUM_ERROR: u8 = 1 << 0;
+const BUSY: u8 = 1 << 1;
+const IMAGE_FULL: u8 = 1 << 2;
+const UNPROCESSED: u8 = 1 << 3;
+const PACKET_ERROR: u8 = 1 << 4;
+
+const WIDTH: usize = 160;
+const TILES_PER_ROW: usize = WIDTH / 8;
+const TILE_SIZE: usize = 16;
+/// The printer memory holds 9 data packets of 2 rows of tiles, a 160x144 picture
+const BUFFER_SIZE: usize = 9 * 2 * TILES_PER_ROW * TILE_SIZE;
+/// Height of the blank band printed for each margin unit
+const MARGIN_HEIGHT: usize = 8;
+/// Status inquiries answered busy after a print, games wait for the print to finish
+const PRINT_STATUS_INQUIRIES: u8 = 4;
+/// Palette most games send, 0 is the same
+const DEFAULT_PALETTE: u8 = 0xE4;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum State {
+    Magic(usize),
+    Command,
+    Compression,
+    Length(usize),
+    Data,
+    Checksum(usize),
+    DeviceId,
+    Status,
+}
+
+/// Game Boy Printer on the other end of the cable, saves what it prints as PNG images
+/// named `print_001.png`, `print_002.png`... in its directory
+pub struct Printer {
+    directory: PathBuf,
+    printed: u32,
+    state: State,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    status: u8,
+    // Status inquiries left before the current print is done
+    busy: u8,
+    // Decompressed 2bpp tiles, rows of 20 tiles
+    image: Vec<u8>,
+}
+
+impl Printer {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            printed: 0,
+            state: State::Magic(0),
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            status: 0,
+            busy: 0,
+            image: Vec::new(),
+        }
+    }
+
+    /// Images printed so far
+    pub fn printed(&self) -> u32 {
+        self.printed
+    }
+
+    fn execute(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !(CHECKSUM_ERROR | PACKET_ERROR);
+        match self.command {
+            INIT | BREAK => {
+                self.image.clear();
+                self.status = 0;
+                self.busy = 0;
+            }
+            // An empty packet ends the image
+            DATA if self.data.is_empty() => self.status |= IMAGE_FULL,
+            DATA => {
+                let data = if self.compressed {
+                    decompress(&self.data)
+                } else {
+                    std::mem::take(&mut self.data)
+                };
+                let space = BUFFER_SIZE - self.image.len();
+                self.image.extend_from_slice(&data[..data.len().min(space)]);
+                self.status |= UNPROCESSED;
+            }
+            PRINT if self.data.len() == 4 => {
+                let (sheets, margins, palette) = (self.data[0], self.data[1], self.data[2]);
+                // The last byte is the exposure, how dark the print is
+                self.print(sheets, margins >> 4, margins & 0xF, palette);
+                self.status = BUSY | IMAGE_FULL;
+                self.busy = PRINT_STATUS_INQUIRIES;
+            }
+            STATUS if self.busy > 0 => {
+                self.busy -= 1;
+                if self.busy == 0 {
+                    self.image.clear();
+                    self.status = 0;
+                }
+            }
+            STATUS => {}
+            _ => self.status |= PACKET_ERROR,
+        }
+    }
+
+    fn print(&mut self, sheets: u8, margin_before: u8, margin_after: u8, palette: u8) {
+        let palette = if palette == 0 {
+            DEFAULT_PALETTE
+        } else {
+            palette
+        };
+        let rows = self.image.len() / (TILES_PER_ROW * TILE_SIZE);
+        // No sheet only feeds paper
+        if sheets == 0 || rows == 0 {
+            return;
+        }
+        let mut pixels = Vec::new();
+        for _ in 0..sheets {
+            blank(&mut pixels, margin_before as usize * MARGIN_HEIGHT);
+            for y in 0..rows * 8 {
+                for x in 0..WIDTH {
+                    let tile = (y / 8 * TILES_PER_ROW + x / 8) * TILE_SIZE;
+                    let line = tile + (y % 8) * 2;
+                    let bit = 7 - x % 8;
+                    let color = ((self.image[line] >> bit) & 1)
+                        | (((self.image[line + 1] >> bit) & 1) << 1);
+                    let shade = (palette >> (color * 2)) & 0b11;
+                    pixels.extend_from_slice(&DMG_COLORS[shade as usize]);
+                }
+            }
+            blank(&mut pixels, margin_after as usize * MARGIN_HEIGHT);
+        }
+        self.printed += 1;
+        let height = pixels.len() / (WIDTH * 3);
+        let path = self
+            .directory
+            .join(format!("print_{:03}.png", self.printed));
+        let image = png::encode_rgb(WIDTH as u32, height as u32, &pixels);
+        if let Err(e) = fs::write(&path, image) {
+            eprintln!("Unable to save print {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn blank(pixels: &mut Vec<u8>, lines: usize) {
+    pixels.resize(pixels.len() + lines * WIDTH * 3, 0xFF);
+}
+
+/// Run length decoding: a control byte with bit 7 set repeats the next byte (control & 0x7F) + 2
+/// times, otherwise (control + 1) bytes are copied as is
+fn decompress(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut bytes = data.iter();
+    while let Some(&control) = bytes.next() {
+        if control & 0x80 != 0 {
+            let Some(&byte) = bytes.next() else { break };
+            output.resize(output.len() + (control & 0x7F) as usize + 2, byte);
+        } else {
+            output.extend(bytes.by_ref().take(control as usize + 1));
+        }
+    }
+    output
+}
+
+impl SerialLink for Printer {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut reply = 0x00;
+        self.state = match self.state {
+            State::Magic(i) if byte == MAGIC[i] => {
+                if i + 1 < MAGIC.len() {
+                    State::Magic(i + 1)
+                } else {
+                    State::Command
+                }
+            }
+            // Out of sync, wait for the start of a packet
+            State::Magic(_) if byte == MAGIC[0] => State::Magic(1),
+            State::Magic(_) => State::Magic(0),
+            State::Command => {
+                self.command = byte;
+                self.checksum = byte as u16;
+                State::Compression
+            }
+            State::Compression => {
+                self.compressed = byte & 1 != 0;
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                State::Length(0)
+            }
+            State::Length(i) => {
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                if i == 0 {
+                    self.length = byte as u16;
+                    State::Length(1)
+                } else {
+                    self.length |= (byte as u16) << 8;
+                    self.data.clear();
+                    if self.length == 0 {
+                        State::Checksum(0)
+                    } else {
+                        State::Data
+                    }
+                }
+            }
+            State::Data => {
+                self.data.push(byte);
+                self.checksum = self.checksum.wrapping_add(byte as u16);
+                if self.data.len() == self.length as usize {
+                    State::Checksum(0)
+                } else {
+                    State::Data
+                }
+            }
+            State::Checksum(0) => {
+                self.received_checksum = byte as u16;
+                State::Checksum(1)
+            }
+            State::Checksum(_) => {
+                self.received_checksum |= (byte as u16) << 8;
+                self.execute();
+                State::DeviceId
+            }
+            State::DeviceId => {
+                reply = DEVICE_ID;
+                State::Status
+            }
+            State::Status => {
+                reply = self.status;
+                State::Magic(0)
+            }
+        };
+        reply
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::{Port, SerialLink, IDLE_BYTE};
+
+// Messages are [kind, sequence number, byte]
+const TRANSFER: u8 = 0;
+const REPLY: u8 = 1;
+
+/// Link cable to another emulator over a localhost TCP connection.
+///
+/// A clocked transfer is a request the peer answers from its background thread with the
+/// byte it has ready, if it is waiting for a transfer, so both sides always agree on
+/// whether the transfer happened.
+pub struct TcpLink {
+    writer: Arc<Mutex<TcpStream>>,
+    port: Arc<Mutex<Port>>,
+    replies: Receiver<[u8; 2]>,
+    sequence: u8,
+}
+
+impl TcpLink {
+    /// Waits for the peer to connect on `port`
+    pub fn listen(port: u16) -> io::Result<TcpLink> {
+        TcpLink::accept(TcpListener::bind((Ipv4Addr::LOCALHOST, port))?)
+    }
+
+    /// Waits for the peer to connect to `listener`, which may be bound to port 0 to let the
+    /// system pick a free port
+    pub fn accept(listener: TcpListener) -> io::Result<TcpLink> {
+        println!(
+            "Waiting for a link cable connection on localhost:{}",
+            listener.local_addr()?.port()
+        );
+        let (stream, address) = listener.accept()?;
+        println!("Link cable connected from {}", address);
+        TcpLink::new(stream)
+    }
+
+    /// Connects to a peer listening on `port`
+    pub fn connect(port: u16) -> io::Result<TcpLink> {
+        TcpLink::new(TcpStream::connect((Ipv4Addr::LOCALHOST, port))?)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<TcpLink> {
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let writer = Arc::new(Mutex::new(stream));
+        let port = Arc::new(Mutex::new(Port::default()));
+        let (sender, replies) = mpsc::channel();
+        let (thread_writer, thread_port) = (writer.clone(), port.clone());
+        // Answers the peer's transfers even while the emulation is busy or blocked
+        thread::spawn(move || {
+            let mut message = [0; 3];
+            while reader.read_exact(&mut message).is_ok() {
+                match message {
+                    [TRANSFER, sequence, received] => {
+                        let mut port = thread_port.lock().unwrap();
+                        // The byte is lost when this side isn't waiting for a transfer
+                        let reply = match port.ready.take() {
+                            Some(reply) => {
+                                port.received = Some(received);
+                                reply
+                            }
+                            None => IDLE_BYTE,
+                        };
+                        send(&thread_writer, REPLY, sequence, reply);
+                    }
+                    [_, sequence, reply] => {
+                        if sender.send([sequence, reply]).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        Ok(TcpLink {
+            writer,
+            port,
+            replies,
+            sequence: 0,
+        })
+    }
+}
+
+fn send(writer: &Mutex<TcpStream>, kind: u8, sequence: u8, byte: u8) {
+    // A broken connection shows up as a disconnected channel
+    let _ = writer.lock().unwrap().write_all(&[kind, sequence, byte]);
+}
+
+impl SerialLink for TcpLink {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        self.sequence = self.sequence.wrapping_add(1);
+        send(&self.writer, TRANSFER, self.sequence, byte);
+        // Every transfer gets a reply, only a lost connection ends the wait
+        while let Ok([sequence, reply]) = self.replies.recv() {
+            if sequence == self.sequence {
+                return reply;
+            }
+        }
+        IDLE_BYTE
+    }
+
+    fn poll(&mut self, byte: u8) -> Option<u8> {
+        let mut port = self.port.lock().unwrap();
+        match port.received.take() {
+            Some(received) => {
+                port.ready = None;
+                Some(received)
+            }
+            None => {
+                port.ready = Some(byte);
+                None
+            }
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.port.lock().unwrap().cancel();
+    }
+}
@@ -0,0 +1,257 @@
+//! Game Genie and GameShark cheat codes.
+//!
+//! A Game Genie sits between the cartridge and the console and replaces the byte read at a
+//! ROM address, only when the ROM holds the compare byte if the code has one (the address
+//! may be in a switchable bank). A GameShark writes its values in RAM at every VBlank.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::mmu::{MemoryHandler, MemoryRead, MemoryWrite, Mmu};
+use crate::util::state::{StateError, StateReader, StateWriter};
+
+const CART_RAM: u16 = 0xA000;
+const CART_RAM_END: u16 = 0xBFFF;
+const CART_RAM_BANK_SIZE: usize = 0x2000;
+const WRAM: u16 = 0xC000;
+const WRAM_BANK: u16 = 0xD000;
+const WRAM_BANK_END: u16 = 0xDFFF;
+const HRAM: u16 = 0xFF80;
+const HRAM_END: u16 = 0xFFFE;
+// GameShark bank bytes selecting the CGB WRAM bank of the low nibble
+const GAMESHARK_WRAM_BANK: u8 = 0x90;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheatError {
+    /// Neither a Game Genie nor a GameShark code
+    Format(String),
+    /// A Game Genie code outside of the cartridge ROM
+    Address(u16),
+    /// A GameShark code outside of the cartridge RAM, WRAM and HRAM
+    RamAddress(u16),
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheatError::Format(code) => write!(
+                f,
+                "invalid cheat code {}, expected ABC-DEF(-GHI) for Game Genie or 8 hex digits \
+                 for GameShark",
+                code
+            ),
+            CheatError::Address(address) => {
+                write!(
+                    f,
+                    "Game Genie code for ${:04X}, outside of the ROM",
+                    address
+                )
+            }
+            CheatError::RamAddress(address) => {
+                write!(
+                    f,
+                    "GameShark code for ${:04X}, outside of the cartridge RAM, WRAM and HRAM",
+                    address
+                )
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Code {
+    /// Reads `value` at `address` of the ROM, when it holds `compare` if any
+    GameGenie {
+        address: u16,
+        value: u8,
+        compare: Option<u8>,
+    },
+    /// Writes `value` at `address` at every VBlank. In the cartridge RAM, `bank` is the
+    /// external RAM bank written whatever bank is mapped (usually $01, the only bank of
+    /// small cartridges). $9X selects the CGB WRAM bank X at $D000-$DFFF.
+    GameShark { bank: u8, value: u8, address: u16 },
+}
+
+impl FromStr for Code {
+    type Err = CheatError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let digits: Option<Vec<u8>> = code
+            .chars()
+            .filter(|&c| c != '-')
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect();
+        let format_error = || CheatError::Format(code.to_string());
+        let digits = digits.ok_or_else(format_error)?;
+        let byte = |i: usize| (digits[i] << 4) | digits[i + 1];
+        match digits.len() {
+            // ABC-DEF-GHI: AB value, FCDE address with F inverted, GI compare byte, rotated
+            // and scrambled, H unused
+            6 | 9 => {
+                let address = (((digits[5] ^ 0xF) as u16) << 12)
+                    | ((digits[2] as u16) << 8)
+                    | ((digits[3] as u16) << 4)
+                    | digits[4] as u16;
+                if address >= 0x8000 {
+                    return Err(CheatError::Address(address));
+                }
+                let compare = (digits.len() == 9)
+                    .then(|| ((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xBA);
+                Ok(Code::GameGenie {
+                    address,
+                    value: byte(0),
+                    compare,
+                })
+            }
+            // BBVVLLHH: bank, value, little endian address
+            8 if !code.contains('-') => {
+                let address = u16::from_le_bytes([byte(4), byte(6)]);
+                // Writes elsewhere would reach the MBC registers or the I/O registers
+                if !matches!(
+                    address,
+                    CART_RAM..=CART_RAM_END | WRAM..=WRAM_BANK_END | HRAM..=HRAM_END
+                ) {
+                    return Err(CheatError::RamAddress(address));
+                }
+                Ok(Code::GameShark {
+                    bank: byte(0),
+                    value: byte(2),
+                    address,
+                })
+            }
+            _ => Err(format_error()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Cheat {
+    /// As entered
+    pub code: String,
+    pub kind: Code,
+    pub enabled: bool,
+}
+
+#[derive(Default)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// Adds an enabled cheat, returns its index
+    pub fn add(&mut self, code: &str) -> Result<usize, CheatError> {
+        let kind = code.trim().parse()?;
+        self.cheats.push(Cheat {
+            code: code.trim().to_string(),
+            kind,
+            enabled: true,
+        });
+        Ok(self.cheats.len() - 1)
+    }
+
+    /// Removes the cheat at `index`, the following ones move down
+    pub fn remove(&mut self, index: usize) -> Option<Cheat> {
+        (index < self.cheats.len()).then(|| self.cheats.remove(index))
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        match self.cheats.get_mut(index) {
+            Some(cheat) => {
+                cheat.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u32(self.cheats.len() as u32);
+        for cheat in self.cheats.iter() {
+            state.buffer(cheat.code.as_bytes());
+            state.bool(cheat.enabled);
+        }
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        let mut cheats = Vec::new();
+        for _ in 0..state.u32()? {
+            let code = std::str::from_utf8(state.buffer()?).map_err(|_| StateError::NotAState)?;
+            let kind = code.parse().map_err(|_| StateError::NotAState)?;
+            cheats.push(Cheat {
+                code: code.to_string(),
+                kind,
+                enabled: state.bool()?,
+            });
+        }
+        self.cheats = cheats;
+        Ok(())
+    }
+
+    /// Applies the enabled GameShark codes
+    pub fn vblank(&self, mmu: &mut Mmu) {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled) {
+            let Code::GameShark {
+                bank,
+                value,
+                address,
+            } = cheat.kind
+            else {
+                continue;
+            };
+            match address {
+                CART_RAM..=CART_RAM_END => {
+                    // Cartridges with less banks mirror them
+                    let size = mmu.cart_ram().len();
+                    if size > 0 {
+                        let offset = bank as usize * CART_RAM_BANK_SIZE
+                            + (address - CART_RAM) as usize;
+                        mmu.write_cart_ram(offset % size, value);
+                    }
+                }
+                // Only bank 1 is emulated, bank 0 selects it as on the CGB
+                WRAM_BANK..=WRAM_BANK_END if bank & 0xF0 == GAMESHARK_WRAM_BANK => {
+                    if bank & 0x0F <= 1 {
+                        mmu.write(address, value);
+                    }
+                }
+                // WRAM and HRAM, checked when parsing
+                _ => mmu.write(address, value),
+            }
+        }
+    }
+}
+
+impl MemoryHandler for Cheats {
+    fn read(&self, mmu: &Mmu, address: u16) -> MemoryRead {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled) {
+            let Code::GameGenie {
+                address: cheat_address,
+                value,
+                compare,
+            } = cheat.kind
+            else {
+                continue;
+            };
+            if cheat_address != address {
+                continue;
+            }
+            if compare.is_some_and(|compare| mmu.read_backing(address) != compare) {
+                continue;
+            }
+            return MemoryRead::Replace(value);
+        }
+        MemoryRead::Pass
+    }
+
+    fn write(&mut self, _: &Mmu, _: u16, _: u8) -> MemoryWrite {
+        MemoryWrite::Pass
+    }
+}
@@ -0,0 +1,119 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use super::mmu::{MemoryHandler, MemoryRead, MemoryWrite, Mmu};
+use crate::cpu::interrupt::InterruptRequest;
+use crate::util::state::{StateError, StateReader, StateWriter};
+
+// P1 bits selecting which half of the buttons is read, when cleared
+const SELECT_DIRECTIONS: u8 = 1 << 4;
+const SELECT_ACTIONS: u8 = 1 << 5;
+const SELECT_MASK: u8 = SELECT_DIRECTIONS | SELECT_ACTIONS;
+
+/// Pressed buttons, the directions in the low nibble and the actions in the high one, in
+/// the order of the P1 bits
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Buttons(pub u8);
+
+impl Buttons {
+    pub const NONE: Buttons = Buttons(0);
+    pub const RIGHT: Buttons = Buttons(1 << 0);
+    pub const LEFT: Buttons = Buttons(1 << 1);
+    pub const UP: Buttons = Buttons(1 << 2);
+    pub const DOWN: Buttons = Buttons(1 << 3);
+    pub const A: Buttons = Buttons(1 << 4);
+    pub const B: Buttons = Buttons(1 << 5);
+    pub const SELECT: Buttons = Buttons(1 << 6);
+    pub const START: Buttons = Buttons(1 << 7);
+
+    pub fn contains(self, buttons: Buttons) -> bool {
+        self.0 & buttons.0 == buttons.0
+    }
+
+    fn directions(self) -> u8 {
+        self.0 & 0x0F
+    }
+
+    fn actions(self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+impl BitOr for Buttons {
+    type Output = Buttons;
+
+    fn bitor(self, rhs: Buttons) -> Buttons {
+        Buttons(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Buttons {
+    fn bitor_assign(&mut self, rhs: Buttons) {
+        self.0 |= rhs.0;
+    }
+}
+
+pub struct Joypad {
+    interrupt_request: InterruptRequest,
+    // P1 bits 4 and 5, both halves are selected after the boot ROM
+    select: u8,
+    pressed: Buttons,
+}
+
+impl Joypad {
+    pub fn new(interrupt_request: InterruptRequest) -> Self {
+        Self {
+            interrupt_request,
+            select: 0,
+            pressed: Buttons::NONE,
+        }
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        let before = self.lines();
+        self.pressed = buttons;
+        self.request_interrupt(before);
+    }
+
+    /// Low nibble of P1, a cleared bit is a pressed button of a selected half
+    fn lines(&self) -> u8 {
+        let mut pressed = 0;
+        if self.select & SELECT_DIRECTIONS == 0 {
+            pressed |= self.pressed.directions();
+        }
+        if self.select & SELECT_ACTIONS == 0 {
+            pressed |= self.pressed.actions();
+        }
+        !pressed & 0x0F
+    }
+
+    // The interrupt is requested when a line goes low
+    fn request_interrupt(&mut self, before: u8) {
+        if before & !self.lines() != 0 {
+            self.interrupt_request.joypad(true);
+        }
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.select);
+        state.u8(self.pressed.0);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.select = state.u8()?;
+        self.pressed = Buttons(state.u8()?);
+        Ok(())
+    }
+}
+
+impl MemoryHandler for Joypad {
+    fn read(&self, _: &Mmu, _: u16) -> MemoryRead {
+        MemoryRead::Replace(self.select | self.lines())
+    }
+
+    fn write(&mut self, _: &Mmu, _: u16, value: u8) -> MemoryWrite {
+        let before = self.lines();
+        self.select = value & SELECT_MASK;
+        self.request_interrupt(before);
+        MemoryWrite::Block
+    }
+}
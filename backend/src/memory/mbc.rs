@@ -1,58 +1,143 @@
 #![allow(unused)]
 use crate::is_bit_set;
 
-use super::mmu::{MemoryHandler, MemoryRead, MemoryWrite};
+use super::mmu::{Backing, MemoryHandler, MemoryRead, MemoryWrite, Mmu};
+use crate::util::state::{StateError, StateReader, StateWriter};
 use core::fmt;
+use std::rc::Rc;
 
 const CGB: u8 = 7;
 const CGB_ONLY: u8 = 6;
+const ROM_BANK_SIZE: usize = 0x4000;
+const RAM_BANK_SIZE: usize = 0x2000;
+// The cartridge header ends at 0x014F
+const HEADER_END: usize = 0x150;
 
 trait MemoryBank {
-    fn read(&self, address: u16) -> MemoryRead;
-    fn write(&self, address: u16, value: u8) -> MemoryWrite;
+    /// Maps the ROM and RAM banks currently selected into `mmu`
+    fn map(&self, mmu: &Mmu);
+    fn write(&mut self, address: u16, value: u8) -> MemoryWrite;
+
+    fn rom_bank(&self) -> u16 {
+        1
+    }
+
+    fn ram_bank(&self) -> u8 {
+        0
+    }
+
+    fn ram_enabled(&self) -> bool {
+        false
+    }
+
+    /// Saves the banking registers, the mapping is saved by the `Mmu`
+    fn save_state(&self, state: &mut StateWriter) {}
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        Ok(())
+    }
+}
+
+/// Snapshot of the cartridge banking state, as seen by the debugger
+pub struct BankInfo {
+    pub mbc: String,
+    pub rom_bank: u16,
+    pub ram_bank: u8,
+    pub ram_enabled: bool,
+    pub boot_rom_mapped: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CartridgeError {
+    /// The file ends before the end of the header, with its length
+    TooShort(usize),
+    /// The cartridge type isn't emulated, with its header code
+    Unsupported(u8),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::TooShort(length) => {
+                write!(f, "not a ROM, {} bytes is too short for a header", length)
+            }
+            CartridgeError::Unsupported(code) => {
+                let mbc = match code {
+                    0x05 | 0x06 => "MBC2",
+                    0x0F..=0x13 => "MBC3",
+                    0x19..=0x1E => "MBC5",
+                    0x20 => "MBC6",
+                    0x22 => "MBC7",
+                    _ => "unknown",
+                };
+                write!(f, "unsupported cartridge type ${:02X} ({})", code, mbc)
+            }
+        }
+    }
 }
 
 enum MbcType {
     MbcNone(MbcNone),
-    Mbc1(MbcNone),
-    Mbc2(MbcNone),
-    Mbc3(MbcNone),
-    Mbc5(MbcNone),
-    Mbc6(MbcNone),
-    Mbc7(MbcNone),
+    Mbc1(Mbc1),
 }
 
 impl MbcType {
-    pub fn new(code: u8, rom: Vec<u8>) -> MbcType {
+    pub fn new(code: u8, rom_size: usize, ram_size: usize) -> Result<MbcType, CartridgeError> {
         match code {
-            0x00 => MbcType::MbcNone(MbcNone::new(rom)),
-            _ => unimplemented!("Mbc type not yet implemented"),
+            0x00 | 0x08 | 0x09 => Ok(MbcType::MbcNone(MbcNone::new(ram_size))),
+            0x01..=0x03 => Ok(MbcType::Mbc1(Mbc1::new(rom_size, ram_size))),
+            _ => Err(CartridgeError::Unsupported(code)),
         }
     }
 }
 
 impl MemoryBank for MbcType {
-    fn read(&self, address: u16) -> MemoryRead {
+    fn map(&self, mmu: &Mmu) {
         match self {
-            MbcType::MbcNone(mbc) => mbc.read(address),
-            MbcType::Mbc1(_) => todo!(),
-            MbcType::Mbc2(_) => todo!(),
-            MbcType::Mbc3(_) => todo!(),
-            MbcType::Mbc5(_) => todo!(),
-            MbcType::Mbc6(_) => todo!(),
-            MbcType::Mbc7(_) => todo!(),
+            MbcType::MbcNone(mbc) => mbc.map(mmu),
+            MbcType::Mbc1(mbc) => mbc.map(mmu),
         }
     }
 
-    fn write(&self, address: u16, value: u8) -> MemoryWrite {
+    fn write(&mut self, address: u16, value: u8) -> MemoryWrite {
         match self {
             MbcType::MbcNone(mbc) => mbc.write(address, value),
-            MbcType::Mbc1(_) => todo!(),
-            MbcType::Mbc2(_) => todo!(),
-            MbcType::Mbc3(_) => todo!(),
-            MbcType::Mbc5(_) => todo!(),
-            MbcType::Mbc6(_) => todo!(),
-            MbcType::Mbc7(_) => todo!(),
+            MbcType::Mbc1(mbc) => mbc.write(address, value),
+        }
+    }
+
+    fn rom_bank(&self) -> u16 {
+        match self {
+            MbcType::MbcNone(mbc) => mbc.rom_bank(),
+            MbcType::Mbc1(mbc) => mbc.rom_bank(),
+        }
+    }
+
+    fn ram_bank(&self) -> u8 {
+        match self {
+            MbcType::MbcNone(mbc) => mbc.ram_bank(),
+            MbcType::Mbc1(mbc) => mbc.ram_bank(),
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        match self {
+            MbcType::MbcNone(mbc) => mbc.save_state(state),
+            MbcType::Mbc1(mbc) => mbc.save_state(state),
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        match self {
+            MbcType::MbcNone(mbc) => mbc.load_state(state),
+            MbcType::Mbc1(mbc) => mbc.load_state(state),
+        }
+    }
+
+    fn ram_enabled(&self) -> bool {
+        match self {
+            MbcType::MbcNone(mbc) => mbc.ram_enabled(),
+            MbcType::Mbc1(mbc) => mbc.ram_enabled(),
         }
     }
 }
@@ -62,43 +147,138 @@ impl fmt::Display for MbcType {
         let display_name = match self {
             MbcType::MbcNone(_) => "None",
             MbcType::Mbc1(_) => "MBC1",
-            MbcType::Mbc2(_) => "MBC2",
-            MbcType::Mbc3(_) => "MBC3",
-            MbcType::Mbc5(_) => "MBC5",
-            MbcType::Mbc6(_) => "MBC6",
-            MbcType::Mbc7(_) => "MBC7",
         };
         write!(f, "{}", display_name)
     }
 }
 
 struct MbcNone {
-    rom: Vec<u8>,
+    has_ram: bool,
 }
 
 impl MbcNone {
-    pub fn new(rom: Vec<u8>) -> Self {
-        Self { rom }
+    pub fn new(ram_size: usize) -> Self {
+        Self {
+            has_ram: ram_size > 0,
+        }
     }
 }
 
 impl MemoryBank for MbcNone {
-    fn read(&self, address: u16) -> MemoryRead {
-        if address <= 0x7FFF {
-            return MemoryRead::Replace(self.rom[address as usize]);
+    fn map(&self, mmu: &Mmu) {
+        mmu.map((0x0000, 0x7FFF), Backing::Rom(0));
+        if self.has_ram {
+            mmu.map((0xA000, 0xBFFF), Backing::CartRam(0));
+        } else {
+            mmu.map((0xA000, 0xBFFF), Backing::Unmapped);
         }
-        MemoryRead::Pass
     }
 
-    fn write(&self, address: u16, value: u8) -> MemoryWrite {
+    fn write(&mut self, address: u16, value: u8) -> MemoryWrite {
         match address {
-            0..=0x7FFF => {
-                return MemoryWrite::Block;
-            }
-            0xA000..=0xBFFF => return MemoryWrite::Pass,
+            0..=0x7FFF => MemoryWrite::Block,
             _ => unreachable!("Invalid memory write at address 0x{:04X}", address),
         }
     }
+
+    fn ram_enabled(&self) -> bool {
+        // Without an MBC the RAM, if any, is always accessible
+        self.has_ram
+    }
+}
+
+struct Mbc1 {
+    rom_banks: usize,
+    ram_banks: usize,
+    ram_enabled: bool,
+    bank1: u8, // 5 bits, 0x2000-0x3FFF
+    bank2: u8, // 2 bits, 0x4000-0x5FFF
+    mode: bool, // false: simple banking, true: advanced banking
+}
+
+impl Mbc1 {
+    pub fn new(rom_size: usize, ram_size: usize) -> Self {
+        Self {
+            rom_banks: (rom_size / ROM_BANK_SIZE).max(2),
+            ram_banks: ram_size.div_ceil(RAM_BANK_SIZE),
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+        }
+    }
+
+    fn low_rom_bank(&self) -> usize {
+        if self.mode {
+            ((self.bank2 as usize) << 5) % self.rom_banks
+        } else {
+            0
+        }
+    }
+
+    fn high_rom_bank(&self) -> usize {
+        ((self.bank2 as usize) << 5 | self.bank1 as usize) % self.rom_banks
+    }
+
+    fn selected_ram_bank(&self) -> usize {
+        if self.mode && self.ram_banks > 0 {
+            self.bank2 as usize % self.ram_banks
+        } else {
+            0
+        }
+    }
+}
+
+impl MemoryBank for Mbc1 {
+    fn map(&self, mmu: &Mmu) {
+        mmu.map((0x0000, 0x3FFF), Backing::Rom(self.low_rom_bank() * ROM_BANK_SIZE));
+        mmu.map((0x4000, 0x7FFF), Backing::Rom(self.high_rom_bank() * ROM_BANK_SIZE));
+        if self.ram_enabled && self.ram_banks > 0 {
+            let offset = self.selected_ram_bank() * RAM_BANK_SIZE;
+            mmu.map((0xA000, 0xBFFF), Backing::CartRam(offset));
+        } else {
+            mmu.map((0xA000, 0xBFFF), Backing::Unmapped);
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) -> MemoryWrite {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            // Bank 0 can't be selected in the switchable area
+            0x2000..=0x3FFF => self.bank1 = (value & 0x1F).max(1),
+            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            0x6000..=0x7FFF => self.mode = value & 0x01 != 0,
+            _ => unreachable!("Invalid MBC1 write at address 0x{:04X}", address),
+        }
+        MemoryWrite::Block
+    }
+
+    fn rom_bank(&self) -> u16 {
+        self.high_rom_bank() as u16
+    }
+
+    fn ram_bank(&self) -> u8 {
+        self.selected_ram_bank() as u8
+    }
+
+    fn ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.ram_enabled);
+        state.u8(self.bank1);
+        state.u8(self.bank2);
+        state.bool(self.mode);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.ram_enabled = state.bool()?;
+        self.bank1 = state.u8()?;
+        self.bank2 = state.u8()?;
+        self.mode = state.bool()?;
+        Ok(())
+    }
 }
 
 fn decode_string(data: &[u8]) -> String {
@@ -106,41 +286,68 @@ fn decode_string(data: &[u8]) -> String {
 }
 
 struct Cartridge {
+    rom: Rc<[u8]>,
     title: String,
     cgb: bool,
     cgb_only: bool,
     mbc: MbcType,
+    battery: bool,
     rom_size: u8,
     ram_size: u8,
 }
 
+/// Size in bytes of the cartridge RAM declared in the header
+fn ram_size_bytes(code: u8) -> usize {
+    match code {
+        0x1 => 0x800,
+        0x2 => 0x2000,
+        0x3 => 0x8000,
+        0x4 => 0x20000,
+        0x5 => 0x10000,
+        _ => 0,
+    }
+}
+
+/// Whether the cartridge type declares a battery keeping its RAM (or clock) powered
+fn has_battery(code: u8) -> bool {
+    matches!(
+        code,
+        0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x22 | 0xFF
+    )
+}
+
 fn checksum(rom: &[u8]) {
     println!("Should verify cartridge checksum");
 }
 
 impl Cartridge {
-    pub fn new(rom: Vec<u8>) -> Self {
+    pub fn new(rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooShort(rom.len()));
+        }
         let title = decode_string(&rom[0x134..=0x142]);
         let mbc_type = rom[0x147];
         let rom_size = rom[0x148];
         let ram_size = rom[0x149];
-        Self {
+        Ok(Self {
             title,
             cgb: is_bit_set!(rom[0x143], CGB),
             cgb_only: is_bit_set!(rom[0x143], CGB_ONLY),
-            mbc: MbcType::new(mbc_type, rom),
+            mbc: MbcType::new(mbc_type, rom.len(), ram_size_bytes(ram_size))?,
+            battery: has_battery(mbc_type),
+            rom: Rc::from(rom),
             rom_size,
             ram_size,
-        }
+        })
     }
 }
 
 impl MemoryHandler for Cartridge {
-    fn read(&self, mmu: &super::mmu::Mmu, address: u16) -> MemoryRead {
-        self.mbc.read(address)
+    fn read(&self, mmu: &Mmu, address: u16) -> MemoryRead {
+        MemoryRead::Pass
     }
 
-    fn write(&mut self, mmu: &super::mmu::Mmu, address: u16, value: u8) -> MemoryWrite {
+    fn write(&mut self, mmu: &Mmu, address: u16, value: u8) -> MemoryWrite {
         self.mbc.write(address, value)
     }
 }
@@ -177,52 +384,102 @@ impl fmt::Display for Cartridge {
 
 pub struct Mbc {
     cart: Cartridge,
-    boot_rom: Vec<u8>,
+    boot_rom: Rc<[u8]>,
     boot_rom_enabled: bool,
 }
 
 impl Mbc {
-    pub fn new(boot_rom: Option<Vec<u8>>, rom: Vec<u8>) -> Self {
-        let cart = Cartridge::new(rom);
+    pub fn new(boot_rom: Option<Vec<u8>>, rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        let cart = Cartridge::new(rom)?;
         println!("{}", cart);
 
-        match boot_rom {
+        Ok(match boot_rom {
             Some(boot_rom) => Self {
                 cart,
-                boot_rom: boot_rom,
+                boot_rom: Rc::from(boot_rom),
                 boot_rom_enabled: true,
             },
             None => Self {
                 cart,
-                boot_rom: Vec::with_capacity(0),
+                boot_rom: Rc::from([]),
                 boot_rom_enabled: false,
             },
+        })
+    }
+
+    /// The cartridge ROM, as validated when loading it
+    pub fn rom(&self) -> Rc<[u8]> {
+        self.cart.rom.clone()
+    }
+
+    pub fn bank_info(&self) -> BankInfo {
+        BankInfo {
+            mbc: self.cart.mbc.to_string(),
+            rom_bank: self.cart.mbc.rom_bank(),
+            ram_bank: self.cart.mbc.ram_bank(),
+            ram_enabled: self.cart.mbc.ram_enabled(),
+            boot_rom_mapped: self.boot_rom_enabled,
+        }
+    }
+
+    /// Whether the cartridge RAM survives power off and should be saved
+    pub fn has_battery(&self) -> bool {
+        self.cart.battery && ram_size_bytes(self.cart.ram_size) > 0
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.boot_rom_enabled);
+        self.cart.mbc.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.boot_rom_enabled = state.bool()?;
+        self.cart.mbc.load_state(state)
+    }
+
+    /// Hands the ROM images and cartridge RAM over to `mmu` and maps them
+    pub fn attach(&self, mmu: &mut Mmu) {
+        mmu.load_rom(self.cart.rom.clone());
+        mmu.load_boot_rom(self.boot_rom.clone());
+        mmu.load_cart_ram(vec![0; ram_size_bytes(self.cart.ram_size)]);
+        self.map(mmu);
+    }
+
+    fn map(&self, mmu: &Mmu) {
+        self.cart.mbc.map(mmu);
+        if !self.boot_rom_enabled {
+            return;
+        }
+        for page in (0..self.boot_rom.len()).step_by(0x100) {
+            if self.in_boot_rom(page as u16) {
+                mmu.map((page as u16, page as u16 + 0xFF), Backing::BootRom(page));
+            }
         }
     }
 
     #[inline]
     fn in_boot_rom(&self, address: u16) -> bool {
-        address < 0x100 || (self.boot_rom.len() == 0x900 && address >= 200 && address < 0x900)
+        // The CGB boot ROM leaves a hole for the cartridge header
+        address < 0x100 || (self.boot_rom.len() == 0x900 && (0x200..0x900).contains(&address))
     }
 }
 
 impl MemoryHandler for Mbc {
-    fn read(&self, mmu: &super::mmu::Mmu, address: u16) -> MemoryRead {
-        if self.boot_rom_enabled && self.in_boot_rom(address) {
-            return MemoryRead::Replace(self.boot_rom[address as usize]);
-        }
+    fn read(&self, mmu: &Mmu, address: u16) -> MemoryRead {
+        // ROM reads are served by the mappings
         self.cart.read(mmu, address)
     }
 
-    fn write(&mut self, mmu: &super::mmu::Mmu, address: u16, value: u8) -> MemoryWrite {
-        if self.boot_rom_enabled && self.in_boot_rom(address) {
-            eprintln!("Write to boot rom detected ?!");
-            return MemoryWrite::Block;
-        } else if address == 0xFF50 {
+    fn write(&mut self, mmu: &Mmu, address: u16, value: u8) -> MemoryWrite {
+        // The boot ROM only overlays reads, writes still reach the MBC
+        if address == 0xFF50 {
             self.boot_rom_enabled = false;
+            self.map(mmu);
             return MemoryWrite::Block;
         }
-        let write = self.cart.write(mmu, address, value);
-        write
+        let result = self.cart.write(mmu, address, value);
+        // The write may have switched banks
+        self.map(mmu);
+        result
     }
 }
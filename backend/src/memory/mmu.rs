@@ -1,4 +1,57 @@
-use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use crate::util::state::{StateError, StateReader, StateWriter};
+
+const PAGE_SIZE: usize = 0x100;
+const PAGES: usize = 0x100;
+const OAM_SIZE: usize = 0xA0;
+const VRAM: usize = 0x8000;
+const VRAM_SIZE: usize = 0x2000;
+/// CGB second VRAM bank, stored after the address space
+pub const VRAM_BANK_1: usize = 0x10000;
+const MEMORY_SIZE: usize = VRAM_BANK_1 + VRAM_SIZE;
+
+/// Bits of the I/O registers (0xFF00-0xFF7F) that always read as 1 on DMG: unused bits,
+/// write-only registers and unmapped addresses. High RAM and IE are fully readable.
+#[rustfmt::skip]
+const IO_UNUSED_BITS: [u8; PAGE_SIZE] = [
+    // P1, SB, SC, -, DIV, TIMA, TMA, TAC, -...-, IF
+    0xC0, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE0,
+    // NR10-NR14, -, NR21-NR24, NR30-NR34, -
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, 0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    // NR41-NR44, NR50-NR52, -
+    0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00, 0x70, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Wave RAM
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // LCDC, STAT, SCY, SCX, LY, LYC, DMA, BGP, OBP0, OBP1, WY, WX, CGB registers
+    0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Boot ROM disable, CGB registers
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // High RAM, IE
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Unused bits of the CGB registers, on CGB hardware only
+const CGB_IO_UNUSED_BITS: [(u16, u8); 6] = [
+    (0xFF02, 0x7C), // SC, with the clock speed
+    (0xFF4F, 0xFE), // VBK
+    (0xFF68, 0x40), // BCPS
+    (0xFF69, 0x00), // BCPD
+    (0xFF6A, 0x40), // OCPS
+    (0xFF6B, 0x00), // OCPD
+];
 
 pub enum MemoryRead {
     Replace(u8),
@@ -16,89 +69,379 @@ pub trait MemoryHandler {
     fn write(&mut self, mmu: &Mmu, address: u16, value: u8) -> MemoryWrite;
 }
 
-#[allow(unused)]
+/// Storage a page of the address space is mapped to, with the offset of the page in it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backing {
+    /// Internal memory (work RAM, video RAM, high RAM, I/O registers, ...)
+    Memory(usize),
+    /// Cartridge ROM, writes are ignored
+    Rom(usize),
+    /// Boot ROM, writes are ignored
+    BootRom(usize),
+    /// Cartridge RAM
+    CartRam(usize),
+    /// Nothing drives the bus: reads return 0xFF and writes are ignored
+    Unmapped,
+    /// Object attribute memory, followed by the unusable area (0xFEA0-0xFEFF)
+    /// which reads as 0 and ignores writes
+    Oam(usize),
+    /// I/O registers and high RAM, see `IO_UNUSED_BITS`
+    Io(usize),
+}
+
+impl Backing {
+    fn save_state(self, state: &mut StateWriter) {
+        let (kind, base) = match self {
+            Backing::Memory(base) => (0, base),
+            Backing::Rom(base) => (1, base),
+            Backing::BootRom(base) => (2, base),
+            Backing::CartRam(base) => (3, base),
+            Backing::Unmapped => (4, 0),
+            Backing::Oam(base) => (5, base),
+            Backing::Io(base) => (6, base),
+        };
+        state.u8(kind);
+        state.u32(base as u32);
+    }
+
+    fn load_state(state: &mut StateReader) -> Result<Backing, StateError> {
+        let kind = state.u8()?;
+        let base = state.u32()? as usize;
+        match kind {
+            0 if base + PAGE_SIZE <= MEMORY_SIZE => Ok(Backing::Memory(base)),
+            1 => Ok(Backing::Rom(base)),
+            2 => Ok(Backing::BootRom(base)),
+            3 => Ok(Backing::CartRam(base)),
+            4 => Ok(Backing::Unmapped),
+            5 if base + PAGE_SIZE <= MEMORY_SIZE => Ok(Backing::Oam(base)),
+            6 if base + PAGE_SIZE <= MEMORY_SIZE => Ok(Backing::Io(base)),
+            _ => Err(StateError::NotAState),
+        }
+    }
+
+    fn offset(self, offset: usize) -> Backing {
+        match self {
+            Backing::Memory(base) => Backing::Memory(base + offset),
+            Backing::Rom(base) => Backing::Rom(base + offset),
+            Backing::BootRom(base) => Backing::BootRom(base + offset),
+            Backing::CartRam(base) => Backing::CartRam(base + offset),
+            Backing::Unmapped => Backing::Unmapped,
+            Backing::Oam(base) => Backing::Oam(base + offset),
+            Backing::Io(base) => Backing::Io(base + offset),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Page {
+    backing: Backing,
+    // Whether accesses have to go through the handlers registered on the page
+    dispatch_reads: bool,
+    dispatch_writes: bool,
+}
+
+/// Identifies a registered handler, to remove it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HandlerId(u32);
+
+struct HandlerEntry {
+    id: HandlerId,
+    start: u16,
+    end: u16,
+    reads: bool,
+    handler: Rc<RefCell<dyn MemoryHandler>>,
+}
+
+/// The address space is split in 256 pages of 256 bytes, each one mapped directly to a
+/// backing store. Handlers are only consulted on the pages they are registered on, so
+/// plain ROM and RAM accesses never leave the page table.
 pub struct Mmu {
-    pub handlers: BTreeMap<u16, Vec<Rc<RefCell<dyn MemoryHandler>>>>,
-    memory: [u8; 0xffff],
+    pages: [Cell<Page>; PAGES],
+    handlers: Vec<Vec<HandlerEntry>>,
+    next_handler: u32,
+    // Boxed to keep systems small enough to move around on the stack
+    memory: Box<[u8; MEMORY_SIZE]>,
+    io_unused_bits: [u8; PAGE_SIZE],
+    rom: Rc<[u8]>,
+    boot_rom: Rc<[u8]>,
+    cart_ram: Vec<u8>,
     pub interrupts_enable: u8,
     pub interrupts_flags: u8,
 }
 
 impl Mmu {
     pub fn new() -> Mmu {
+        let pages = std::array::from_fn(|page| {
+            let address = page * PAGE_SIZE;
+            let backing = match address {
+                // echo ram
+                0xE000..=0xFDFF => Backing::Memory(address - 0x2000),
+                0xFE00 => Backing::Oam(address),
+                0xFF00 => Backing::Io(address),
+                _ => Backing::Memory(address),
+            };
+            Cell::new(Page {
+                backing,
+                dispatch_reads: false,
+                dispatch_writes: false,
+            })
+        });
         Mmu {
-            handlers: BTreeMap::new(),
-            memory: [0; 0xffff],
+            pages,
+            handlers: (0..PAGES).map(|_| Vec::new()).collect(),
+            next_handler: 0,
+            memory: Box::new([0; MEMORY_SIZE]),
+            io_unused_bits: IO_UNUSED_BITS,
+            rom: Rc::from([]),
+            boot_rom: Rc::from([]),
+            cart_ram: Vec::new(),
             interrupts_enable: 0,
             interrupts_flags: 0,
         }
     }
 
+    pub fn load_rom(&mut self, rom: Rc<[u8]>) {
+        self.rom = rom;
+    }
+
+    pub fn load_boot_rom(&mut self, boot_rom: Rc<[u8]>) {
+        self.boot_rom = boot_rom;
+    }
+
+    pub fn load_cart_ram(&mut self, ram: Vec<u8>) {
+        self.cart_ram = ram;
+    }
+
+    /// Makes the CGB registers readable
+    pub fn enable_cgb_registers(&mut self) {
+        for (address, bits) in CGB_IO_UNUSED_BITS {
+            self.io_unused_bits[address as usize % PAGE_SIZE] = bits;
+        }
+    }
+
+    /// Reads `bank` of VRAM directly, whatever bank is mapped
+    #[inline]
+    pub fn vram(&self, bank: u8, address: u16) -> u8 {
+        let base = if bank == 0 { VRAM } else { VRAM_BANK_1 };
+        self.memory[base + (address as usize - VRAM) % VRAM_SIZE]
+    }
+
+    pub fn cart_ram(&self) -> &[u8] {
+        &self.cart_ram
+    }
+
+    /// Writes cartridge RAM directly, whatever bank is mapped, out of range writes are ignored
+    pub fn write_cart_ram(&mut self, offset: usize, value: u8) {
+        if let Some(byte) = self.cart_ram.get_mut(offset) {
+            *byte = value;
+        }
+    }
+
+    /// Saves the memory contents and the mappings, the handlers are part of the devices
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(self.memory.as_slice());
+        state.buffer(&self.cart_ram);
+        for page in self.pages.iter() {
+            page.get().backing.save_state(state);
+        }
+        state.u8(self.interrupts_enable);
+        state.u8(self.interrupts_flags);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.memory.copy_from_slice(state.bytes(MEMORY_SIZE)?);
+        let cart_ram = state.buffer()?;
+        if cart_ram.len() != self.cart_ram.len() {
+            return Err(StateError::OtherSystem);
+        }
+        self.cart_ram.copy_from_slice(cart_ram);
+        for page in self.pages.iter() {
+            let mut entry = page.get();
+            entry.backing = Backing::load_state(state)?;
+            page.set(entry);
+        }
+        self.interrupts_enable = state.u8()?;
+        self.interrupts_flags = state.u8()?;
+        Ok(())
+    }
+
+    /// Maps the pages of `address_range` (which must be page aligned) to consecutive
+    /// pages of `backing`. Handlers registered on the range are kept.
+    pub fn map(&self, address_range: (u16, u16), backing: Backing) {
+        let (start, end) = (address_range.0 as usize, address_range.1 as usize);
+        assert!(
+            start % PAGE_SIZE == 0 && end % PAGE_SIZE == PAGE_SIZE - 1,
+            "Unaligned mapping 0x{:04X}-0x{:04X}",
+            start,
+            end
+        );
+        for (i, page) in (start / PAGE_SIZE..=end / PAGE_SIZE).enumerate() {
+            let mut entry = self.pages[page].get();
+            entry.backing = backing.offset(i * PAGE_SIZE);
+            self.pages[page].set(entry);
+        }
+    }
+
     pub fn add_handler<T: MemoryHandler + 'static>(
         &mut self,
         address_range: (u16, u16),
         handler: T,
-    ) {
-        let handler = Rc::new(RefCell::new(handler));
-        for address in address_range.0..=address_range.1 {
-            if self.handlers.contains_key(&address) {
-                match self.handlers.get_mut(&address) {
-                    Some(handler_list) => handler_list.push(handler.clone()),
-                    None => {}
-                }
+    ) -> HandlerId {
+        self.register(address_range, Rc::new(RefCell::new(handler)), true, false)
+    }
+
+    /// Same as `add_handler`, but only writes are dispatched to the handler
+    /// (used for control registers overlaid on ROM, reads are served by the mapping)
+    pub fn add_write_handler<T: MemoryHandler + 'static>(
+        &mut self,
+        address_range: (u16, u16),
+        handler: T,
+    ) -> HandlerId {
+        self.register(address_range, Rc::new(RefCell::new(handler)), false, false)
+    }
+
+    /// Same as `add_handler`, but the handler is consulted before any handler
+    /// already registered for the range (used by spies that must see every access)
+    pub fn insert_handler<T: MemoryHandler + 'static>(
+        &mut self,
+        address_range: (u16, u16),
+        handler: T,
+    ) -> HandlerId {
+        self.register(address_range, Rc::new(RefCell::new(handler)), true, true)
+    }
+
+    fn register(
+        &mut self,
+        address_range: (u16, u16),
+        handler: Rc<RefCell<dyn MemoryHandler>>,
+        reads: bool,
+        front: bool,
+    ) -> HandlerId {
+        let id = HandlerId(self.next_handler);
+        self.next_handler += 1;
+        let (start, end) = address_range;
+        for page in (start as usize / PAGE_SIZE)..=(end as usize / PAGE_SIZE) {
+            let entry = HandlerEntry {
+                id,
+                start,
+                end,
+                reads,
+                handler: handler.clone(),
+            };
+            if front {
+                self.handlers[page].insert(0, entry);
             } else {
-                self.handlers.insert(address, vec![handler.clone()]);
+                self.handlers[page].push(entry);
             }
+            let mut flags = self.pages[page].get();
+            flags.dispatch_reads |= reads;
+            flags.dispatch_writes = true;
+            self.pages[page].set(flags);
         }
+        id
     }
 
-    pub fn read(&self, addr: u16) -> u8 {
-        match self.handlers.get(&addr) {
-            Some(handlers) => {
-                for handler in handlers {
-                    match handler.borrow().read(self, addr) {
-                        MemoryRead::Replace(value) => return value,
-                        MemoryRead::Pass => {}
-                    }
-                }
+    /// Unregisters the handler `id` from all its pages, the pages left without handlers
+    /// are accessed directly again
+    pub fn remove_handler(&mut self, id: HandlerId) {
+        for (page, entries) in self.handlers.iter_mut().enumerate() {
+            let count = entries.len();
+            entries.retain(|entry| entry.id != id);
+            if entries.len() != count {
+                let mut flags = self.pages[page].get();
+                flags.dispatch_reads = entries.iter().any(|entry| entry.reads);
+                flags.dispatch_writes = !entries.is_empty();
+                self.pages[page].set(flags);
             }
-            None => {
-                // #[cfg(feature="debug")]
-                // println!("[MMU] No explicit handler for address 0x{:04x}", addr);
+        }
+    }
+
+    #[inline]
+    pub fn read(&self, addr: u16) -> u8 {
+        let page = self.pages[addr as usize / PAGE_SIZE].get();
+        let offset = addr as usize % PAGE_SIZE;
+        if page.dispatch_reads {
+            if let Some(value) = self.dispatch_read(addr) {
+                return match page.backing {
+                    Backing::Io(_) => value | self.io_unused_bits[offset],
+                    _ => value,
+                };
             }
-        };
+        }
+        self.read_backing(addr)
+    }
 
-        match addr {
-            // echo ram read
-            0xE000..=0xFDFF => self.memory[(addr - 0x2000) as usize],
-            // normal ram read
-            _ => self.memory[addr as usize],
+    /// Reads what `addr` is mapped to, without the handlers
+    #[inline]
+    pub fn read_backing(&self, addr: u16) -> u8 {
+        let page = self.pages[addr as usize / PAGE_SIZE].get();
+        let offset = addr as usize % PAGE_SIZE;
+        match page.backing {
+            Backing::Memory(base) => self.memory[base + offset],
+            Backing::Rom(base) => self.rom.get(base + offset).copied().unwrap_or(0xFF),
+            Backing::BootRom(base) => self.boot_rom.get(base + offset).copied().unwrap_or(0xFF),
+            Backing::CartRam(base) => self.cart_ram.get(base + offset).copied().unwrap_or(0xFF),
+            Backing::Unmapped => 0xFF,
+            Backing::Oam(_) if offset >= OAM_SIZE => 0x00,
+            Backing::Oam(base) => self.memory[base + offset],
+            Backing::Io(base) => self.memory[base + offset] | self.io_unused_bits[offset],
         }
     }
 
+    #[inline]
     pub fn write(&mut self, addr: u16, value: u8) {
-        match self.handlers.get(&addr) {
-            Some(handlers) => {
-                for handler in handlers {
-                    match handler.borrow_mut().write(self, addr, value) {
-                        MemoryWrite::Replace(v) => {
-                            self.memory[addr as usize] = v;
-                            return;
-                        }
-                        MemoryWrite::Pass => (),
-                        MemoryWrite::Block => return,
-                    }
+        let page = self.pages[addr as usize / PAGE_SIZE].get();
+        let mut value = value;
+        if page.dispatch_writes {
+            match self.dispatch_write(addr, value) {
+                MemoryWrite::Replace(v) => value = v,
+                MemoryWrite::Pass => (),
+                MemoryWrite::Block => return,
+            }
+        }
+        // The handlers may have remapped the page
+        let page = self.pages[addr as usize / PAGE_SIZE].get();
+        let offset = addr as usize % PAGE_SIZE;
+        match page.backing {
+            Backing::Memory(base) | Backing::Io(base) => self.memory[base + offset] = value,
+            Backing::Oam(base) if offset < OAM_SIZE => self.memory[base + offset] = value,
+            Backing::CartRam(base) => {
+                if let Some(byte) = self.cart_ram.get_mut(base + offset) {
+                    *byte = value;
                 }
             }
-            None => (),
-            // None => println!("[MMU] No explicit handler for address 0x{:04x}", addr),
-        };
+            Backing::Oam(_) | Backing::Rom(_) | Backing::BootRom(_) | Backing::Unmapped => (),
+        }
+    }
+
+    fn dispatch_read(&self, addr: u16) -> Option<u8> {
+        for entry in self.handlers[addr as usize / PAGE_SIZE].iter() {
+            if !entry.reads || addr < entry.start || addr > entry.end {
+                continue;
+            }
+            // A handler reading memory from its own callback is already borrowed: it is
+            // skipped, so it sees what is behind it
+            if let Ok(handler) = entry.handler.try_borrow() {
+                if let MemoryRead::Replace(value) = handler.read(self, addr) {
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
 
-        match addr {
-            // echo ram write
-            0xE000..=0xFDFF => self.memory[(addr - 0x2000) as usize] = value,
-            // normal ram write
-            _ => self.memory[addr as usize] = value,
+    fn dispatch_write(&self, addr: u16, value: u8) -> MemoryWrite {
+        for entry in self.handlers[addr as usize / PAGE_SIZE].iter() {
+            if addr < entry.start || addr > entry.end {
+                continue;
+            }
+            // Handlers only get `&Mmu`, so writes never nest and no handler is borrowed
+            let mut handler = entry.handler.borrow_mut();
+            match handler.write(self, addr, value) {
+                MemoryWrite::Pass => (),
+                result => return result,
+            }
         }
+        MemoryWrite::Pass
     }
 }
@@ -1,3 +1,5 @@
+pub mod cheats;
+pub mod joypad;
 pub mod mmu;
 pub mod mbc;
 pub mod serial;
\ No newline at end of file
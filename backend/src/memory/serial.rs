@@ -1,7 +1,10 @@
 use super::mmu::{MemoryHandler, MemoryRead, MemoryWrite, Mmu};
+use crate::link::{Disconnected, SerialLink};
+use crate::util::state::{StateError, StateReader, StateWriter};
 use crate::{cpu::interrupt::InterruptRequest, is_bit_set};
 
 const CYCLES_TO_SEND: u32 = 512 * 8; // 8192Hz clock => 512 cpu cycles * 8 bits
+const CYCLES_TO_SEND_FAST: u32 = 16 * 8; // CGB 262144Hz clock => 16 cpu cycles * 8 bits
 const CLOCK_SELECT: u8 = 0;
 const CLOCK_SPEED: u8 = 1;
 const TRANSFER_ENABLE: u8 = 7;
@@ -9,16 +12,17 @@ const TRANSFER_ENABLE: u8 = 7;
 pub struct Serial {
     interrupt_request: InterruptRequest,
     sb: u8,                 // Next byte
-    recv: u8,               // Received btye
+    link: Box<dyn SerialLink>,
     transfer_enable: bool,  // true if there is an ongoing or pending transfer
     clock_speed: bool,      // CGB only: false: normal, true: fast
     clock_select: bool,     // false: external clock, true : internal
     clock: u32,             // clock timer
     log: String,
+    cgb: bool,
 }
 
 impl Serial {
-    pub fn new(interrupt_request: InterruptRequest) -> Self {
+    pub fn new(interrupt_request: InterruptRequest, cgb: bool) -> Self {
         Self {
             interrupt_request,
             sb: 0x0,
@@ -27,10 +31,16 @@ impl Serial {
             clock_select: true,
             clock: 0,
             log: String::with_capacity(150),
-            recv: 0,
+            link: Box::new(Disconnected),
+            cgb,
         }
     }
 
+    /// Plugs `link` in the serial port, replacing the current one
+    pub fn set_link(&mut self, link: Box<dyn SerialLink>) {
+        self.link = link;
+    }
+
     pub fn step(&mut self, elapsed_cycles: u16) {
         if !self.transfer_enable {
             return;
@@ -39,25 +49,69 @@ impl Serial {
         // Master
         if self.clock_select {
             self.clock += elapsed_cycles as u32;
+            let cycles_to_send = if self.clock_speed {
+                CYCLES_TO_SEND_FAST
+            } else {
+                CYCLES_TO_SEND
+            };
             // Transfer done
-            if self.clock >= CYCLES_TO_SEND {
+            if self.clock >= cycles_to_send {
                 #[cfg(feature="debug")]
                 println!("Serial transfer done");
-                self.sb = self.recv;
+                self.sb = self.link.exchange(self.sb);
+                self.clock = 0;
                 self.transfer_enable = false;
                 self.interrupt_request.serial(true);
             }
         }
-        // Slave
-        else {
-            todo!("Implement serial transfer for slave");
+        // Slave: the transfer only ends when the peer clocks it, maybe never
+        else if let Some(received) = self.link.poll(self.sb) {
+            #[cfg(feature="debug")]
+            println!("Serial transfer done (external clock)");
+            self.sb = received;
+            self.transfer_enable = false;
+            self.interrupt_request.serial(true);
         }
     }
 
+    /// Saves the transfer in progress, not the link nor the output
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.sb);
+        state.bool(self.transfer_enable);
+        state.bool(self.clock_speed);
+        state.bool(self.clock_select);
+        state.u32(self.clock);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), StateError> {
+        self.sb = state.u8()?;
+        self.transfer_enable = state.bool()?;
+        self.clock_speed = state.bool()?;
+        self.clock_select = state.bool()?;
+        self.clock = state.u32()?;
+        Ok(())
+    }
+
+    /// Every byte sent with the internal clock so far
+    pub fn output(&self) -> &str {
+        &self.log
+    }
+
     fn set_sc(&mut self, value: u8) {
+        let was_waiting = self.waiting_for_clock();
         self.transfer_enable = is_bit_set!(value, TRANSFER_ENABLE);
-        self.clock_speed = is_bit_set!(value, CLOCK_SPEED);
+        // Only the CGB has the fast clock
+        self.clock_speed = self.cgb && is_bit_set!(value, CLOCK_SPEED);
         self.clock_select = is_bit_set!(value, CLOCK_SELECT);
+        // Otherwise the peer could still clock out the byte offered by the last poll
+        if was_waiting && !self.waiting_for_clock() {
+            self.link.cancel();
+        }
+    }
+
+    /// A transfer with the external clock is in progress
+    fn waiting_for_clock(&self) -> bool {
+        self.transfer_enable && !self.clock_select
     }
 
     fn get_sc(&self) -> u8 {
@@ -85,11 +139,9 @@ impl MemoryHandler for Serial {
             }
             0xFF02 => {
                 self.set_sc(value);
-                // TODO : abstract byte sending to a handler (network or other)
-                // For now, just log the byte
-                if self.transfer_enable {
+                if self.transfer_enable && self.clock_select {
                     self.log.push(self.sb as char);
-                    println!("{}", self.log);
+                    self.clock = 0;
                 }
             }
             _ => unreachable!("Invalid serial write : 0x{:04X}", address),
@@ -0,0 +1,119 @@
+//! BizHawk movies: a zip archive with a `Header.txt` of `key value` lines and an
+//! `Input Log.txt` listing the buttons of each frame, like `|UDLRSsBAP|`, a `.` for each
+//! released one. The ROM is identified by its SHA-1, which isn't checked.
+
+use super::{Movie, MovieError};
+use crate::system::{Buttons, System};
+use crate::util::zip::Archive;
+
+pub const MAGIC: &[u8; 4] = b"PK\x03\x04";
+const HEADER: &str = "Header.txt";
+const INPUT_LOG: &str = "Input Log.txt";
+// Of the Gambatte core, when the log doesn't name its columns
+const DEFAULT_LOG_KEY: &str = "#Up|Down|Left|Right|Start|Select|B|A|Power|";
+
+fn read_text(archive: &Archive, name: &str) -> Result<String, MovieError> {
+    let entry = archive
+        .find(name)
+        .ok_or_else(|| MovieError::Invalid(format!("no {}", name)))?;
+    let data = archive
+        .read(entry)
+        .map_err(|e| MovieError::Invalid(e.to_string()))?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    header.lines().find_map(|line| {
+        let (line_key, value) = line.trim().split_once(' ')?;
+        line_key.eq_ignore_ascii_case(key).then(|| value.trim())
+    })
+}
+
+fn is_true(value: Option<&str>) -> bool {
+    matches!(value, Some(value) if value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+/// Button of a log column, `None` for the power button and the others we don't have
+fn button(name: &str) -> Option<Buttons> {
+    // Multiplayer cores prefix the buttons with the player
+    let name = name.strip_prefix("P1 ").unwrap_or(name);
+    Some(match name {
+        "Up" => Buttons::UP,
+        "Down" => Buttons::DOWN,
+        "Left" => Buttons::LEFT,
+        "Right" => Buttons::RIGHT,
+        "Start" => Buttons::START,
+        "Select" => Buttons::SELECT,
+        "B" => Buttons::B,
+        "A" => Buttons::A,
+        _ => return None,
+    })
+}
+
+fn is_power(name: &str) -> bool {
+    name.strip_prefix("P1 ").unwrap_or(name) == "Power"
+}
+
+fn parse_inputs(log: &str) -> Result<Vec<Buttons>, MovieError> {
+    let key = log
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("LogKey:"))
+        .unwrap_or(DEFAULT_LOG_KEY);
+    let columns: Vec<&str> = key
+        .trim_start_matches('#')
+        .split(['|', '#'])
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    let mut inputs = Vec::new();
+    for line in log
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('|'))
+    {
+        let values: Vec<char> = line.chars().filter(|&c| c != '|').collect();
+        if values.len() != columns.len() {
+            return Err(MovieError::Invalid(format!(
+                "frame {} has {} inputs instead of {}",
+                inputs.len(),
+                values.len(),
+                columns.len()
+            )));
+        }
+        let mut buttons = Buttons::NONE;
+        for (name, value) in columns.iter().zip(values) {
+            if value == '.' || value == ' ' {
+                continue;
+            }
+            if let Some(button) = button(name) {
+                buttons |= button;
+            } else if is_power(name) && !inputs.is_empty() {
+                return Err(MovieError::Unsupported("resets during the movie"));
+            }
+        }
+        inputs.push(buttons);
+    }
+    Ok(inputs)
+}
+
+pub fn import(data: &[u8], system: &mut System) -> Result<Movie, MovieError> {
+    let archive = Archive::new(data).map_err(|e| MovieError::Invalid(e.to_string()))?;
+    let header = read_text(&archive, HEADER)?;
+    if is_true(header_value(&header, "StartsFromSavestate")) {
+        return Err(MovieError::Unsupported("starts from a save state"));
+    }
+    if is_true(header_value(&header, "StartsFromSaveRam")) {
+        return Err(MovieError::Unsupported("starts from a battery save"));
+    }
+    let platform = header_value(&header, "Platform").unwrap_or_default();
+    let cgb = match platform {
+        "GB" | "SGB" => is_true(header_value(&header, "IsCGBMode")),
+        "GBC" => true,
+        _ => return Err(MovieError::OtherSystem),
+    };
+    let rerecords = header_value(&header, "rerecordCount")
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+    let inputs = parse_inputs(&read_text(&archive, INPUT_LOG)?)?;
+    Movie::imported(system, cgb, &[], inputs, rerecords)
+}
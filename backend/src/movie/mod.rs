@@ -0,0 +1,319 @@
+//! Joypad input recorded frame by frame, replayed bit identically.
+//!
+//! A movie starts either at power on or from a save state, and holds the buttons pressed
+//! during each frame of `System::run_frame`. Nothing in a `System` depends on the host (there
+//! is no real time clock), so replaying the inputs from the same start always leads to the
+//! same state, as long as nothing is plugged in the serial port.
+//!
+//! Rerecording loads a save state taken during the recording, drops the inputs after it and
+//! goes on recording from there, the rerecord count tells how many times it happened.
+//!
+//! Movies of BizHawk (`.bk2`) and VisualBoyAdvance (`.vbm`) can be imported, each of their
+//! frames becoming a frame of `System::run_frame`.
+
+mod bk2;
+mod vbm;
+
+use std::fmt;
+
+use crate::system::{Buttons, Model, System, CYCLES_PER_FRAME};
+use crate::util::crc32;
+use crate::util::state::{StateError, StateReader, StateWriter};
+
+const MAGIC: &[u8; 4] = b"GBMV";
+const VERSION: u8 = 1;
+
+const POWER_ON: u8 = 0;
+const SAVE_STATE: u8 = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MovieError {
+    /// The data doesn't start like a movie
+    NotAMovie,
+    /// Recorded by another version of the emulator
+    Version(u8),
+    /// Recorded with another model or cartridge
+    OtherSystem,
+    /// The data ends before the movie does
+    Truncated,
+    /// The system isn't at the power on the movie starts from, another boot ROM was used
+    StartMismatch,
+    /// The save state can't be loaded
+    State(StateError),
+    /// The save state was taken before the start or after the end of the movie
+    NotInMovie,
+    /// The imported movie relies on something we can't reproduce
+    Unsupported(&'static str),
+    /// The imported movie is malformed
+    Invalid(String),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovieError::NotAMovie => write!(f, "not a movie"),
+            MovieError::Version(version) => write!(f, "unsupported movie version {}", version),
+            MovieError::OtherSystem => write!(f, "movie of another model or cartridge"),
+            MovieError::Truncated => write!(f, "truncated movie"),
+            MovieError::StartMismatch => {
+                write!(
+                    f,
+                    "the system doesn't power on like when the movie was recorded"
+                )
+            }
+            MovieError::State(e) => write!(f, "{}", e),
+            MovieError::NotInMovie => write!(f, "save state taken outside of the movie"),
+            MovieError::Unsupported(feature) => write!(f, "unsupported movie, {}", feature),
+            MovieError::Invalid(message) => write!(f, "invalid movie: {}", message),
+        }
+    }
+}
+
+impl From<StateError> for MovieError {
+    fn from(e: StateError) -> Self {
+        MovieError::State(e)
+    }
+}
+
+/// Where the movie starts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Start {
+    /// From a system just powered on, with its battery backed RAM
+    PowerOn,
+    /// From the current state of the system
+    SaveState,
+}
+
+enum StartData {
+    PowerOn {
+        save: Vec<u8>,
+        // Of the state at power on, tells the boot ROM and the save were the same
+        checksum: u32,
+    },
+    SaveState(Vec<u8>),
+}
+
+pub struct Movie {
+    model: Model,
+    rom_checksum: u32,
+    start_cycles: u64,
+    start: StartData,
+    rerecords: u32,
+    inputs: Vec<Buttons>,
+}
+
+impl Movie {
+    /// An empty movie starting at the current state of `system`, which must have just been
+    /// powered on for `Start::PowerOn`
+    pub fn new(system: &System, start: Start) -> Movie {
+        let state = system.save_state();
+        let start = match start {
+            Start::PowerOn => StartData::PowerOn {
+                save: system.save_ram().unwrap_or_default(),
+                checksum: crc32::crc32(&state),
+            },
+            Start::SaveState => StartData::SaveState(state),
+        };
+        Movie {
+            model: system.model(),
+            rom_checksum: system.rom_checksum(),
+            start_cycles: system.cycles(),
+            start,
+            rerecords: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn start(&self) -> Start {
+        match self.start {
+            StartData::PowerOn { .. } => Start::PowerOn,
+            StartData::SaveState(_) => Start::SaveState,
+        }
+    }
+
+    /// Frames recorded
+    pub fn len(&self) -> u64 {
+        self.inputs.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn rerecords(&self) -> u32 {
+        self.rerecords
+    }
+
+    pub fn inputs(&self) -> &[Buttons] {
+        &self.inputs
+    }
+
+    /// Brings `system` to the start of the movie, a system just powered on with the model and
+    /// cartridge of the movie
+    pub fn rewind(&self, system: &mut System) -> Result<(), MovieError> {
+        if system.model() != self.model || system.rom_checksum() != self.rom_checksum {
+            return Err(MovieError::OtherSystem);
+        }
+        match &self.start {
+            StartData::PowerOn { save, checksum } => {
+                if system.save_ram().is_some() {
+                    system.load_save_ram(save);
+                }
+                if crc32::crc32(&system.save_state()) != *checksum {
+                    return Err(MovieError::StartMismatch);
+                }
+            }
+            StartData::SaveState(state) => system.load_state(state)?,
+        }
+        Ok(())
+    }
+
+    /// The frame of the movie `system` is at, the number of frames run since its start
+    pub fn frame(&self, system: &System) -> u64 {
+        (system.cycles() / CYCLES_PER_FRAME).saturating_sub(self.start_cycles / CYCLES_PER_FRAME)
+    }
+
+    /// Runs a frame of `system` holding `buttons` and records it, replacing the inputs recorded
+    /// after the current frame if any
+    pub fn record(&mut self, system: &mut System, buttons: Buttons) {
+        let frame = self.frame(system) as usize;
+        self.inputs.truncate(frame);
+        self.inputs.push(buttons);
+        system.run_frame(buttons);
+    }
+
+    /// Loads `state`, taken during the recording, to record again from there
+    pub fn rerecord(&mut self, system: &mut System, state: &[u8]) -> Result<(), MovieError> {
+        system.load_state(state)?;
+        let frame = self.frame(system);
+        if system.cycles() < self.start_cycles || frame > self.len() {
+            return Err(MovieError::NotInMovie);
+        }
+        self.inputs.truncate(frame as usize);
+        self.rerecords += 1;
+        Ok(())
+    }
+
+    /// Runs the next frame of the movie on `system`, returns false once the movie has ended
+    pub fn play_frame(&self, system: &mut System) -> bool {
+        match self.inputs.get(self.frame(system) as usize) {
+            Some(buttons) => {
+                system.run_frame(*buttons);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = StateWriter::new();
+        data.bytes(MAGIC);
+        data.u8(VERSION);
+        data.u8(self.model as u8);
+        data.u32(self.rom_checksum);
+        data.u64(self.start_cycles);
+        match &self.start {
+            StartData::PowerOn { save, checksum } => {
+                data.u8(POWER_ON);
+                data.buffer(save);
+                data.u32(*checksum);
+            }
+            StartData::SaveState(state) => {
+                data.u8(SAVE_STATE);
+                data.buffer(state);
+            }
+        }
+        data.u32(self.rerecords);
+        let inputs: Vec<u8> = self.inputs.iter().map(|buttons| buttons.0).collect();
+        data.buffer(&inputs);
+        data.into_bytes()
+    }
+
+    /// Reads a movie written by `to_bytes`, or imports a BizHawk or VisualBoyAdvance one which
+    /// then starts at the power on of `system`
+    pub fn import(data: &[u8], system: &mut System) -> Result<Movie, MovieError> {
+        if data.starts_with(MAGIC) {
+            Movie::from_bytes(data)
+        } else if data.starts_with(vbm::MAGIC) {
+            vbm::import(data, system)
+        } else if data.starts_with(bk2::MAGIC) {
+            bk2::import(data, system)
+        } else {
+            Err(MovieError::NotAMovie)
+        }
+    }
+
+    /// A movie of `inputs` recorded by another emulator from the power on of `system`, with
+    /// the battery save `save`, empty for a new game
+    fn imported(
+        system: &mut System,
+        cgb: bool,
+        save: &[u8],
+        inputs: Vec<Buttons>,
+        rerecords: u32,
+    ) -> Result<Movie, MovieError> {
+        if system.model().is_cgb() != cgb {
+            return Err(MovieError::OtherSystem);
+        }
+        if let Some(ram) = system.save_ram() {
+            system.load_save_ram(&vec![0; ram.len()]);
+            system.load_save_ram(save);
+        }
+        let mut movie = Movie::new(system, Start::PowerOn);
+        movie.inputs = inputs;
+        movie.rerecords = rerecords;
+        Ok(movie)
+    }
+
+    /// Reads back a movie written by `to_bytes`
+    pub fn from_bytes(data: &[u8]) -> Result<Movie, MovieError> {
+        let mut data = StateReader::new(data);
+        if data.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(MovieError::NotAMovie);
+        }
+        // The reader only fails when the data ends early
+        Movie::read(&mut data).map_err(|e| match e {
+            MovieError::State(StateError::Truncated) => MovieError::Truncated,
+            e => e,
+        })
+    }
+
+    fn read(data: &mut StateReader) -> Result<Movie, MovieError> {
+        let version = data.u8()?;
+        if version != VERSION {
+            return Err(MovieError::Version(version));
+        }
+        let model = Model::ALL
+            .get(data.u8()? as usize)
+            .copied()
+            .ok_or(MovieError::OtherSystem)?;
+        let rom_checksum = data.u32()?;
+        let start_cycles = data.u64()?;
+        let start = match data.u8()? {
+            POWER_ON => StartData::PowerOn {
+                save: data.buffer()?.to_vec(),
+                checksum: data.u32()?,
+            },
+            SAVE_STATE => StartData::SaveState(data.buffer()?.to_vec()),
+            _ => return Err(MovieError::NotAMovie),
+        };
+        let rerecords = data.u32()?;
+        let inputs = data
+            .buffer()?
+            .iter()
+            .map(|&buttons| Buttons(buttons))
+            .collect();
+        Ok(Movie {
+            model,
+            rom_checksum,
+            start_cycles,
+            start,
+            rerecords,
+            inputs,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+//! VisualBoyAdvance movies: a 64 bytes header, then 2 bytes of input per frame and controller
+
+use super::{Movie, MovieError};
+use crate::system::{Buttons, System};
+
+pub const MAGIC: &[u8; 4] = b"VBM\x1A";
+const VERSION: u32 = 1;
+const HEADER_LENGTH: usize = 0x40;
+
+// Movie start flags
+const FROM_SNAPSHOT: u8 = 1 << 0;
+const FROM_SRAM: u8 = 1 << 1;
+// System flags
+const GBA: u8 = 1 << 0;
+const GBC: u8 = 1 << 1;
+
+// Input bits above the buttons, A B Select Start Right Left Up Down in the low byte
+const RESET: u16 = 1 << 11;
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// VBM buttons have the actions in the low nibble and the directions in the high one
+fn buttons(input: u16) -> Buttons {
+    Buttons((input as u8).rotate_left(4))
+}
+
+pub fn import(data: &[u8], system: &mut System) -> Result<Movie, MovieError> {
+    if data.len() < HEADER_LENGTH {
+        return Err(MovieError::Truncated);
+    }
+    let version = u32_at(data, 0x04);
+    if version != VERSION {
+        return Err(MovieError::Version(version as u8));
+    }
+    let frames = u32_at(data, 0x0C) as usize;
+    let rerecords = u32_at(data, 0x10);
+    let start = data[0x14];
+    let controllers = data[0x15] & 0x0F;
+    let platform = data[0x16];
+    let sram_offset = u32_at(data, 0x38) as usize;
+    let input_offset = u32_at(data, 0x3C) as usize;
+
+    if platform & GBA != 0 {
+        return Err(MovieError::OtherSystem);
+    }
+    // The header checksum and global checksum of the cartridge header
+    let mmu = system.cpu().mmu();
+    if data[0x31] != mmu.read(0x14D) || data[0x32..0x34] != [mmu.read(0x14E), mmu.read(0x14F)] {
+        return Err(MovieError::OtherSystem);
+    }
+    if start & FROM_SNAPSHOT != 0 {
+        return Err(MovieError::Unsupported("starts from a save state"));
+    }
+    let sram = if start & FROM_SRAM != 0 {
+        data.get(sram_offset..input_offset)
+            .ok_or(MovieError::Truncated)?
+    } else {
+        &[]
+    };
+    if controllers == 0 {
+        return Err(MovieError::Invalid(String::from("no controller")));
+    }
+
+    // Only the first controller is plugged in a Game Boy
+    let frame_length = 2 * controllers.count_ones() as usize;
+    let inputs = data
+        .get(input_offset..input_offset + frames * frame_length)
+        .ok_or(MovieError::Truncated)?;
+    let mut buttons_of_frames = Vec::with_capacity(frames);
+    for frame in inputs.chunks(frame_length) {
+        let input = u16::from_le_bytes([frame[0], frame[1]]);
+        if input & RESET != 0 && !buttons_of_frames.is_empty() {
+            return Err(MovieError::Unsupported("resets during the movie"));
+        }
+        buttons_of_frames.push(buttons(input));
+    }
+    Movie::imported(
+        system,
+        platform & GBC != 0,
+        sram,
+        buttons_of_frames,
+        rerecords,
+    )
+}
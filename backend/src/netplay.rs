@@ -0,0 +1,311 @@
+//! Two players on two emulators linked over TCP, in lockstep.
+//!
+//! Both sides emulate both consoles linked together, from the same cartridges and saves
+//! exchanged when connecting, so the link cable is part of the session and only the inputs
+//! go over the network. Each input is scheduled a few frames ahead (the input delay) to give
+//! it time to reach the other side, a frame only runs once the inputs of both players are
+//! known. The states of both sides are compared regularly to detect desyncs.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream};
+
+use crate::link::LinkedSystems;
+use crate::system::{Buttons, CartridgeError, Model, System, CYCLES_PER_FRAME};
+use crate::util::crc32;
+
+const MAGIC: &[u8; 4] = b"GBNP";
+const VERSION: u8 = 1;
+/// Frames between two state checksums
+pub const CHECKSUM_INTERVAL: u64 = 60;
+
+// Messages are [kind, frame (u32), payload]
+const INPUT: u8 = 0;
+const CHECKSUM: u8 = 1;
+
+#[derive(Debug)]
+pub enum NetplayError {
+    Io(io::Error),
+    /// The peer doesn't speak the same protocol
+    Protocol(String),
+    /// The states of both sides differ after this frame
+    Desync(u64),
+    /// One of the cartridges can't be emulated
+    Cartridge(CartridgeError),
+}
+
+impl fmt::Display for NetplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetplayError::Io(e) => write!(f, "{}", e),
+            NetplayError::Protocol(message) => write!(f, "{}", message),
+            NetplayError::Desync(frame) => write!(f, "desync detected after frame {}", frame),
+            NetplayError::Cartridge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for NetplayError {
+    fn from(e: io::Error) -> Self {
+        NetplayError::Io(e)
+    }
+}
+
+impl From<CartridgeError> for NetplayError {
+    fn from(e: CartridgeError) -> Self {
+        NetplayError::Cartridge(e)
+    }
+}
+
+/// Console of a player, as sent to the other side when connecting
+pub struct Console {
+    pub model: Model,
+    pub rom: Vec<u8>,
+    /// Battery backed cartridge RAM, empty for a new game
+    pub save: Vec<u8>,
+}
+
+impl Console {
+    fn write(&self, stream: &mut TcpStream) -> io::Result<()> {
+        stream.write_all(&[self.model as u8])?;
+        for data in [&self.rom, &self.save] {
+            stream.write_all(&(data.len() as u32).to_le_bytes())?;
+            stream.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    fn read(stream: &mut TcpStream) -> Result<Console, NetplayError> {
+        let mut model = [0];
+        stream.read_exact(&mut model)?;
+        let model = Model::ALL
+            .get(model[0] as usize)
+            .copied()
+            .ok_or_else(|| NetplayError::Protocol(format!("unknown model {}", model[0])))?;
+        let mut data = [Vec::new(), Vec::new()];
+        for data in data.iter_mut() {
+            let mut length = [0; 4];
+            stream.read_exact(&mut length)?;
+            data.resize(u32::from_le_bytes(length) as usize, 0);
+            stream.read_exact(data)?;
+        }
+        let [rom, save] = data;
+        Ok(Console { model, rom, save })
+    }
+
+    fn system(self) -> Result<System, CartridgeError> {
+        let mut system = System::with_model(self.model, None, self.rom)?;
+        if !self.save.is_empty() {
+            system.load_save_ram(&self.save);
+        }
+        Ok(system)
+    }
+}
+
+pub struct Session {
+    stream: TcpStream,
+    systems: LinkedSystems,
+    // 0 for the side that listened, player 1
+    player: usize,
+    delay: u8,
+    frame: u64,
+    // Local inputs not run yet, starting with the one of the current frame
+    local_inputs: VecDeque<Buttons>,
+    local_checksums: HashMap<u64, u32>,
+    remote_checksums: HashMap<u64, u32>,
+}
+
+impl Session {
+    /// Waits for the other player on `port` and plays as player 1, with the given input
+    /// delay in frames
+    pub fn listen(port: u16, console: Console, delay: u8) -> Result<Session, NetplayError> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        Session::accept(listener, console, delay)
+    }
+
+    /// Waits for the other player on `listener` and plays as player 1, the listener may be
+    /// bound to port 0 to let the system pick a free port
+    pub fn accept(
+        listener: TcpListener,
+        console: Console,
+        delay: u8,
+    ) -> Result<Session, NetplayError> {
+        println!(
+            "Waiting for the other player on localhost:{}",
+            listener.local_addr()?.port()
+        );
+        let (mut stream, address) = listener.accept()?;
+        println!("Player 2 connected from {}", address);
+        stream.set_nodelay(true)?;
+
+        stream.write_all(MAGIC)?;
+        stream.write_all(&[VERSION, delay])?;
+        console.write(&mut stream)?;
+        let remote = Console::read(&mut stream)?;
+        Session::new(stream, [console, remote], 0, delay)
+    }
+
+    /// Connects to player 1 listening on `port` and plays as player 2, with its input delay
+    pub fn connect(port: u16, console: Console) -> Result<Session, NetplayError> {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        stream.set_nodelay(true)?;
+
+        let mut header = [0; 6];
+        stream.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(NetplayError::Protocol(String::from(
+                "the other side isn't a compatible netplay session",
+            )));
+        }
+        let delay = header[5];
+        let remote = Console::read(&mut stream)?;
+        console.write(&mut stream)?;
+        Session::new(stream, [remote, console], 1, delay)
+    }
+
+    fn new(
+        stream: TcpStream,
+        consoles: [Console; 2],
+        player: usize,
+        delay: u8,
+    ) -> Result<Session, NetplayError> {
+        let [first, second] = consoles;
+        Ok(Session {
+            stream,
+            systems: LinkedSystems::new(first.system()?, second.system()?),
+            player,
+            delay,
+            frame: 0,
+            // Nobody presses anything before the first inputs arrive
+            local_inputs: (0..delay).map(|_| Buttons::NONE).collect(),
+            local_checksums: HashMap::new(),
+            remote_checksums: HashMap::new(),
+        })
+    }
+
+    /// 0 for player 1, 1 for player 2
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn input_delay(&self) -> u8 {
+        self.delay
+    }
+
+    /// Frames run so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The console of the local player
+    pub fn system(&self) -> &System {
+        self.systems.system(self.player)
+    }
+
+    /// Both consoles, player 1 first
+    pub fn systems(&self) -> &LinkedSystems {
+        &self.systems
+    }
+
+    /// Ends the session, keeping both consoles
+    pub fn into_systems(mut self) -> LinkedSystems {
+        self.close();
+        self.systems
+    }
+
+    /// Stops sending and waits for the other player to end the session as well, so that
+    /// neither side drops the connection with messages of the other not read yet
+    pub fn close(&mut self) {
+        if self.stream.shutdown(Shutdown::Write).is_err() {
+            return;
+        }
+        let _ = io::copy(&mut self.stream, &mut io::sink());
+    }
+
+    /// Schedules `buttons` for the frame `input_delay` frames from now, then runs the current
+    /// frame once the other player's input for it has arrived
+    pub fn run_frame(&mut self, buttons: Buttons) -> Result<(), NetplayError> {
+        let scheduled = self.frame + self.delay as u64;
+        self.send(INPUT, scheduled, &[buttons.0])?;
+        self.local_inputs.push_back(buttons);
+
+        let local = self.local_inputs.pop_front().unwrap();
+        let remote = if self.frame < self.delay as u64 {
+            Buttons::NONE
+        } else {
+            self.receive_input()?
+        };
+        let player = self.player;
+        self.systems.system_mut(player).set_buttons(local);
+        self.systems.system_mut(1 - player).set_buttons(remote);
+        self.systems.run_for(CYCLES_PER_FRAME);
+        self.frame += 1;
+
+        if self.frame.is_multiple_of(CHECKSUM_INTERVAL) {
+            let checksum = self.checksum();
+            self.send(CHECKSUM, self.frame, &checksum.to_le_bytes())?;
+            self.local_checksums.insert(self.frame, checksum);
+            self.compare(self.frame)?;
+        }
+        Ok(())
+    }
+
+    fn checksum(&self) -> u32 {
+        let crc = crc32::crc32(&self.systems.system(0).save_state());
+        crc32::update(crc, &self.systems.system(1).save_state())
+    }
+
+    fn compare(&mut self, frame: u64) -> Result<(), NetplayError> {
+        let (Some(local), Some(remote)) = (
+            self.local_checksums.get(&frame),
+            self.remote_checksums.get(&frame),
+        ) else {
+            return Ok(());
+        };
+        if local != remote {
+            return Err(NetplayError::Desync(frame));
+        }
+        self.local_checksums.remove(&frame);
+        self.remote_checksums.remove(&frame);
+        Ok(())
+    }
+
+    fn send(&mut self, kind: u8, frame: u64, payload: &[u8]) -> io::Result<()> {
+        let mut message = vec![kind];
+        message.extend_from_slice(&(frame as u32).to_le_bytes());
+        message.extend_from_slice(payload);
+        self.stream.write_all(&message)
+    }
+
+    /// Reads messages until the other player's input for the current frame
+    fn receive_input(&mut self) -> Result<Buttons, NetplayError> {
+        loop {
+            let mut header = [0; 5];
+            self.stream.read_exact(&mut header)?;
+            let frame = u32::from_le_bytes(header[1..].try_into().unwrap()) as u64;
+            match header[0] {
+                INPUT => {
+                    let mut buttons = [0];
+                    self.stream.read_exact(&mut buttons)?;
+                    if frame != self.frame {
+                        return Err(NetplayError::Protocol(format!(
+                            "input of frame {} received for frame {}",
+                            frame, self.frame
+                        )));
+                    }
+                    return Ok(Buttons(buttons[0]));
+                }
+                CHECKSUM => {
+                    let mut checksum = [0; 4];
+                    self.stream.read_exact(&mut checksum)?;
+                    self.remote_checksums
+                        .insert(frame, u32::from_le_bytes(checksum));
+                    self.compare(frame)?;
+                }
+                kind => return Err(NetplayError::Protocol(format!("unknown message {}", kind))),
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! IPS, UPS and BPS patches, applied to the ROM before it is loaded.
+//!
+//! IPS records bytes to write at offsets of the ROM. UPS XORs runs of bytes with the ROM and
+//! BPS builds the patched ROM from copies of the ROM, of the patch and of itself. Both end
+//! with the CRC-32 of the ROM they apply to, of the patched ROM and of the patch.
+
+use std::fmt;
+
+use crate::util::crc32::crc32;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_END: &[u8] = b"EOF";
+const UPS_MAGIC: &[u8] = b"UPS1";
+const BPS_MAGIC: &[u8] = b"BPS1";
+// Source, target and patch CRC-32
+const FOOTER_LENGTH: usize = 12;
+// The largest cartridge ROM (MBC5), bigger sizes are taken for corruption rather than allocated
+const MAX_ROM_SIZE: usize = 0x80_0000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// Neither an IPS, UPS nor BPS patch
+    UnknownFormat,
+    /// The patch is truncated or points outside of the ROM
+    Corrupt,
+    /// The patch doesn't match its own CRC
+    Damaged,
+    /// The patch is for another ROM
+    Source { expected: u32, actual: u32 },
+    /// The patched ROM doesn't match the CRC of the patch
+    Target { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::UnknownFormat => write!(f, "not an IPS, UPS or BPS patch"),
+            PatchError::Corrupt => write!(f, "corrupt or truncated patch"),
+            PatchError::Damaged => write!(f, "damaged patch, its CRC doesn't match"),
+            PatchError::Source { expected, actual } => write!(
+                f,
+                "patch for another ROM, expected CRC {:08X} but the ROM has {:08X}",
+                expected, actual
+            ),
+            PatchError::Target { expected, actual } => write!(
+                f,
+                "patched ROM CRC {:08X} doesn't match the expected {:08X}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Self {
+        Self { data, position }
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], PatchError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or(PatchError::Corrupt)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, PatchError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32_le(&mut self) -> Result<u32, PatchError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Big endian number of `length` bytes
+    fn be(&mut self, length: usize) -> Result<usize, PatchError> {
+        Ok(self
+            .bytes(length)?
+            .iter()
+            .fold(0, |value, &byte| (value << 8) | byte as usize))
+    }
+
+    /// UPS and BPS variable length number: 7 bits per byte from the lowest, the last byte
+    /// with bit 7 set, every continuation adding one to remove redundant encodings
+    fn number(&mut self) -> Result<usize, PatchError> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.byte()?;
+            value = ((byte & 0x7F) as usize)
+                .checked_mul(shift)
+                .and_then(|bits| value.checked_add(bits))
+                .ok_or(PatchError::Corrupt)?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80).ok_or(PatchError::Corrupt)?;
+            value = value.checked_add(shift).ok_or(PatchError::Corrupt)?;
+        }
+    }
+
+    /// Size of a ROM
+    fn size(&mut self) -> Result<usize, PatchError> {
+        match self.number()? {
+            size if size <= MAX_ROM_SIZE => Ok(size),
+            _ => Err(PatchError::Corrupt),
+        }
+    }
+}
+
+/// Returns `rom` patched with `patch`, the format being detected from its header
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(rom, patch)
+    } else if patch.starts_with(UPS_MAGIC) {
+        apply_ups(rom, patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(rom, patch)
+    } else {
+        Err(PatchError::UnknownFormat)
+    }
+}
+
+fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut target = rom.to_vec();
+    let mut reader = Reader::new(patch, IPS_MAGIC.len());
+    loop {
+        if reader.bytes(IPS_END.len())? == IPS_END {
+            break;
+        }
+        reader.position -= IPS_END.len();
+        let offset = reader.be(3)?;
+        let bytes = match reader.be(2)? {
+            // Run length encoded record
+            0 => {
+                let length = reader.be(2)?;
+                vec![reader.byte()?; length]
+            }
+            length => reader.bytes(length)?.to_vec(),
+        };
+        let end = offset + bytes.len();
+        if end > MAX_ROM_SIZE {
+            return Err(PatchError::Corrupt);
+        }
+        if end > target.len() {
+            target.resize(end, 0);
+        }
+        target[offset..end].copy_from_slice(&bytes);
+    }
+    // Lunar IPS extension truncating the ROM
+    if let Ok(size) = reader.be(3) {
+        target.truncate(size);
+    }
+    Ok(target)
+}
+
+/// Checks the CRC of `patch` and of `rom`, returns the expected CRC of the patched ROM
+fn check_footer(rom: &[u8], patch: &[u8]) -> Result<u32, PatchError> {
+    let Some(body) = patch.len().checked_sub(4) else {
+        return Err(PatchError::Corrupt);
+    };
+    let mut footer = Reader::new(patch, patch.len().saturating_sub(FOOTER_LENGTH));
+    let source = footer.u32_le()?;
+    let target = footer.u32_le()?;
+    if footer.u32_le()? != crc32(&patch[..body]) {
+        return Err(PatchError::Damaged);
+    }
+    let actual = crc32(rom);
+    if actual != source {
+        return Err(PatchError::Source {
+            expected: source,
+            actual,
+        });
+    }
+    Ok(target)
+}
+
+fn check_target(target: Vec<u8>, expected: u32) -> Result<Vec<u8>, PatchError> {
+    let actual = crc32(&target);
+    if actual != expected {
+        return Err(PatchError::Target { expected, actual });
+    }
+    Ok(target)
+}
+
+fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let expected = check_footer(rom, patch)?;
+    let end = patch.len() - FOOTER_LENGTH;
+    let mut reader = Reader::new(&patch[..end], UPS_MAGIC.len());
+    if reader.size()? != rom.len() {
+        return Err(PatchError::Corrupt);
+    }
+    let mut target = rom.to_vec();
+    target.resize(reader.size()?, 0);
+    let mut offset: usize = 0;
+    while reader.position < end {
+        offset = offset.saturating_add(reader.number()?);
+        // XORed bytes until a zero one, which skips a byte
+        loop {
+            let byte = reader.byte()?;
+            if byte == 0 {
+                offset += 1;
+                break;
+            }
+            *target.get_mut(offset).ok_or(PatchError::Corrupt)? ^= byte;
+            offset += 1;
+        }
+    }
+    check_target(target, expected)
+}
+
+/// Moves `offset` by the signed BPS number next in `reader`, bit 0 being the sign
+fn relative(reader: &mut Reader, offset: usize) -> Result<usize, PatchError> {
+    let number = reader.number()?;
+    let offset = if number & 1 == 0 {
+        offset.checked_add(number >> 1)
+    } else {
+        offset.checked_sub(number >> 1)
+    };
+    offset.ok_or(PatchError::Corrupt)
+}
+
+fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let expected = check_footer(rom, patch)?;
+    let end = patch.len() - FOOTER_LENGTH;
+    let mut reader = Reader::new(&patch[..end], BPS_MAGIC.len());
+    if reader.size()? != rom.len() {
+        return Err(PatchError::Corrupt);
+    }
+    let size = reader.size()?;
+    let metadata = reader.number()?;
+    reader.bytes(metadata)?;
+
+    let mut target = Vec::with_capacity(size);
+    let mut source_offset = 0;
+    let mut target_offset = 0;
+    while reader.position < end {
+        let action = reader.number()?;
+        let length = (action >> 2) + 1;
+        if target.len() + length > size {
+            return Err(PatchError::Corrupt);
+        }
+        match action & 3 {
+            // Source read: the ROM at the same offset
+            0 => {
+                let start = target.len();
+                let bytes = rom.get(start..start + length).ok_or(PatchError::Corrupt)?;
+                target.extend_from_slice(bytes);
+            }
+            // Target read: bytes of the patch
+            1 => target.extend_from_slice(reader.bytes(length)?),
+            // Source copy: the ROM from a relative offset
+            2 => {
+                source_offset = relative(&mut reader, source_offset)?;
+                let bytes = rom
+                    .get(source_offset..source_offset + length)
+                    .ok_or(PatchError::Corrupt)?;
+                target.extend_from_slice(bytes);
+                source_offset += length;
+            }
+            // Target copy: the patched ROM from a relative offset, overlapping to repeat it
+            _ => {
+                target_offset = relative(&mut reader, target_offset)?;
+                for _ in 0..length {
+                    let byte = *target.get(target_offset).ok_or(PatchError::Corrupt)?;
+                    target.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+    if target.len() != size {
+        return Err(PatchError::Corrupt);
+    }
+    check_target(target, expected)
+}
@@ -1,19 +1,110 @@
 use std::cell::{Ref, RefCell, RefMut};
-use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
 use std::rc::Rc;
+use std::str::FromStr;
 
-use crate::memory::mmu::{MemoryRead, MemoryWrite};
-
-use super::memory::mbc::Mbc;
-use super::memory::mmu::MemoryHandler;
+use super::boot;
+use super::memory::cheats::Cheats;
+use super::memory::joypad::Joypad;
+use super::memory::mbc::{BankInfo, Mbc};
+pub use super::memory::mbc::CartridgeError;
+use super::memory::mmu::{HandlerId, MemoryHandler};
 
 use super::cpu::cpu::Cpu;
 use super::cpu::interrupt::InterruptController;
 use super::cpu::timer::Timer;
 #[cfg(feature = "blaarg")]
 use super::debug::blaarg_spy::BlaargSpy;
+use super::debug::disassembler::{self, DisassembledInstruction};
+use super::debug::trace::Tracer;
+use super::graphics::ppu::Ppu;
+use super::link::SerialLink;
 use super::memory::mmu::Mmu;
 use super::memory::serial::Serial;
+use super::util::state::{StateError, StateReader, StateWriter};
+use super::util::{crc32, png};
+
+pub use super::memory::cheats::{Cheat, CheatError, Code};
+pub use super::memory::joypad::Buttons;
+
+/// Clock cycles of a frame when the LCD is on
+pub const CYCLES_PER_FRAME: u64 = 70224;
+
+const STATE_MAGIC: &[u8; 4] = b"GBST";
+const STATE_VERSION: u8 = 4;
+
+pub use super::graphics::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Hardware revision being emulated
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Model {
+    /// Early DMG revision, with a different boot ROM
+    Dmg0,
+    #[default]
+    Dmg,
+    /// Gameboy Pocket
+    Mgb,
+    /// Super Gameboy
+    Sgb,
+    Sgb2,
+    /// Gameboy Color
+    Cgb,
+    /// Gameboy Advance, running Gameboy Color games
+    Agb,
+}
+
+impl Model {
+    /// Every model, in the order of their `as u8` values
+    pub const ALL: [Model; 7] = [
+        Model::Dmg0,
+        Model::Dmg,
+        Model::Mgb,
+        Model::Sgb,
+        Model::Sgb2,
+        Model::Cgb,
+        Model::Agb,
+    ];
+
+    pub fn is_cgb(self) -> bool {
+        matches!(self, Model::Cgb | Model::Agb)
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dmg0" => Ok(Model::Dmg0),
+            "dmg" => Ok(Model::Dmg),
+            "mgb" => Ok(Model::Mgb),
+            "sgb" => Ok(Model::Sgb),
+            "sgb2" => Ok(Model::Sgb2),
+            "cgb" => Ok(Model::Cgb),
+            "agb" => Ok(Model::Agb),
+            _ => Err(format!(
+                "Unknown model {}, expected dmg0, dmg, mgb, sgb, sgb2, cgb or agb",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Model::Dmg0 => "DMG0",
+            Model::Dmg => "DMG",
+            Model::Mgb => "MGB",
+            Model::Sgb => "SGB",
+            Model::Sgb2 => "SGB2",
+            Model::Cgb => "CGB",
+            Model::Agb => "AGB",
+        };
+        write!(f, "{}", name)
+    }
+}
 
 #[derive(Clone)]
 struct IoMemoryHandler<T>(Rc<RefCell<T>>);
@@ -42,36 +133,73 @@ impl<T: MemoryHandler> Device<T> {
 impl<T: MemoryHandler> MemoryHandler for IoMemoryHandler<T> {
     fn read(&self, mmu: &Mmu, address: u16) -> crate::memory::mmu::MemoryRead {
         match self.0.try_borrow_mut() {
-            Ok(device) => return device.read(mmu, address),
+            Ok(device) => device.read(mmu, address),
             Err(e) => panic!("Recursive read at 0x{:04X}: {}", address, e),
         }
-        MemoryRead::Pass
     }
 
     fn write(&mut self, mmu: &Mmu, address: u16, value: u8) -> crate::memory::mmu::MemoryWrite {
         match self.0.try_borrow_mut() {
-            Ok(mut device) => return device.write(mmu, address, value),
+            Ok(mut device) => device.write(mmu, address, value),
             Err(e) => panic!("Recursive write at 0x{:04X}: {}", address, e),
         }
-        MemoryWrite::Block
     }
 }
 
 pub struct System {
+    model: Model,
     cpu: Cpu,
+    mbc: Device<Mbc>,
     interrupt_controller: Device<InterruptController>,
     timer: Device<Timer>,
     serial: Device<Serial>,
+    ppu: Device<Ppu>,
+    joypad: Device<Joypad>,
+    cheats: Device<Cheats>,
+    // ROM addresses the cheats are hooked on, for the enabled Game Genie codes
+    cheat_hooks: Vec<(u16, HandlerId)>,
+    // Identifies the cartridge in save states
+    rom_checksum: u32,
+    trace: Option<Tracer>,
+    cycles: u64,
 }
 
 impl System {
-    pub fn new(boot_rom: Option<Vec<u8>>, rom: Vec<u8>) -> Self {
+    pub fn new(boot_rom: Option<Vec<u8>>, rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        Self::with_model(Model::default(), boot_rom, rom)
+    }
+
+    pub fn with_model(
+        model: Model,
+        boot_rom: Option<Vec<u8>>,
+        rom: Vec<u8>,
+    ) -> Result<Self, CartridgeError> {
         let interrupt_controller = Device::new(InterruptController::new());
-        let serial = Device::new(Serial::new(interrupt_controller.borrow().request()));
+        let serial = Device::new(Serial::new(
+            interrupt_controller.borrow().request(),
+            model.is_cgb(),
+        ));
         let timer = Device::new(Timer::new(interrupt_controller.borrow().request()));
+        let ppu = Device::new(Ppu::new(
+            interrupt_controller.borrow().request(),
+            model.is_cgb(),
+        ));
+
+        let joypad = Device::new(Joypad::new(interrupt_controller.borrow().request()));
+        let rom_checksum = crc32::crc32(&rom);
 
         let mut mmu = Mmu::new();
-        let mbc = Device::new(Mbc::new(boot_rom, rom));
+        let skip_boot = boot_rom.is_none();
+        // Checks the header before anything else reads it
+        let mbc = Device::new(Mbc::new(boot_rom, rom)?);
+        let rom = mbc.borrow().rom();
+        if skip_boot {
+            let div_counter = boot::div_counter(model, &rom);
+            timer.borrow_mut().set_div_counter(div_counter);
+            boot::load_logo(model, &mut mmu, &rom);
+        }
+        let registers = skip_boot.then(|| boot::registers(model, &rom));
+        let io_registers = skip_boot.then(|| boot::io_registers(model, &rom));
 
         #[cfg(feature = "blaarg")]
         {
@@ -80,32 +208,305 @@ impl System {
             mmu.add_handler((0xA000, 0xBFFF), spy);
         }
 
-        mmu.add_handler((0x0000, 0x7fff), mbc.handler());
+        mbc.borrow().attach(&mut mmu);
+        mmu.add_write_handler((0x0000, 0x7fff), mbc.handler());
         mmu.add_handler((0xff50, 0xff50), mbc.handler());
-        mmu.add_handler((0xa000, 0xbfff), mbc.handler());
 
+        mmu.add_handler((0xFF00, 0xFF00), joypad.handler());
         mmu.add_handler((0xFF01, 0xFF02), serial.handler());
         mmu.add_handler((0xFF04, 0xFF07), timer.handler());
+        mmu.add_handler((0xFF40, 0xFF4B), ppu.handler());
+        if model.is_cgb() {
+            mmu.enable_cgb_registers();
+            mmu.add_handler((0xFF4C, 0xFF4C), ppu.handler());
+            mmu.add_handler((0xFF4F, 0xFF4F), ppu.handler());
+            mmu.add_handler((0xFF68, 0xFF6B), ppu.handler());
+            // KEY0 is locked when the boot ROM is unmapped
+            mmu.insert_handler((0xFF50, 0xFF50), ppu.handler());
+        }
 
         mmu.add_handler((0xff0f, 0xff0f), interrupt_controller.handler());
         mmu.add_handler((0xffff, 0xffff), interrupt_controller.handler());
 
-        // for (addr, handlers) in mmu.handlers.iter() {
-        //     println!("0x{:04X} : {:?}", addr, handlers.len());
-        // }
-        let cpu = Cpu::new(mmu);
-        Self {
+        for (address, value) in io_registers.into_iter().flatten() {
+            mmu.write(address, value);
+        }
+        if skip_boot {
+            // DMA isn't written, that would start a transfer
+            let (ly, dots) = boot::lcd_position(model);
+            ppu.borrow_mut().set_boot_state(ly, dots);
+        }
+
+        let mut cpu = Cpu::new(mmu);
+        if let Some(registers) = registers {
+            cpu.registers = registers;
+        }
+        Ok(Self {
+            model,
             cpu,
+            mbc,
             interrupt_controller,
             timer,
             serial,
+            ppu,
+            joypad,
+            cheats: Device::new(Cheats::new()),
+            cheat_hooks: Vec::new(),
+            rom_checksum,
+            trace: None,
+            cycles: 0,
+        })
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// CRC32 of the cartridge ROM
+    pub fn rom_checksum(&self) -> u32 {
+        self.rom_checksum
+    }
+
+    /// Starts logging every executed instruction to `output` in the Gameboy Doctor
+    /// format, or stops logging when `None`
+    pub fn set_trace(&mut self, output: Option<Box<dyn Write>>) {
+        if let Some(mut trace) = self.trace.take() {
+            if let Err(e) = trace.flush() {
+                eprintln!("Unable to flush trace: {}", e);
+            }
         }
+        self.trace = output.map(Tracer::new);
+    }
+
+    pub fn tracing(&self) -> bool {
+        self.trace.is_some()
     }
 
     pub fn step(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            // Nothing is executed while halted or locked up
+            if !self.cpu.halted && !self.cpu.locked {
+                if let Err(e) = trace.log(&self.cpu) {
+                    eprintln!("Unable to write trace, disabling it: {}", e);
+                    self.trace = None;
+                }
+            }
+        }
         let mut elapsed = self.cpu.execute_instruction() as u16;
         elapsed += self.cpu.handle_interrupts(self.interrupt_controller.borrow_mut()) as u16;
         self.timer.borrow_mut().step(elapsed);
         self.serial.borrow_mut().step(elapsed);
+        let (dma, vblank) = {
+            let mut ppu = self.ppu.borrow_mut();
+            let frames = ppu.frames();
+            ppu.step(elapsed, self.cpu.mmu());
+            (ppu.take_dma(), ppu.frames() != frames)
+        };
+        if let Some(source) = dma {
+            self.oam_dma(source);
+        }
+        if vblank {
+            self.cheats.borrow().vblank(self.cpu.mmu_mut());
+        }
+        self.cycles += elapsed as u64;
+    }
+
+    /// Copies 160 bytes from `source`00 to OAM, all at once
+    fn oam_dma(&mut self, source: u8) {
+        let mmu = self.cpu.mmu_mut();
+        let source = (source as u16) << 8;
+        for offset in 0..0xA0 {
+            let value = mmu.read(source + offset);
+            mmu.write(0xFE00 + offset, value);
+        }
+    }
+
+    /// Clock cycles elapsed since power on
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Frames completed by the PPU since power on
+    pub fn frames(&self) -> u64 {
+        self.ppu.borrow().frames()
+    }
+
+    /// RGB888 pixels of the last frame, `SCREEN_WIDTH` x `SCREEN_HEIGHT`
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.ppu.borrow().framebuffer().to_vec()
+    }
+
+    /// The last frame as a PNG image
+    pub fn screenshot(&self) -> Vec<u8> {
+        png::encode_rgb(
+            SCREEN_WIDTH as u32,
+            SCREEN_HEIGHT as u32,
+            self.ppu.borrow().framebuffer(),
+        )
+    }
+
+    /// Presses `buttons` and releases the others
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.joypad.borrow_mut().set_buttons(buttons);
+    }
+
+    /// Holds `buttons` until the next frame boundary, every `CYCLES_PER_FRAME` cycles since
+    /// power on, whether the LCD is on or not
+    pub fn run_frame(&mut self, buttons: Buttons) {
+        self.set_buttons(buttons);
+        let end = (self.cycles / CYCLES_PER_FRAME + 1) * CYCLES_PER_FRAME;
+        while self.cycles < end {
+            self.step();
+        }
+    }
+
+    /// Snapshot of the whole emulation state, everything but the cartridge ROM, the boot ROM
+    /// and what is plugged in the serial port
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        state.bytes(STATE_MAGIC);
+        state.u8(STATE_VERSION);
+        state.u8(self.model as u8);
+        state.u32(self.rom_checksum);
+        state.u64(self.cycles);
+        self.cpu.save_state(&mut state);
+        self.mbc.borrow().save_state(&mut state);
+        self.interrupt_controller.borrow().save_state(&mut state);
+        self.timer.borrow().save_state(&mut state);
+        self.serial.borrow().save_state(&mut state);
+        self.ppu.borrow().save_state(&mut state);
+        self.joypad.borrow().save_state(&mut state);
+        self.cheats.borrow().save_state(&mut state);
+        state.into_bytes()
+    }
+
+    /// Restores a snapshot taken by `save_state` with the same model and cartridge. The
+    /// system is left in an unspecified state when the snapshot is truncated.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut state = StateReader::new(data);
+        if state.bytes(STATE_MAGIC.len()).ok() != Some(STATE_MAGIC.as_slice()) {
+            return Err(StateError::NotAState);
+        }
+        let version = state.u8()?;
+        if version != STATE_VERSION {
+            return Err(StateError::Version(version));
+        }
+        if state.u8()? != self.model as u8 || state.u32()? != self.rom_checksum {
+            return Err(StateError::OtherSystem);
+        }
+        self.cycles = state.u64()?;
+        self.cpu.load_state(&mut state)?;
+        self.mbc.borrow_mut().load_state(&mut state)?;
+        self.interrupt_controller.borrow_mut().load_state(&mut state)?;
+        self.timer.borrow_mut().load_state(&mut state)?;
+        self.serial.borrow_mut().load_state(&mut state)?;
+        self.ppu.borrow_mut().load_state(&mut state)?;
+        self.joypad.borrow_mut().load_state(&mut state)?;
+        self.cheats.borrow_mut().load_state(&mut state)?;
+        self.hook_cheats();
+        Ok(())
+    }
+
+    /// Adds an enabled Game Genie or GameShark code, returns its index in `cheats`
+    pub fn add_cheat(&mut self, code: &str) -> Result<usize, CheatError> {
+        let index = self.cheats.borrow_mut().add(code)?;
+        self.hook_cheats();
+        Ok(index)
+    }
+
+    /// Removes the cheat at `index`, the following ones move down, returns whether it existed
+    pub fn remove_cheat(&mut self, index: usize) -> bool {
+        let removed = self.cheats.borrow_mut().remove(index).is_some();
+        self.hook_cheats();
+        removed
+    }
+
+    /// Returns whether there is a cheat at `index`
+    pub fn set_cheat_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        let found = self.cheats.borrow_mut().set_enabled(index, enabled);
+        self.hook_cheats();
+        found
+    }
+
+    /// Only the addresses patched by enabled Game Genie codes go through the cheats, not
+    /// the whole ROM, the others are unhooked
+    fn hook_cheats(&mut self) {
+        let addresses: Vec<u16> = self
+            .cheats
+            .borrow()
+            .cheats()
+            .iter()
+            .filter(|cheat| cheat.enabled)
+            .filter_map(|cheat| match cheat.kind {
+                Code::GameGenie { address, .. } => Some(address),
+                Code::GameShark { .. } => None,
+            })
+            .collect();
+        let mmu = self.cpu.mmu_mut();
+        self.cheat_hooks.retain(|&(address, id)| {
+            let used = addresses.contains(&address);
+            if !used {
+                mmu.remove_handler(id);
+            }
+            used
+        });
+        for address in addresses {
+            if !self.cheat_hooks.iter().any(|&(hooked, _)| hooked == address) {
+                let id = mmu.add_handler((address, address), self.cheats.handler());
+                self.cheat_hooks.push((address, id));
+            }
+        }
+    }
+
+    pub fn cheats(&self) -> Vec<Cheat> {
+        self.cheats.borrow().cheats().to_vec()
+    }
+
+    /// Plugs `link` in the serial port, nothing is plugged in by default
+    pub fn set_serial_link(&mut self, link: Box<dyn SerialLink>) {
+        self.serial.borrow_mut().set_link(link);
+    }
+
+    /// Everything sent so far over the serial port
+    pub fn serial_output(&self) -> String {
+        self.serial.borrow().output().to_string()
+    }
+
+    /// Cartridge RAM to persist between runs, `None` when the cartridge has no battery
+    pub fn save_ram(&self) -> Option<Vec<u8>> {
+        if !self.mbc.borrow().has_battery() {
+            return None;
+        }
+        Some(self.cpu.mmu().cart_ram().to_vec())
+    }
+
+    /// Restores cartridge RAM returned by `save_ram`, extra or missing bytes are ignored
+    pub fn load_save_ram(&mut self, data: &[u8]) {
+        let mmu = self.cpu.mmu_mut();
+        let mut ram = mmu.cart_ram().to_vec();
+        let length = ram.len().min(data.len());
+        ram[..length].copy_from_slice(&data[..length]);
+        mmu.load_cart_ram(ram);
+    }
+
+    /// Decodes the instruction at `address` as currently mapped
+    pub fn disassemble(&self, address: u16) -> DisassembledInstruction {
+        disassembler::disassemble(self.cpu.mmu(), address)
+    }
+
+    pub(crate) fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub(crate) fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    pub(crate) fn serial(&self) -> Ref<'_, Serial> {
+        self.serial.borrow()
+    }
+
+    pub(crate) fn bank_info(&self) -> BankInfo {
+        self.mbc.borrow().bank_info()
     }
 }
@@ -2,7 +2,7 @@
 #[macro_export]
 macro_rules! is_bit_set {
     ($value:expr, $bit:expr) => {
-        ($value & ((1 << $bit))) != 0
+        ($value & (1 << $bit)) != 0
     };
 }
 
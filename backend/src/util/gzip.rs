@@ -0,0 +1,98 @@
+//! Reading of gzip files, the first member only
+
+use std::fmt;
+
+use super::crc32;
+use super::inflate;
+
+const MAGIC: [u8; 2] = [0x1F, 0x8B];
+const DEFLATE: u8 = 8;
+const HEADER_LENGTH: usize = 10;
+// CRC-32 and size of the decompressed data
+const TRAILER_LENGTH: usize = 8;
+
+const HEADER_CRC: u8 = 1 << 1;
+const EXTRA: u8 = 1 << 2;
+const NAME: u8 = 1 << 3;
+const COMMENT: u8 = 1 << 4;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GzipError {
+    NotAGzip,
+    /// The file is damaged or truncated
+    Corrupt,
+    /// The data doesn't decompress to what was compressed
+    Checksum,
+}
+
+impl fmt::Display for GzipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GzipError::NotAGzip => write!(f, "not a gzip file"),
+            GzipError::Corrupt => write!(f, "corrupt gzip file"),
+            GzipError::Checksum => write!(f, "CRC mismatch in gzip file"),
+        }
+    }
+}
+
+pub struct Gzip {
+    /// Of the compressed file, if stored
+    pub name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Skips a zero terminated string, returns it and the offset after it
+fn string(data: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let length = data.get(offset..)?.iter().position(|&byte| byte == 0)?;
+    Some((&data[offset..offset + length], offset + length + 1))
+}
+
+impl Gzip {
+    /// Decompresses `data` and checks its CRC
+    pub fn read(data: &[u8]) -> Result<Self, GzipError> {
+        if data.len() < HEADER_LENGTH || data[..2] != MAGIC || data[2] != DEFLATE {
+            return Err(GzipError::NotAGzip);
+        }
+        let flags = data[3];
+        let (name, start) = Self::header(data, flags).ok_or(GzipError::Corrupt)?;
+        let trailer = data
+            .len()
+            .checked_sub(TRAILER_LENGTH)
+            .filter(|&trailer| trailer >= start)
+            .ok_or(GzipError::Corrupt)?;
+        let decompressed = inflate::inflate(&data[start..trailer]).ok_or(GzipError::Corrupt)?;
+        let crc = u32::from_le_bytes(data[trailer..trailer + 4].try_into().unwrap());
+        let size = u32::from_le_bytes(data[trailer + 4..].try_into().unwrap());
+        // The size is modulo 2^32
+        if crc32::crc32(&decompressed) != crc || decompressed.len() as u32 != size {
+            return Err(GzipError::Checksum);
+        }
+        Ok(Self {
+            name,
+            data: decompressed,
+        })
+    }
+
+    /// Returns the file name and the offset of the compressed data
+    fn header(data: &[u8], flags: u8) -> Option<(Option<String>, usize)> {
+        let mut offset = HEADER_LENGTH;
+        if flags & EXTRA != 0 {
+            let length = u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().unwrap());
+            offset += 2 + length as usize;
+        }
+        let mut name = None;
+        if flags & NAME != 0 {
+            // ISO 8859-1, which maps to the first 256 code points
+            let (bytes, next) = string(data, offset)?;
+            name = Some(bytes.iter().map(|&byte| byte as char).collect());
+            offset = next;
+        }
+        if flags & COMMENT != 0 {
+            offset = string(data, offset)?.1;
+        }
+        if flags & HEADER_CRC != 0 {
+            offset += 2;
+        }
+        Some((name, offset))
+    }
+}
@@ -1 +1,7 @@
-pub mod bit_operations;
\ No newline at end of file
+pub mod bit_operations;
+pub mod crc32;
+pub mod gzip;
+pub mod inflate;
+pub mod png;
+pub mod state;
+pub mod zip;
@@ -0,0 +1,161 @@
+//! Reading of zip archives, stored or deflated entries only
+
+use std::fmt;
+
+use super::crc32;
+use super::inflate;
+
+const END_OF_DIRECTORY: u32 = 0x0605_4B50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4B50;
+const LOCAL_HEADER: u32 = 0x0403_4B50;
+const END_OF_DIRECTORY_LENGTH: usize = 22;
+const MAX_COMMENT_LENGTH: usize = 0xFFFF;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const ENCRYPTED: u16 = 1 << 0;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ZipError {
+    /// No zip central directory was found
+    NotAZip,
+    /// The archive is damaged or truncated
+    Corrupt,
+    /// The entry is compressed with another method than deflate, or encrypted
+    Unsupported(String),
+    /// The entry doesn't decompress to what was stored
+    Checksum(String),
+}
+
+impl fmt::Display for ZipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZipError::NotAZip => write!(f, "not a zip archive"),
+            ZipError::Corrupt => write!(f, "corrupt zip archive"),
+            ZipError::Unsupported(name) => {
+                write!(
+                    f,
+                    "{} is encrypted or compressed with an unsupported method",
+                    name
+                )
+            }
+            ZipError::Checksum(name) => write!(f, "CRC mismatch in {}", name),
+        }
+    }
+}
+
+pub struct Entry {
+    pub name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    // Of the local header
+    offset: usize,
+}
+
+impl Entry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().unwrap(),
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+pub struct Archive<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+impl<'a> Archive<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ZipError> {
+        // The end of central directory record is followed by a comment of any length
+        let last = data
+            .len()
+            .checked_sub(END_OF_DIRECTORY_LENGTH)
+            .ok_or(ZipError::NotAZip)?;
+        let end = (last.saturating_sub(MAX_COMMENT_LENGTH)..=last)
+            .rev()
+            .find(|&offset| u32_at(data, offset) == Some(END_OF_DIRECTORY))
+            .ok_or(ZipError::NotAZip)?;
+        let entries = Self::directory(data, end).ok_or(ZipError::Corrupt)?;
+        Ok(Self { data, entries })
+    }
+
+    fn directory(data: &[u8], end: usize) -> Option<Vec<Entry>> {
+        let count = u16_at(data, end + 10)? as usize;
+        let mut offset = u32_at(data, end + 16)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(data, offset)? != DIRECTORY_ENTRY {
+                return None;
+            }
+            let name_length = u16_at(data, offset + 28)? as usize;
+            let extra_length = u16_at(data, offset + 30)? as usize;
+            let comment_length = u16_at(data, offset + 32)? as usize;
+            let name = data.get(offset + 46..offset + 46 + name_length)?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                flags: u16_at(data, offset + 8)?,
+                method: u16_at(data, offset + 10)?,
+                crc: u32_at(data, offset + 16)?,
+                compressed_size: u32_at(data, offset + 20)? as usize,
+                size: u32_at(data, offset + 24)? as usize,
+                offset: u32_at(data, offset + 42)? as usize,
+            });
+            offset += 46 + name_length + extra_length + comment_length;
+        }
+        Some(entries)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The entry named `name`, ignoring case
+    pub fn find(&self, name: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Decompresses `entry` and checks its CRC
+    pub fn read(&self, entry: &Entry) -> Result<Vec<u8>, ZipError> {
+        if entry.flags & ENCRYPTED != 0 {
+            return Err(ZipError::Unsupported(entry.name.clone()));
+        }
+        let compressed = self.compressed(entry).ok_or(ZipError::Corrupt)?;
+        let data = match entry.method {
+            STORED => compressed.to_vec(),
+            DEFLATED => inflate::inflate(compressed).ok_or(ZipError::Corrupt)?,
+            _ => return Err(ZipError::Unsupported(entry.name.clone())),
+        };
+        if data.len() != entry.size || crc32::crc32(&data) != entry.crc {
+            return Err(ZipError::Checksum(entry.name.clone()));
+        }
+        Ok(data)
+    }
+
+    fn compressed(&self, entry: &Entry) -> Option<&'a [u8]> {
+        let header = entry.offset;
+        if u32_at(self.data, header)? != LOCAL_HEADER {
+            return None;
+        }
+        // The local extra field may differ from the central directory one
+        let name_length = u16_at(self.data, header + 26)? as usize;
+        let extra_length = u16_at(self.data, header + 28)? as usize;
+        let start = header + 30 + name_length + extra_length;
+        self.data.get(start..start + entry.compressed_size)
+    }
+}
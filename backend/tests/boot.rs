@@ -0,0 +1,89 @@
+//! Starts at the cartridge entry point with the state the boot ROM of each model leaves
+
+use backend::debug::debugger::{Debugger, Register};
+use backend::system::{Model, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const CGB_ONLY: u8 = 0xC0;
+
+struct BootState {
+    model: Model,
+    // AF, BC, DE, HL
+    registers: [u16; 4],
+    div: u8,
+    ly: u8,
+    stat: u8,
+}
+
+#[rustfmt::skip]
+const BOOT_STATES: [BootState; 7] = [
+    BootState { model: Model::Dmg0, registers: [0x0100, 0xFF13, 0x00C1, 0x8403], div: 0x18, ly: 0x91, stat: 0x81 },
+    BootState { model: Model::Dmg, registers: [0x01B0, 0x0013, 0x00D8, 0x014D], div: 0xAB, ly: 0x00, stat: 0x85 },
+    BootState { model: Model::Mgb, registers: [0xFFB0, 0x0013, 0x00D8, 0x014D], div: 0xAB, ly: 0x00, stat: 0x85 },
+    BootState { model: Model::Sgb, registers: [0x0100, 0x0014, 0x0000, 0xC060], div: 0xD8, ly: 0x00, stat: 0x85 },
+    BootState { model: Model::Sgb2, registers: [0xFF00, 0x0014, 0x0000, 0xC060], div: 0xD8, ly: 0x00, stat: 0x85 },
+    BootState { model: Model::Cgb, registers: [0x1180, 0x0000, 0xFF56, 0x000D], div: 0x1E, ly: 0x00, stat: 0x85 },
+    BootState { model: Model::Agb, registers: [0x1100, 0x0100, 0xFF56, 0x000D], div: 0x1E, ly: 0x00, stat: 0x85 },
+];
+
+fn boot(model: Model, code: &[u8]) -> Debugger {
+    let mut rom = RomBuilder::new(code).bytes(0x14D, &[0x42]);
+    if model.is_cgb() {
+        rom = rom.bytes(0x143, &[CGB_ONLY]);
+    }
+    Debugger::new(System::with_model(model, None, rom.build()).unwrap())
+}
+
+#[test]
+fn starts_with_the_boot_rom_state() {
+    for expected in BOOT_STATES {
+        let model = expected.model;
+        let debugger = boot(model, &[]);
+        let registers = [Register::AF, Register::BC, Register::DE, Register::HL]
+            .map(|register| debugger.read_register(register));
+        assert_eq!(registers, expected.registers, "{} registers", model);
+        assert_eq!(debugger.read_register(Register::SP), 0xFFFE);
+        assert_eq!(debugger.read_register(Register::PC), 0x0100);
+
+        let io = |address| debugger.peek(address);
+        assert_eq!(io(0xFF04), expected.div, "{} DIV", model);
+        assert_eq!(io(0xFF44), expected.ly, "{} LY", model);
+        assert_eq!(io(0xFF41), expected.stat, "{} STAT", model);
+        let sc = if model.is_cgb() { 0x7F } else { 0x7E };
+        // SC, IF, LCDC, DMA, BGP, OBP0, OBP1
+        assert_eq!(
+            [0xFF02, 0xFF0F, 0xFF40, 0xFF46, 0xFF47, 0xFF48, 0xFF49].map(io),
+            [sc, 0xE1, 0x91, 0xFF, 0xFC, 0xFF, 0xFF],
+            "{} I/O registers",
+            model
+        );
+    }
+}
+
+#[test]
+fn timer_follows_the_divider() {
+    // 66 NOPs then a loop
+    let mut code = [0x00; 68];
+    code[66..].copy_from_slice(&[0x18, 0xFE]);
+    let mut debugger = boot(Model::Dmg, &code);
+    // NOP, JP $0150
+    debugger.step();
+    debugger.step();
+    debugger.poke(0xFF04, 0x00);
+    // Enabled, every 16 clocks
+    debugger.poke(0xFF07, 0x05);
+    for _ in 0..64 {
+        debugger.step();
+    }
+    assert_eq!((debugger.peek(0xFF04), debugger.peek(0xFF05)), (0x01, 16));
+
+    // Half way to the next tick: resetting DIV is a falling edge of the selected bit
+    debugger.step();
+    debugger.step();
+    assert_eq!(debugger.peek(0xFF05), 16);
+    debugger.poke(0xFF04, 0x00);
+    assert_eq!((debugger.peek(0xFF04), debugger.peek(0xFF05)), (0x00, 17));
+}
@@ -0,0 +1,53 @@
+//! Loads cartridges and switches their banks
+
+use backend::debug::debugger::{Debugger, StopReason};
+use backend::system::{CartridgeError, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const MBC1: u8 = 0x01;
+const MBC3: u8 = 0x13;
+
+#[test]
+fn rejects_unsupported_cartridges() {
+    let rom = RomBuilder::new(&[]).cartridge(MBC3, 0x03).build();
+    assert_eq!(
+        System::new(None, rom).err(),
+        Some(CartridgeError::Unsupported(MBC3))
+    );
+    // Ends in the middle of the header
+    let rom = RomBuilder::new(&[]).build()[..0x140].to_vec();
+    assert_eq!(
+        System::new(None, rom).err(),
+        Some(CartridgeError::TooShort(0x140))
+    );
+}
+
+#[test]
+fn boot_rom_writes_reach_the_mbc() {
+    #[rustfmt::skip]
+    let boot_rom = [
+        0x3E, 0x02,       // LD A, $02
+        0xEA, 0x00, 0x20, // LD ($2000), A
+        0x3E, 0x01,       // LD A, $01
+        0xE0, 0x50,       // LDH ($50), A
+    ];
+    let mut boot_rom = boot_rom.to_vec();
+    boot_rom.resize(0x100, 0);
+    // With 2 banks, selecting bank 2 maps bank 0 at $4000
+    let rom = RomBuilder::new(&[0x18, 0xFE])
+        .cartridge(MBC1, 0x00)
+        .bytes(0x0050, &[0xAA])
+        .bytes(0x4050, &[0xBB])
+        .build();
+    let mut debugger = Debugger::new(System::new(Some(boot_rom), rom).unwrap());
+    assert_eq!(debugger.peek(0x4050), 0xBB);
+    debugger.add_breakpoint(0x150);
+    assert!(matches!(
+        debugger.resume(None),
+        StopReason::Breakpoint(0x150)
+    ));
+    assert_eq!(debugger.peek(0x4050), 0xAA);
+}
@@ -0,0 +1,93 @@
+//! Colors DMG games on a CGB with the compatibility palette picked from their title, and
+//! locks the mode the boot ROM selects through KEY0
+
+use backend::debug::debugger::Debugger;
+use backend::system::{Model, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const OLD_LICENSEE: usize = 0x14B;
+const NINTENDO: u8 = 0x01;
+const CGB_COMPATIBLE: u8 = 0x80;
+const BGP: u16 = 0xFF47;
+const BCPS: u16 = 0xFF68;
+const KEY0: u16 = 0xFF4C;
+const CYCLES_PER_FRAME: u64 = 70_224;
+
+/// RGB of a CGB color, each 5 bits component scaled to 8 bits
+fn rgb(color: u16) -> [u8; 3] {
+    [0, 5, 10].map(|shift| {
+        let component = ((color >> shift) & 0x1F) as u8;
+        component << 3 | component >> 2
+    })
+}
+
+/// Colors of the 4 BG shades, read from the top left pixel of the blank screen
+fn bg_colors(model: Model, rom: Vec<u8>) -> [[u8; 3]; 4] {
+    let mut debugger = Debugger::new(System::with_model(model, None, rom).unwrap());
+    [0, 1, 2, 3].map(|shade| {
+        debugger.poke(BGP, shade);
+        let end = debugger.system().cycles() + 2 * CYCLES_PER_FRAME;
+        while debugger.system().cycles() < end {
+            debugger.step();
+        }
+        let framebuffer = debugger.system().framebuffer();
+        [framebuffer[0], framebuffer[1], framebuffer[2]]
+    })
+}
+
+fn game(title: &[u8], licensee: u8) -> Vec<u8> {
+    RomBuilder::new(&[0x18, 0xFE]) // JR -2
+        .title(title)
+        .bytes(OLD_LICENSEE, &[licensee])
+        .build()
+}
+
+#[test]
+fn dmg_games_get_the_palette_of_their_title() {
+    // Pokémon Red gets the red palette
+    assert_eq!(
+        bg_colors(Model::Cgb, game(b"POKEMON RED", NINTENDO)),
+        [0x7FFF, 0x421F, 0x1CF2, 0x0000].map(rgb)
+    );
+    // Only the titles of Nintendo games are looked up, other games and unknown titles get
+    // the default one
+    let default = [0x7FFF, 0x1BEF, 0x6180, 0x0000].map(rgb);
+    assert_eq!(bg_colors(Model::Cgb, game(b"POKEMON RED", 0x00)), default);
+    assert_eq!(bg_colors(Model::Agb, game(b"ZZZ", NINTENDO)), default);
+}
+
+#[test]
+fn key0_locks_the_mode_selected_by_the_boot_rom() {
+    // CGB games keep access to the CGB registers
+    let rom = RomBuilder::new(&[0x18, 0xFE])
+        .bytes(0x143, &[CGB_COMPATIBLE])
+        .build();
+    let mut debugger = Debugger::new(System::with_model(Model::Cgb, None, rom.clone()).unwrap());
+    debugger.poke(BCPS, 0x85);
+    assert_eq!(debugger.peek(BCPS), 0xC5);
+
+    // A boot ROM selecting the DMG compatibility mode for the same game
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x04, // LD A, $04
+        0xE0, 0x4C, // LDH (KEY0), A
+        0x3E, 0x11, // LD A, $11
+        0xE0, 0x50, // LDH ($50), A, unmapping the boot ROM
+    ];
+    let mut boot_rom = vec![0; 0x900];
+    boot_rom[..code.len()].copy_from_slice(&code);
+    let system = System::with_model(Model::Cgb, Some(boot_rom), rom).unwrap();
+    let mut debugger = Debugger::new(system);
+    while debugger.pc() != 0x0100 {
+        debugger.step();
+    }
+    debugger.poke(BCPS, 0x85);
+    assert_eq!(debugger.peek(BCPS), 0xFF);
+    // Too late to go back
+    debugger.poke(KEY0, CGB_COMPATIBLE);
+    debugger.poke(BCPS, 0x85);
+    assert_eq!(debugger.peek(BCPS), 0xFF);
+}
@@ -0,0 +1,195 @@
+//! Applies Game Genie and GameShark codes
+
+use backend::debug::debugger::Debugger;
+use backend::system::{Buttons, CheatError, Code, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const RESULT: u16 = 0xFF80;
+
+/// Stores an immediate in $FF80 and keeps copying $C000 to $FF81, with 4 banks of battery
+/// backed RAM
+fn cheat_rom() -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x05,       // LD A, $05
+        0xE0, 0x80,       // LDH ($80), A
+        0x21, 0x00, 0xC0, // LD HL, $C000
+        0x7E,             // Loop: LD A, (HL)
+        0xE0, 0x81,       // LDH ($81), A
+        0x18, 0xFB,       // JR Loop
+    ];
+    // MBC1+RAM+BATTERY, 32 KiB of RAM
+    RomBuilder::new(&code).cartridge(0x03, 0x03).build()
+}
+
+fn run(cheats: &[&str], frames: u64) -> Debugger {
+    let mut system = System::new(None, cheat_rom()).unwrap();
+    for code in cheats {
+        system.add_cheat(code).unwrap();
+    }
+    for _ in 0..frames {
+        system.run_frame(Buttons::NONE);
+    }
+    Debugger::new(system)
+}
+
+#[test]
+fn decodes_codes() {
+    assert_eq!(
+        "421-51F-F0E".parse(),
+        Ok(Code::GameGenie {
+            address: 0x0151,
+            value: 0x42,
+            compare: Some(0x05),
+        })
+    );
+    assert_eq!(
+        "421-51F".parse(),
+        Ok(Code::GameGenie {
+            address: 0x0151,
+            value: 0x42,
+            compare: None,
+        })
+    );
+    assert_eq!(
+        "019900C0".parse(),
+        Ok(Code::GameShark {
+            bank: 0x01,
+            value: 0x99,
+            address: 0xC000,
+        })
+    );
+    // Pokémon Red, 99 in the money
+    assert_eq!(
+        "019947D3".parse(),
+        Ok(Code::GameShark {
+            bank: 0x01,
+            value: 0x99,
+            address: 0xD347,
+        })
+    );
+    // CGB WRAM bank 2
+    assert_eq!(
+        "92631ED1".parse(),
+        Ok(Code::GameShark {
+            bank: 0x92,
+            value: 0x63,
+            address: 0xD11E,
+        })
+    );
+    assert_eq!("421-517".parse::<Code>(), Err(CheatError::Address(0x8151)));
+    // HRAM, but not the MBC, VRAM, echo RAM nor the I/O registers
+    assert!("01998AFF".parse::<Code>().is_ok());
+    for (code, address) in [
+        ("01990020", 0x2000),
+        ("01990080", 0x8000),
+        ("019900E0", 0xE000),
+        ("019940FF", 0xFF40),
+        ("0199FFFF", 0xFFFF),
+    ] {
+        assert_eq!(code.parse::<Code>(), Err(CheatError::RamAddress(address)));
+    }
+    assert_eq!(
+        "42X-51F".parse::<Code>(),
+        Err(CheatError::Format(String::from("42X-51F")))
+    );
+    assert_eq!(
+        "0199-00C0".parse::<Code>(),
+        Err(CheatError::Format(String::from("0199-00C0")))
+    );
+}
+
+#[test]
+fn game_genie_replaces_rom_reads() {
+    assert_eq!(run(&[], 1).peek(RESULT), 0x05);
+    assert_eq!(run(&["421-51F"], 1).peek(RESULT), 0x42);
+    assert_eq!(run(&["421-51F-F0E"], 1).peek(RESULT), 0x42);
+    // The ROM doesn't hold the compare byte
+    assert_eq!(run(&["421-51F-E0E"], 1).peek(RESULT), 0x05);
+
+    let mut system = System::new(None, cheat_rom()).unwrap();
+    let index = system.add_cheat("421-51F").unwrap();
+    assert!(system.set_cheat_enabled(index, false));
+    system.run_frame(Buttons::NONE);
+    assert_eq!(Debugger::new(system).peek(RESULT), 0x05);
+}
+
+#[test]
+fn gameshark_writes_ram_at_vblank() {
+    let debugger = run(&["019900C0"], 2);
+    assert_eq!(debugger.peek(RESULT + 1), 0x99);
+
+    let mut debugger = run(&["019900C0", "025534A0", "014434A0"], 2);
+    let system = debugger.system_mut();
+    // The first byte is the cartridge RAM bank, whatever bank is mapped
+    let ram = system.save_ram().unwrap();
+    assert_eq!(ram[2 * 0x2000 + 0x34], 0x55);
+    assert_eq!(ram[0x2000 + 0x34], 0x44);
+    assert_eq!(ram[0x34], 0x00);
+    let cheats = system.cheats();
+    assert_eq!(cheats.len(), 3);
+    assert_eq!(cheats[1].code, "025534A0");
+    assert!(system.remove_cheat(2));
+    assert!(system.remove_cheat(1));
+
+    assert!(system.remove_cheat(0));
+    assert!(!system.remove_cheat(1));
+    debugger.poke(0xC000, 0x00);
+    debugger.system_mut().run_frame(Buttons::NONE);
+    assert_eq!(debugger.peek(RESULT + 1), 0x00);
+}
+
+#[test]
+fn gameshark_selects_cgb_wram_banks() {
+    // Only bank 1 is emulated
+    let debugger = run(&["9177FFD0", "9066FED0", "9255FDD0"], 2);
+    assert_eq!(debugger.peek(0xD0FF), 0x77);
+    assert_eq!(debugger.peek(0xD0FE), 0x66);
+    assert_eq!(debugger.peek(0xD0FD), 0x00);
+    // Only $D000-$DFFF is banked
+    assert_eq!(run(&["9299FFC0"], 2).peek(0xC0FF), 0x99);
+}
+
+#[test]
+fn removed_game_genie_codes_are_unhooked() {
+    let mut system = System::new(None, cheat_rom()).unwrap();
+    system.add_cheat("421-51F").unwrap();
+    system.add_cheat("431-51F").unwrap();
+    assert!(system.remove_cheat(0));
+    let mut debugger = Debugger::new(system);
+    assert_eq!(debugger.peek(0x0151), 0x43);
+
+    debugger.system_mut().set_cheat_enabled(0, false);
+    assert_eq!(debugger.peek(0x0151), 0x05);
+    debugger.system_mut().set_cheat_enabled(0, true);
+    assert_eq!(debugger.peek(0x0151), 0x43);
+    assert!(debugger.system_mut().remove_cheat(0));
+    assert_eq!(debugger.peek(0x0151), 0x05);
+}
+
+#[test]
+fn cheats_are_saved_in_states() {
+    let mut system = System::new(None, cheat_rom()).unwrap();
+    system.add_cheat("421-51F").unwrap();
+    let index = system.add_cheat("019900C0").unwrap();
+    system.set_cheat_enabled(index, false);
+    let state = system.save_state();
+
+    let mut other = System::new(None, cheat_rom()).unwrap();
+    other.load_state(&state).unwrap();
+    let cheats = other.cheats();
+    assert_eq!(cheats.len(), 2);
+    assert_eq!(cheats[0].code, "421-51F");
+    assert!(cheats[0].enabled);
+    assert!(!cheats[1].enabled);
+
+    // The restored Game Genie code is hooked, loading a state without it unhooks it
+    assert_eq!(Debugger::new(other).peek(0x0151), 0x42);
+    let restart = System::new(None, cheat_rom()).unwrap().save_state();
+    system.load_state(&restart).unwrap();
+    assert!(system.cheats().is_empty());
+    assert_eq!(Debugger::new(system).peek(0x0151), 0x05);
+}
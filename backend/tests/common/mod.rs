@@ -0,0 +1,52 @@
+//! Helpers shared by the integration tests
+// Each test crate uses part of them
+#![allow(dead_code, unused_imports)]
+
+use backend::util::crc32;
+
+mod rom;
+
+pub use rom::RomBuilder;
+
+/// Zip archive of uncompressed `files`
+pub fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let mut fields = Vec::new();
+        // Version, flags, method, time, date
+        fields.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        fields.extend_from_slice(&crc32::crc32(data).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // No extra field
+        fields.extend_from_slice(&[0, 0]);
+
+        zip.extend_from_slice(b"PK\x03\x04");
+        zip.extend_from_slice(&fields);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(data);
+
+        directory.extend_from_slice(b"PK\x01\x02");
+        // Made by version
+        directory.extend_from_slice(&[20, 0]);
+        directory.extend_from_slice(&fields);
+        // No comment, disk 0, attributes
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = zip.len() as u32;
+    zip.extend_from_slice(&directory);
+    zip.extend_from_slice(b"PK\x05\x06");
+    zip.extend_from_slice(&[0; 4]);
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&directory_offset.to_le_bytes());
+    // No comment
+    zip.extend_from_slice(&[0, 0]);
+    zip
+}
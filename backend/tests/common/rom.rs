@@ -0,0 +1,44 @@
+//! Minimal cartridge ROMs for the tests and benchmarks
+
+const ENTRY: usize = 0x100;
+const CODE: usize = 0x150;
+const TITLE: usize = 0x134;
+const CARTRIDGE_TYPE: usize = 0x147;
+const RAM_SIZE: usize = 0x149;
+
+/// 32 KiB ROM whose entry point jumps to code at $0150, without MBC nor RAM by default
+pub struct RomBuilder {
+    rom: Vec<u8>,
+}
+
+impl RomBuilder {
+    pub fn new(code: &[u8]) -> Self {
+        let mut rom = vec![0; 0x8000];
+        // NOP, JP $0150
+        rom[ENTRY..ENTRY + 4].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+        rom[CODE..CODE + code.len()].copy_from_slice(code);
+        Self { rom }
+    }
+
+    pub fn title(self, title: &[u8]) -> Self {
+        self.bytes(TITLE, title)
+    }
+
+    /// Cartridge type and RAM size codes of the header, e.g. $03 and $03 for MBC1+RAM+BATTERY
+    /// with 32 KiB of RAM
+    pub fn cartridge(mut self, kind: u8, ram_size: u8) -> Self {
+        self.rom[CARTRIDGE_TYPE] = kind;
+        self.rom[RAM_SIZE] = ram_size;
+        self
+    }
+
+    /// Copies `bytes` at `address`, for header fields, data or subroutines
+    pub fn bytes(mut self, address: usize, bytes: &[u8]) -> Self {
+        self.rom[address..address + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.rom
+    }
+}
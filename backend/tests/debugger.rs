@@ -0,0 +1,340 @@
+//! Steps, breaks and watches memory with the debugger
+
+use std::io::{Cursor, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use backend::debug::debugger::{Access, Debugger, Register, StopReason, WatchKind};
+use backend::debug::{gdb, repl};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+const SUBROUTINE: u16 = 0x160;
+const AFTER_CALL: u16 = 0x155;
+const LOOP: u16 = 0x15B;
+
+fn debugger() -> Debugger {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x42,       // LD A, $42
+        0xCD, 0x60, 0x01, // CALL $0160
+        0xEA, 0x00, 0xC0, // LD ($C000), A
+        0xFA, 0x01, 0xC0, // LD A, ($C001)
+        0x18, 0xFE,       // Loop: JR Loop
+    ];
+    // INC A, RET, then an illegal opcode
+    let rom = RomBuilder::new(&code).bytes(SUBROUTINE as usize, &[0x3C, 0xC9, 0xD3]);
+    Debugger::new(System::new(None, rom.build()).unwrap())
+}
+
+#[test]
+fn steps_over_calls() {
+    let mut debugger = debugger();
+    assert_eq!(debugger.pc(), 0x100);
+    // NOP, JP $0150, LD A, $42
+    for _ in 0..3 {
+        assert!(matches!(debugger.step(), StopReason::Step));
+    }
+    assert_eq!(debugger.pc(), 0x152);
+
+    assert!(matches!(debugger.step_over(), StopReason::Step));
+    assert_eq!(debugger.pc(), AFTER_CALL);
+    assert_eq!(debugger.read_register(Register::A), 0x43);
+
+    // Not a call: a single step
+    assert!(matches!(debugger.step_over(), StopReason::Step));
+    assert_eq!(debugger.pc(), 0x158);
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut debugger = debugger();
+    assert!(debugger.add_breakpoint(SUBROUTINE));
+    assert!(matches!(
+        debugger.resume(None),
+        StopReason::Breakpoint(SUBROUTINE)
+    ));
+    assert_eq!(debugger.pc(), SUBROUTINE);
+
+    // A breakpoint inside the called subroutine stops `next` too
+    let mut debugger = self::debugger();
+    for _ in 0..3 {
+        debugger.step();
+    }
+    debugger.add_breakpoint(SUBROUTINE);
+    assert!(matches!(
+        debugger.step_over(),
+        StopReason::Breakpoint(SUBROUTINE)
+    ));
+
+    assert!(debugger.remove_breakpoint(SUBROUTINE));
+    assert!(!debugger.remove_breakpoint(SUBROUTINE));
+    assert!(matches!(debugger.resume(Some(100)), StopReason::StepLimit));
+    assert_eq!(debugger.pc(), LOOP);
+}
+
+#[test]
+fn stops_at_watchpoints() {
+    let mut debugger = debugger();
+    debugger.add_watchpoint(0xC000, WatchKind::Write);
+    debugger.add_watchpoint(0xC001, WatchKind::Read);
+    let StopReason::Watchpoint(hit) = debugger.resume(None) else {
+        panic!("expected the write watchpoint");
+    };
+    assert_eq!(hit.address, 0xC000);
+    assert_eq!(hit.access, Access::Write);
+    assert_eq!(hit.value, Some(0x43));
+    assert_eq!(hit.pc, AFTER_CALL);
+
+    let StopReason::Watchpoint(hit) = debugger.resume(None) else {
+        panic!("expected the read watchpoint");
+    };
+    assert_eq!(
+        (hit.address, hit.access, hit.value),
+        (0xC001, Access::Read, None)
+    );
+
+    // Peeking doesn't trigger watchpoints
+    assert_eq!(debugger.peek(0xC000), 0x43);
+    assert!(debugger.remove_watchpoint(0xC001));
+    assert_eq!(debugger.watchpoints(), [(0xC000, WatchKind::Write)]);
+}
+
+#[test]
+fn disassembles_with_flag_effects() {
+    let debugger = debugger();
+    for (address, text, flags) in [
+        (0x150, "LD A,$42", "----"),
+        (SUBROUTINE, "INC A", "Z0H-"),
+        (SUBROUTINE + 2, "DB $D3", "----"),
+        (LOOP, "JR $015B", "----"),
+    ] {
+        let instruction = debugger.disassemble(address);
+        assert_eq!(
+            (instruction.text.as_str(), instruction.flags.as_str()),
+            (text, flags)
+        );
+    }
+}
+
+#[test]
+fn interrupts_a_run() {
+    let mut debugger = debugger();
+    let flag = debugger.interrupt_flag();
+    // Set while stopped, so ignored by the next run
+    flag.store(true, Ordering::Relaxed);
+    assert!(matches!(debugger.resume(Some(100)), StopReason::StepLimit));
+
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        flag.store(true, Ordering::Relaxed);
+    });
+    assert!(matches!(debugger.resume(None), StopReason::Interrupted));
+    interrupter.join().unwrap();
+}
+
+#[test]
+fn repl_only_repeats_stepping_commands() {
+    let mut debugger = debugger();
+    let mut output = Vec::new();
+    let input = Cursor::new("step\n\nregs\n\nq\n");
+    repl::run(&mut debugger, input, &mut output).unwrap();
+    // The empty line after `step` steps again, the one after `regs` does nothing
+    assert_eq!(debugger.pc(), 0x150);
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.matches("AF:").count(), 1);
+}
+
+/// Sends a GDB remote packet and returns the reply
+fn gdb_request(stream: &mut TcpStream, command: &str) -> String {
+    let checksum = command
+        .bytes()
+        .fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    write!(stream, "${}#{:02x}", command, checksum).unwrap();
+    let mut reply = Vec::new();
+    let mut byte = [0];
+    // The ack, the packet and its checksum
+    while reply.last() != Some(&b'#') {
+        stream.read_exact(&mut byte).unwrap();
+        reply.push(byte[0]);
+    }
+    let mut checksum = [0; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    let reply = String::from_utf8(reply).unwrap();
+    reply
+        .trim_start_matches('+')
+        .trim_start_matches('$')
+        .trim_end_matches('#')
+        .to_string()
+}
+
+/// Serves `debugger` to a GDB client running `client`, then kills the target
+fn gdb_session<T: Send + 'static>(
+    debugger: &mut Debugger,
+    client: impl FnOnce(&mut TcpStream) -> T + Send + 'static,
+) -> T {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let result = client(&mut stream);
+        stream.write_all(b"$k#6b").unwrap();
+        result
+    });
+    gdb::serve(debugger, listener).unwrap();
+    client.join().unwrap()
+}
+
+/// Reads the whole target description in chunks of `length` bytes
+fn gdb_target_xml(stream: &mut TcpStream, length: usize) -> String {
+    let mut xml = String::new();
+    loop {
+        let command = format!(
+            "qXfer:features:read:target.xml:{:x},{:x}",
+            xml.len(),
+            length
+        );
+        let reply = gdb_request(stream, &command);
+        let (more, chunk) = reply.split_at(1);
+        assert!(chunk.len() <= length);
+        xml.push_str(chunk);
+        match more {
+            "m" => continue,
+            "l" => return xml,
+            _ => panic!("Unexpected reply {}", reply),
+        }
+    }
+}
+
+/// Hex of a register in `g` and `p` replies, least significant byte first
+fn gdb_register(value: u16) -> String {
+    format!("{:02x}{:02x}", value as u8, (value >> 8) as u8)
+}
+
+#[test]
+fn gdb_reads_the_target_description() {
+    let mut debugger = debugger();
+    let xml = gdb_session(&mut debugger, |stream| {
+        let supported = gdb_request(stream, "qSupported:xmlRegisters=i386");
+        assert!(supported.contains("qXfer:features:read+"));
+        gdb_target_xml(stream, 0x40)
+    });
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("<architecture>sm83</architecture>"));
+    assert!(xml.trim_end().ends_with("</target>"));
+}
+
+#[test]
+fn gdb_registers_follow_the_target_description() {
+    let mut debugger = debugger();
+    let registers = [
+        ("af", Register::AF, 0x12B0),
+        ("bc", Register::BC, 0x3456),
+        ("de", Register::DE, 0x789A),
+        ("hl", Register::HL, 0xBCDE),
+        ("sp", Register::SP, 0xCFF0),
+        ("pc", Register::PC, 0x0150),
+    ];
+    for (_, register, value) in registers {
+        debugger.write_register(register, value);
+    }
+    let (xml, g, p) = gdb_session(&mut debugger, |stream| {
+        let xml = gdb_target_xml(stream, 0x1000);
+        let g = gdb_request(stream, "g");
+        let p: Vec<_> = (0..7)
+            .map(|n| gdb_request(stream, &format!("p{:x}", n)))
+            .collect();
+        (xml, g, p)
+    });
+    // Registers are numbered in the order the description lists them
+    let names: Vec<_> = xml
+        .split("<reg name=\"")
+        .skip(1)
+        .map(|reg| reg.split('"').next().unwrap())
+        .collect();
+    assert_eq!(names, ["af", "bc", "de", "hl", "sp", "pc"]);
+    let values: Vec<_> = names
+        .iter()
+        .map(|name| {
+            let (_, _, value) = registers.iter().find(|(n, _, _)| n == name).unwrap();
+            gdb_register(*value)
+        })
+        .collect();
+    assert_eq!(g, values.concat());
+    assert_eq!(p[..6], values);
+    assert_eq!(p[6], "E01");
+}
+
+#[test]
+fn gdb_accesses_memory_through_the_mmu() {
+    let mut debugger = debugger();
+    let replies = gdb_session(&mut debugger, |stream| {
+        [
+            gdb_request(stream, "m150,5"),
+            gdb_request(stream, "Mc000,3:abcdef"),
+            // Echo RAM mirrors WRAM
+            gdb_request(stream, "me000,3"),
+        ]
+    });
+    assert_eq!(replies, ["3e42cd6001", "OK", "abcdef"]);
+    assert_eq!(debugger.peek(0xC001), 0xCD);
+}
+
+#[test]
+fn gdb_continues_to_breakpoints() {
+    let mut debugger = debugger();
+    let replies = gdb_session(&mut debugger, |stream| {
+        [
+            gdb_request(stream, &format!("Z0,{:x},1", AFTER_CALL)),
+            gdb_request(stream, "c"),
+            gdb_request(stream, "?"),
+            gdb_request(stream, "p5"),
+        ]
+    });
+    assert_eq!(
+        replies,
+        [
+            "OK",
+            "T05swbreak:;",
+            "T05swbreak:;",
+            &gdb_register(AFTER_CALL)
+        ]
+    );
+    assert_eq!(debugger.pc(), AFTER_CALL);
+}
+
+#[test]
+fn gdb_steps_one_instruction() {
+    let mut debugger = debugger();
+    let replies = gdb_session(&mut debugger, |stream| {
+        // NOP, then LD A, $42 and LD ($C000), A from the given addresses
+        ["s", "s150", "s155"].map(|command| {
+            let stop = gdb_request(stream, command);
+            (stop, gdb_request(stream, "p5"))
+        })
+    });
+    let expected = [0x101, 0x152, 0x158].map(|pc| (String::from("S05"), gdb_register(pc)));
+    assert_eq!(replies, expected);
+}
+
+#[test]
+fn gdb_rejects_malformed_hex() {
+    let mut debugger = debugger();
+    let replies = gdb_session(&mut debugger, |stream| {
+        // Multibyte characters where hex digits are expected
+        [
+            gdb_request(stream, &format!("G{}", "aaa\u{e9}".repeat(6))),
+            gdb_request(stream, "Mc000,2:a\u{e9}a"),
+            gdb_request(stream, "P0=\u{e9}\u{e9}"),
+            gdb_request(stream, "Mc000,2:12aB"),
+            gdb_request(stream, "mc000,2"),
+        ]
+    });
+    assert_eq!(replies, ["E01", "E01", "E01", "OK", "12ab"]);
+    assert_eq!(debugger.pc(), 0x100);
+}
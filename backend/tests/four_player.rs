@@ -0,0 +1,103 @@
+//! Plays the four player adapter protocol from the player side of the cables
+
+use backend::link::{FourPlayerAdapter, PairLink, SerialLink};
+
+const PING_BYTE_CYCLES: u64 = 16384;
+// With a RATE of 0
+const TRANSMISSION_BYTE_CYCLES: u64 = 4096;
+
+/// Has each player that gives a byte wait for a transfer, runs the adapter until the next one
+/// and returns what the players received
+fn transfer(
+    adapter: &mut FourPlayerAdapter,
+    players: &mut [PairLink; 4],
+    bytes: [Option<u8>; 4],
+    cycles: u64,
+) -> [Option<u8>; 4] {
+    for (player, byte) in players.iter_mut().zip(bytes) {
+        if let Some(byte) = byte {
+            assert_eq!(player.poll(byte), None);
+        }
+    }
+    adapter.step(cycles);
+    let mut received = [None; 4];
+    for (i, (player, byte)) in players.iter_mut().zip(bytes).enumerate() {
+        if byte.is_some() {
+            received[i] = player.poll(0xFF);
+        }
+    }
+    received
+}
+
+/// Runs a whole ping packet, returns what players 1 and 2 received
+fn ping(
+    adapter: &mut FourPlayerAdapter,
+    players: &mut [PairLink; 4],
+    first: [u8; 4],
+    second: [u8; 4],
+) -> [[u8; 4]; 2] {
+    let mut received = [[0; 4]; 2];
+    for i in 0..4 {
+        let bytes = [Some(first[i]), Some(second[i]), None, None];
+        let replies = transfer(adapter, players, bytes, PING_BYTE_CYCLES);
+        received[0][i] = replies[0].unwrap();
+        received[1][i] = replies[1].unwrap();
+        assert_eq!(replies[2..], [None, None]);
+    }
+    received
+}
+
+#[test]
+fn ping_then_transmission() {
+    let (mut adapter, mut players) = FourPlayerAdapter::new();
+    // Player 1 asks for the fastest rate and 2 byte packets
+    let answer = [0x88, 0x88, 0x00, 0x02];
+
+    let received = ping(&mut adapter, &mut players, answer, answer);
+    assert_eq!(
+        received,
+        [[0xFE, 0x01, 0x01, 0x01], [0xFE, 0x02, 0x02, 0x02]]
+    );
+    let received = ping(&mut adapter, &mut players, answer, answer);
+    assert_eq!(
+        received,
+        [[0xFE, 0x31, 0x31, 0x31], [0xFE, 0x32, 0x32, 0x32]]
+    );
+
+    ping(&mut adapter, &mut players, [0xAA; 4], answer);
+    assert!(adapter.transmitting());
+    for _ in 0..4 {
+        let bytes = [Some(0x00), Some(0x00), None, None];
+        let replies = transfer(&mut adapter, &mut players, bytes, TRANSMISSION_BYTE_CYCLES);
+        assert_eq!(replies, [Some(0xCC), Some(0xCC), None, None]);
+    }
+
+    // 4 players x 2 bytes, what was sent comes back in the next packet
+    let sent = [[0x11, 0x12], [0x21, 0x22]];
+    let mut packets = [Vec::new(), Vec::new()];
+    for _ in 0..2 {
+        for i in 0..8 {
+            let bytes = [
+                sent[0].get(i).copied().or(Some(0x00)),
+                sent[1].get(i).copied().or(Some(0x00)),
+                None,
+                None,
+            ];
+            let replies = transfer(&mut adapter, &mut players, bytes, TRANSMISSION_BYTE_CYCLES);
+            packets[0].push(replies[0].unwrap());
+            packets[1].push(replies[1].unwrap());
+        }
+    }
+    let expected = [0, 0, 0, 0, 0, 0, 0, 0, 0x11, 0x12, 0x21, 0x22, 0, 0, 0, 0];
+    assert_eq!(packets[0], expected);
+    assert_eq!(packets[1], expected);
+
+    // Player 1 goes back to pinging
+    for _ in 0..4 {
+        let bytes = [Some(0xFF), Some(0x00), None, None];
+        transfer(&mut adapter, &mut players, bytes, TRANSMISSION_BYTE_CYCLES);
+    }
+    assert!(!adapter.transmitting());
+    let received = ping(&mut adapter, &mut players, answer, answer);
+    assert_eq!(received[0], [0xFE, 0x31, 0x31, 0x31]);
+}
@@ -0,0 +1,118 @@
+//! Runs until a limit or a stop condition and reports which one ended the run
+
+use backend::debug::debugger::Debugger;
+use backend::headless::{self, ExitReason, RunOptions};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+const LOOP: u16 = 0x015C;
+const ILLEGAL: u16 = 0x0159;
+
+/// Turns the LCD on, sends 'O' over the serial port with the internal clock, then loops on
+/// itself
+fn serial_rom() -> System {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x91, // LD A, $91
+        0xE0, 0x40, // LDH (LCDC), A
+        0x3E, b'O', // LD A, 'O'
+        0xE0, 0x01, // LDH (SB), A
+        0x3E, 0x81, // LD A, $81
+        0xE0, 0x02, // LDH (SC), A
+        0x18, 0xFE, // Loop: JR Loop
+    ];
+    System::new(None, RomBuilder::new(&code).build()).unwrap()
+}
+
+fn run(options: RunOptions) -> (ExitReason, bool) {
+    let mut system = serial_rom();
+    let reason = headless::run(&mut system, &options);
+    (reason, reason.is_limit_reached(&options))
+}
+
+#[test]
+fn limits_alone_succeed() {
+    let options = RunOptions {
+        max_cycles: Some(10_000),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::CycleLimit, false));
+    let options = RunOptions {
+        max_frames: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::FrameLimit, false));
+}
+
+#[test]
+fn stop_conditions_succeed() {
+    let options = RunOptions {
+        max_frames: Some(10),
+        stop_pc: Some(LOOP),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::Pc(LOOP), false));
+    let options = RunOptions {
+        max_frames: Some(10),
+        stop_on_loop: true,
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::InfiniteLoop, false));
+    let options = RunOptions {
+        max_frames: Some(10),
+        stop_serial: Some(String::from("O")),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::Serial, false));
+}
+
+#[test]
+fn limits_before_a_stop_condition_fail() {
+    let options = RunOptions {
+        max_cycles: Some(10_000),
+        stop_serial: Some(String::from("OK")),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::CycleLimit, true));
+    let options = RunOptions {
+        max_frames: Some(2),
+        stop_pc: Some(0x4000),
+        ..Default::default()
+    };
+    assert_eq!(run(options), (ExitReason::FrameLimit, true));
+}
+
+#[test]
+fn illegal_opcodes_lock_up_the_cpu() {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x91, // LD A, $91
+        0xE0, 0x40, // LDH (LCDC), A
+        0x3E, 0x01, // LD A, $01
+        0xE0, 0xFF, // LDH (IE), A
+        0xFB,       // EI
+        0xD3,       // Illegal
+    ];
+    let mut system = System::new(None, RomBuilder::new(&code).build()).unwrap();
+    // Time goes on but the VBlank interrupt isn't serviced
+    let options = RunOptions {
+        max_frames: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(headless::run(&mut system, &options), ExitReason::FrameLimit);
+    let options = RunOptions {
+        max_frames: Some(10),
+        stop_on_loop: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        headless::run(&mut system, &options),
+        ExitReason::InfiniteLoop
+    );
+    let debugger = Debugger::new(system);
+    assert_eq!(debugger.pc(), ILLEGAL);
+    assert!(debugger.interrupt_state().locked);
+}
@@ -0,0 +1,75 @@
+//! Reads back the I/O registers, whose unused bits always read as 1
+
+use backend::debug::debugger::Debugger;
+use backend::system::{Model, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const CGB_ONLY: u8 = 0xC0;
+
+/// What each register of $FF00-$FF7F reads after writing $00 to all of them, on DMG
+#[rustfmt::skip]
+const ZEROS_READ_BACK: [u8; 0x80] = [
+    // P1 with no button pressed, SB, SC, -, DIV, TIMA, TMA, TAC, -...-, IF
+    0xCF, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE0,
+    // NR10-NR14, -, NR21-NR24, NR30-NR34, -
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, 0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    // NR41-NR44, NR50-NR52, -
+    0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00, 0x70, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Wave RAM
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // LCDC, STAT with LY=LYC, SCY, SCX, LY, LYC, DMA, BGP, OBP0, OBP1, WY, WX, CGB registers
+    0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Boot ROM disable, CGB registers
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+/// The CGB registers a CGB game can use, and SC with its clock speed bit
+const CGB_ZEROS_READ_BACK: [(u16, u8); 6] = [
+    (0xFF02, 0x7C), // SC
+    (0xFF4F, 0xFE), // VBK
+    (0xFF68, 0x40), // BCPS
+    (0xFF69, 0x00), // BCPD
+    (0xFF6A, 0x40), // OCPS
+    (0xFF6B, 0x00), // OCPD
+];
+
+#[test]
+fn unused_bits_read_as_one() {
+    let rom = RomBuilder::new(&[]).build();
+    let mut debugger = Debugger::new(System::new(None, rom).unwrap());
+    // LCDC is cleared too, so the LCD is off and LY stays 0
+    for address in 0xFF00..=0xFF7F {
+        debugger.poke(address, 0x00);
+    }
+    for (address, expected) in (0xFF00..=0xFF7F).zip(ZEROS_READ_BACK) {
+        assert_eq!(
+            debugger.peek(address),
+            expected,
+            "Register ${:04X}",
+            address
+        );
+    }
+    // High RAM is fully readable
+    debugger.poke(0xFF80, 0x00);
+    assert_eq!(debugger.peek(0xFF80), 0x00);
+}
+
+#[test]
+fn cgb_registers_have_their_own_unused_bits() {
+    let rom = RomBuilder::new(&[]).bytes(0x143, &[CGB_ONLY]).build();
+    let mut debugger = Debugger::new(System::with_model(Model::Cgb, None, rom).unwrap());
+    for (address, expected) in CGB_ZEROS_READ_BACK {
+        debugger.poke(address, 0x00);
+        assert_eq!(
+            debugger.peek(address),
+            expected,
+            "Register ${:04X}",
+            address
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Exchanges bytes between two systems linked in the same process, and between two ends
+//! of a link cable over TCP.
+//!
+//! Both systems run the same small ROM: it sends 4 bytes counting up from its first byte, as
+//! master or slave depending on SC, and stores the bytes received at $FF80.
+
+use std::net::{Ipv4Addr, TcpListener};
+use std::thread;
+
+use backend::debug::debugger::Debugger;
+use backend::link::{LinkedSystems, SerialLink, TcpLink, IDLE_BYTE};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+const RECEIVED: u16 = 0xFF80;
+const SB: u16 = 0xFF01;
+const IF: u16 = 0xFF0F;
+const SERIAL_INTERRUPT: u8 = 0x08;
+const TRANSFERS: u8 = 4;
+// Way longer than 4 transfers with the normal clock
+const CYCLES: u64 = 100_000;
+
+fn exchange_rom(first_byte: u8, sc: u8) -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x21, 0x80, 0xFF, // LD HL, $FF80
+        0x0E, first_byte, // LD C, first_byte
+        // Loop:
+        0x79,             // LD A, C
+        0xE0, 0x01,       // LDH (SB), A
+        0x3E, sc,         // LD A, sc
+        0xE0, 0x02,       // LDH (SC), A
+        0xF0, 0x02,       // Wait: LDH A, (SC)
+        0xCB, 0x7F,       // BIT 7, A
+        0x20, 0xFA,       // JR NZ, Wait
+        0xF0, 0x01,       // LDH A, (SB)
+        0x22,             // LD (HL+), A
+        0x0C,             // INC C
+        0x79,             // LD A, C
+        0xFE, first_byte.wrapping_add(TRANSFERS), // CP first_byte + TRANSFERS
+        0x20, 0xEA,       // JR NZ, Loop
+        0x18, 0xFE,       // JR -2
+    ];
+    RomBuilder::new(&code).build()
+}
+
+fn received(system: System) -> Vec<u8> {
+    let debugger = Debugger::new(system);
+    (0..TRANSFERS as u16)
+        .map(|offset| debugger.peek(RECEIVED + offset))
+        .collect()
+}
+
+#[test]
+fn exchange_between_master_and_slave() {
+    let master = System::new(None, exchange_rom(0xA0, 0x81)).unwrap();
+    let slave = System::new(None, exchange_rom(0x10, 0x80)).unwrap();
+    let mut linked = LinkedSystems::new(master, slave);
+    linked.run_for(CYCLES);
+
+    let (master, slave) = linked.into_systems();
+    assert_eq!(received(master), [0x10, 0x11, 0x12, 0x13]);
+    assert_eq!(received(slave), [0xA0, 0xA1, 0xA2, 0xA3]);
+}
+
+#[test]
+fn slave_without_master_waits() {
+    let first = System::new(None, exchange_rom(0x10, 0x80)).unwrap();
+    let second = System::new(None, exchange_rom(0x20, 0x80)).unwrap();
+    let mut linked = LinkedSystems::new(first, second);
+    linked.run_for(CYCLES);
+
+    let (first, _) = linked.into_systems();
+    assert_eq!(received(first), [0; TRANSFERS as usize]);
+}
+
+#[test]
+fn cancelled_slave_transfers_are_not_clocked() {
+    // Waits for the master with $42 ready, then gives up before the master clocks a bit
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x42, // LD A, $42
+        0xE0, 0x01, // LDH (SB), A
+        0x3E, 0x80, // LD A, $80
+        0xE0, 0x02, // LDH (SC), A
+        0x00,       // NOP
+        0xAF,       // XOR A
+        0xE0, 0x02, // LDH (SC), A
+        0x18, 0xFE, // JR -2
+    ];
+    let master = System::new(None, exchange_rom(0xA0, 0x81)).unwrap();
+    let slave = System::new(None, RomBuilder::new(&code).build()).unwrap();
+    let mut linked = LinkedSystems::new(master, slave);
+    linked.run_for(CYCLES);
+
+    let (master, slave) = linked.into_systems();
+    assert_eq!(received(master), [IDLE_BYTE; TRANSFERS as usize]);
+    let slave = Debugger::new(slave);
+    assert_eq!(slave.peek(SB), 0x42);
+    assert_eq!(slave.peek(IF) & SERIAL_INTERRUPT, 0);
+}
+
+#[test]
+fn cancel_withdraws_the_ready_byte() {
+    let (mut first, mut second) = tcp_links();
+    assert_eq!(second.poll(0x24), None);
+    second.cancel();
+    assert_eq!(first.exchange(0x42), IDLE_BYTE);
+    assert_eq!(second.poll(0x24), None);
+}
+
+fn tcp_links() -> (TcpLink, TcpLink) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let listening = thread::spawn(|| TcpLink::accept(listener).unwrap());
+    let connected = TcpLink::connect(port).unwrap();
+    (listening.join().unwrap(), connected)
+}
+
+fn transfer(master: &mut TcpLink, slave: &mut TcpLink) {
+    for byte in 0..TRANSFERS {
+        assert_eq!(slave.poll(0x10 + byte), None);
+        assert_eq!(master.exchange(0xA0 + byte), 0x10 + byte);
+        assert_eq!(slave.poll(0x10 + byte), Some(0xA0 + byte));
+    }
+}
+
+#[test]
+fn exchange_over_tcp() {
+    let (mut first, mut second) = tcp_links();
+    transfer(&mut first, &mut second);
+    transfer(&mut second, &mut first);
+
+    // Lost when the other side doesn't wait for it, rather than delivered later
+    assert_eq!(first.exchange(0x42), IDLE_BYTE);
+    assert_eq!(second.poll(0x24), None);
+    assert_eq!(first.exchange(0x43), 0x24);
+    assert_eq!(second.poll(0x24), Some(0x43));
+}
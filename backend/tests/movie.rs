@@ -0,0 +1,130 @@
+//! Records movies and plays them back
+
+use backend::movie::{Movie, MovieError, Start};
+use backend::system::{Buttons, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const FRAMES: u64 = 40;
+
+/// Keeps adding the action buttons half of P1 to C, so the state depends on every input
+fn joypad_rom(title: u8) -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x10, // LD A, $10
+        0xE0, 0x00, // LDH (P1), A
+        0xF0, 0x00, // Loop: LDH A, (P1)
+        0x81,       // ADD A, C
+        0x4F,       // LD C, A
+        0x18, 0xFA, // JR Loop
+    ];
+    RomBuilder::new(&code).title(&[title]).build()
+}
+
+fn buttons(frame: u64) -> Buttons {
+    Buttons((frame * 37 % 256) as u8)
+}
+
+fn record(movie: &mut Movie, system: &mut System, frames: u64) {
+    for _ in 0..frames {
+        let frame = movie.frame(system);
+        movie.record(system, buttons(frame));
+    }
+}
+
+/// Plays `movie` on a new system, returns its state at the end
+fn play(movie: &Movie) -> Vec<u8> {
+    let mut system = System::new(None, joypad_rom(0)).unwrap();
+    movie.rewind(&mut system).unwrap();
+    while movie.play_frame(&mut system) {}
+    assert_eq!(movie.frame(&system), movie.len());
+    system.save_state()
+}
+
+#[test]
+fn plays_back_identically() {
+    let mut system = System::new(None, joypad_rom(0)).unwrap();
+    let mut movie = Movie::new(&system, Start::PowerOn);
+    record(&mut movie, &mut system, 3 * FRAMES);
+    assert_eq!(movie.len(), 3 * FRAMES);
+    assert_eq!(movie.frame(&system), 3 * FRAMES);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    assert_eq!(movie.start(), Start::PowerOn);
+    assert!(play(&movie) == system.save_state());
+}
+
+#[test]
+fn rerecords_from_a_save_state() {
+    let mut system = System::new(None, joypad_rom(0)).unwrap();
+    let mut movie = Movie::new(&system, Start::PowerOn);
+    record(&mut movie, &mut system, FRAMES);
+    let state = system.save_state();
+    for _ in 0..FRAMES {
+        movie.record(&mut system, Buttons::A);
+    }
+    let abandoned = system.save_state();
+
+    movie.rerecord(&mut system, &state).unwrap();
+    assert_eq!(movie.frame(&system), FRAMES);
+    assert_eq!(movie.len(), FRAMES);
+    assert_eq!(movie.rerecords(), 1);
+    record(&mut movie, &mut system, FRAMES);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    assert_eq!(movie.rerecords(), 1);
+    let end = play(&movie);
+    assert!(end == system.save_state());
+    assert!(end != abandoned);
+}
+
+#[test]
+fn starts_from_a_save_state() {
+    let mut system = System::new(None, joypad_rom(0)).unwrap();
+    for _ in 0..FRAMES {
+        system.run_frame(Buttons::START);
+    }
+    let mut movie = Movie::new(&system, Start::SaveState);
+    assert_eq!(movie.frame(&system), 0);
+    record(&mut movie, &mut system, FRAMES);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    assert_eq!(movie.start(), Start::SaveState);
+    assert!(play(&movie) == system.save_state());
+}
+
+#[test]
+fn rejects_other_systems_and_data() {
+    let mut system = System::new(None, joypad_rom(0)).unwrap();
+    let mut movie = Movie::new(&system, Start::PowerOn);
+    record(&mut movie, &mut system, FRAMES);
+    let after_end = {
+        let mut system = System::new(None, joypad_rom(0)).unwrap();
+        for frame in 0..2 * FRAMES {
+            system.run_frame(buttons(frame));
+        }
+        system.save_state()
+    };
+    assert_eq!(
+        movie.rerecord(&mut system, &after_end),
+        Err(MovieError::NotInMovie)
+    );
+
+    let mut other = System::new(None, joypad_rom(1)).unwrap();
+    assert_eq!(movie.rewind(&mut other), Err(MovieError::OtherSystem));
+    let mut started = System::new(None, joypad_rom(0)).unwrap();
+    started.run_frame(Buttons::NONE);
+    assert_eq!(movie.rewind(&mut started), Err(MovieError::StartMismatch));
+
+    let data = movie.to_bytes();
+    assert!(matches!(
+        Movie::from_bytes(b"not a movie"),
+        Err(MovieError::NotAMovie)
+    ));
+    assert!(matches!(
+        Movie::from_bytes(&data[..data.len() - 1]),
+        Err(MovieError::Truncated)
+    ));
+}
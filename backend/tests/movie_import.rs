@@ -0,0 +1,181 @@
+//! Imports BizHawk and VisualBoyAdvance movies
+
+use backend::movie::{Movie, MovieError, Start};
+use backend::system::{Buttons, Model, System};
+
+mod common;
+
+use common::{stored_zip, RomBuilder};
+
+const FRAMES: u64 = 60;
+
+/// Keeps adding the action buttons half of P1 to C, so the state depends on every input
+fn joypad_rom() -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x10, // LD A, $10
+        0xE0, 0x00, // LDH (P1), A
+        0xF0, 0x00, // Loop: LDH A, (P1)
+        0x81,       // ADD A, C
+        0x4F,       // LD C, A
+        0x18, 0xFA, // JR Loop
+    ];
+    // Header and global checksums
+    RomBuilder::new(&code)
+        .bytes(0x14D, &[0x12, 0x34, 0x56])
+        .build()
+}
+
+fn buttons(frame: u64) -> Buttons {
+    Buttons((frame * 37 % 256) as u8)
+}
+
+/// State after recording the same inputs ourselves
+fn expected_state() -> Vec<u8> {
+    let mut system = System::new(None, joypad_rom()).unwrap();
+    let mut movie = Movie::new(&system, Start::PowerOn);
+    for frame in 0..FRAMES {
+        movie.record(&mut system, buttons(frame));
+    }
+    system.save_state()
+}
+
+fn play(data: &[u8]) -> Result<(Movie, Vec<u8>), MovieError> {
+    let mut system = System::new(None, joypad_rom()).unwrap();
+    let movie = Movie::import(data, &mut system)?;
+    movie.rewind(&mut system)?;
+    while movie.play_frame(&mut system) {}
+    Ok((movie, system.save_state()))
+}
+
+fn bk2(header: &str, log_key: &str, frames: &[String]) -> Vec<u8> {
+    let mut log = format!("[Input]\nLogKey:{}\n", log_key);
+    for frame in frames {
+        log.push_str(frame);
+        log.push('\n');
+    }
+    log.push_str("[/Input]\n");
+    stored_zip(&[
+        ("Header.txt", header.as_bytes()),
+        ("Input Log.txt", log.as_bytes()),
+    ])
+}
+
+fn bk2_frame(buttons: Buttons, power: bool) -> String {
+    let mut frame = String::from("|");
+    let columns = [
+        (Buttons::UP, 'U'),
+        (Buttons::DOWN, 'D'),
+        (Buttons::LEFT, 'L'),
+        (Buttons::RIGHT, 'R'),
+        (Buttons::START, 'S'),
+        (Buttons::SELECT, 's'),
+        (Buttons::B, 'B'),
+        (Buttons::A, 'A'),
+    ];
+    for (button, mnemonic) in columns {
+        frame.push(if buttons.contains(button) {
+            mnemonic
+        } else {
+            '.'
+        });
+    }
+    frame.push(if power { 'P' } else { '.' });
+    frame.push('|');
+    frame
+}
+
+const GB_HEADER: &str =
+    "MovieVersion BizHawk v2.0.0\nPlatform GB\nCore Gambatte\nrerecordCount 42\n";
+const LOG_KEY: &str = "#Up|Down|Left|Right|Start|Select|B|A|Power|";
+
+#[test]
+fn imports_bizhawk_movies() {
+    let frames: Vec<String> = (0..FRAMES)
+        .map(|frame| bk2_frame(buttons(frame), frame == 0))
+        .collect();
+    let (movie, state) = play(&bk2(GB_HEADER, LOG_KEY, &frames)).unwrap();
+    assert_eq!(movie.len(), FRAMES);
+    assert_eq!(movie.rerecords(), 42);
+    assert!(state == expected_state());
+
+    // Multiplayer cores name the buttons of each player
+    let key = "#P1 Up|P1 Down|P1 Left|P1 Right|P1 Start|P1 Select|P1 B|P1 A|P1 Power|";
+    let (_, state) = play(&bk2(GB_HEADER, key, &frames)).unwrap();
+    assert!(state == expected_state());
+}
+
+#[test]
+fn rejects_unsupported_bizhawk_movies() {
+    let mut frames: Vec<String> = (0..FRAMES)
+        .map(|frame| bk2_frame(buttons(frame), false))
+        .collect();
+    let header = format!("{}StartsFromSavestate True\n", GB_HEADER);
+    assert_eq!(
+        play(&bk2(&header, LOG_KEY, &frames)).err(),
+        Some(MovieError::Unsupported("starts from a save state"))
+    );
+    let header = GB_HEADER.replace("Platform GB", "Platform NES");
+    assert_eq!(
+        play(&bk2(&header, LOG_KEY, &frames)).err(),
+        Some(MovieError::OtherSystem)
+    );
+    let header = format!("{}IsCGBMode 1\n", GB_HEADER);
+    assert_eq!(
+        play(&bk2(&header, LOG_KEY, &frames)).err(),
+        Some(MovieError::OtherSystem)
+    );
+    let mut system = System::with_model(Model::Cgb, None, joypad_rom()).unwrap();
+    assert!(Movie::import(&bk2(&header, LOG_KEY, &frames), &mut system).is_ok());
+
+    frames[10] = bk2_frame(Buttons::NONE, true);
+    assert_eq!(
+        play(&bk2(GB_HEADER, LOG_KEY, &frames)).err(),
+        Some(MovieError::Unsupported("resets during the movie"))
+    );
+    frames[10] = String::from("|..|");
+    assert!(matches!(
+        play(&bk2(GB_HEADER, LOG_KEY, &frames)),
+        Err(MovieError::Invalid(_))
+    ));
+}
+
+fn vbm(checksums: [u8; 3], inputs: &[u16]) -> Vec<u8> {
+    let mut data = vec![0; 0x100];
+    data[..4].copy_from_slice(b"VBM\x1A");
+    data[0x04..0x08].copy_from_slice(&1u32.to_le_bytes());
+    data[0x0C..0x10].copy_from_slice(&(inputs.len() as u32).to_le_bytes());
+    data[0x10..0x14].copy_from_slice(&7u32.to_le_bytes());
+    // Controller 1
+    data[0x15] = 1;
+    data[0x31..0x34].copy_from_slice(&checksums);
+    data[0x3C..0x40].copy_from_slice(&0x100u32.to_le_bytes());
+    for input in inputs {
+        data.extend_from_slice(&input.to_le_bytes());
+    }
+    data
+}
+
+/// A B Select Start Right Left Up Down from bit 0
+fn vbm_input(buttons: Buttons) -> u16 {
+    buttons.0.rotate_right(4) as u16
+}
+
+#[test]
+fn imports_visualboyadvance_movies() {
+    let inputs: Vec<u16> = (0..FRAMES).map(|frame| vbm_input(buttons(frame))).collect();
+    let (movie, state) = play(&vbm([0x12, 0x34, 0x56], &inputs)).unwrap();
+    assert_eq!(movie.len(), FRAMES);
+    assert_eq!(movie.rerecords(), 7);
+    assert!(state == expected_state());
+
+    assert_eq!(
+        play(&vbm([0x12, 0x34, 0x00], &inputs)).err(),
+        Some(MovieError::OtherSystem)
+    );
+    let data = vbm([0x12, 0x34, 0x56], &inputs);
+    assert_eq!(
+        play(&data[..data.len() - 1]).err(),
+        Some(MovieError::Truncated)
+    );
+}
@@ -0,0 +1,83 @@
+//! Plays a netplay session between two threads over localhost
+
+use std::net::{Ipv4Addr, TcpListener};
+use std::thread;
+
+use backend::debug::debugger::Debugger;
+use backend::netplay::{Console, NetplayError, Session};
+use backend::system::{Buttons, Model, System};
+
+mod common;
+
+use common::RomBuilder;
+
+const DELAY: u8 = 3;
+const FRAMES: u64 = 150;
+// Both players press their button from this frame on
+const PRESS_FRAME: u64 = 100;
+const PRESSED: u16 = 0xFF80;
+
+/// Keeps copying the action buttons half of P1 to $FF80
+fn joypad_rom() -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x10, // LD A, $10
+        0xE0, 0x00, // LDH (P1), A
+        0xF0, 0x00, // Loop: LDH A, (P1)
+        0xE0, 0x80, // LDH ($80), A
+        0x18, 0xFA, // JR Loop
+    ];
+    RomBuilder::new(&code).build()
+}
+
+fn console() -> Console {
+    Console {
+        model: Model::Dmg,
+        rom: joypad_rom(),
+        save: Vec::new(),
+    }
+}
+
+fn play(mut session: Session, button: Buttons) -> Result<Vec<System>, NetplayError> {
+    while session.frame() < FRAMES {
+        let buttons = if session.frame() >= PRESS_FRAME {
+            button
+        } else {
+            Buttons::NONE
+        };
+        session.run_frame(buttons)?;
+    }
+    let (first, second) = session.into_systems().into_systems();
+    Ok(vec![first, second])
+}
+
+#[test]
+fn both_sides_stay_in_sync() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let host = thread::spawn(|| {
+        let session = Session::accept(listener, console(), DELAY).unwrap();
+        play(session, Buttons::START)
+            .unwrap()
+            .into_iter()
+            .map(|system| system.save_state())
+            .collect::<Vec<_>>()
+    });
+    let session = Session::connect(port, console()).unwrap();
+    assert_eq!(session.player(), 1);
+    assert_eq!(session.input_delay(), DELAY);
+    let systems = play(session, Buttons::A).unwrap();
+    let host_states = host.join().unwrap();
+
+    let states: Vec<_> = systems.iter().map(System::save_state).collect();
+    assert!(
+        states == host_states,
+        "Both sides ended in different states"
+    );
+    let pressed: Vec<_> = systems
+        .into_iter()
+        .map(|system| Debugger::new(system).peek(PRESSED) & 0x0F)
+        .collect();
+    // Start is bit 3 and A bit 0, pressed buttons read as 0
+    assert_eq!(pressed, [0x07, 0x0E]);
+}
@@ -0,0 +1,133 @@
+//! Applies IPS, UPS and BPS patches
+
+use backend::patch::{apply, PatchError};
+use backend::util::crc32::crc32;
+
+fn rom() -> Vec<u8> {
+    (0..0x8000u32).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+/// UPS and BPS variable length number
+fn number(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let bits = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(bits | 0x80);
+            return bytes;
+        }
+        bytes.push(bits);
+        value -= 1;
+    }
+}
+
+fn with_footer(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+    patch.extend_from_slice(&crc32(source).to_le_bytes());
+    patch.extend_from_slice(&crc32(target).to_le_bytes());
+    let crc = crc32(&patch);
+    patch.extend_from_slice(&crc.to_le_bytes());
+    patch
+}
+
+#[test]
+fn applies_ips() {
+    let rom = rom();
+    let mut patch = b"PATCH".to_vec();
+    // $AA $BB at $0100
+    patch.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x02, 0xAA, 0xBB]);
+    // 4 times $CC at $0200
+    patch.extend_from_slice(&[0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x04, 0xCC]);
+    // $DD past the end
+    patch.extend_from_slice(&[0x00, 0x80, 0x01, 0x00, 0x01, 0xDD]);
+    patch.extend_from_slice(b"EOF");
+
+    let mut expected = rom.clone();
+    expected[0x100..0x102].copy_from_slice(&[0xAA, 0xBB]);
+    expected[0x200..0x204].fill(0xCC);
+    expected.extend_from_slice(&[0x00, 0xDD]);
+    assert_eq!(apply(&rom, &patch).unwrap(), expected);
+
+    // Truncated to 16 KiB
+    patch.extend_from_slice(&[0x00, 0x40, 0x00]);
+    assert_eq!(apply(&rom, &patch).unwrap(), &expected[..0x4000]);
+
+    assert_eq!(
+        apply(&rom, &patch[..patch.len() - 6]),
+        Err(PatchError::Corrupt)
+    );
+    assert_eq!(apply(&rom, b"NOT A PATCH"), Err(PatchError::UnknownFormat));
+}
+
+#[test]
+fn applies_ups() {
+    let rom = rom();
+    let mut target = rom.clone();
+    target[0x10] ^= 0xFF;
+    target[0x12] ^= 0x0F;
+    target.extend_from_slice(&[0x00, 0x42]);
+
+    let mut patch = b"UPS1".to_vec();
+    patch.extend(number(rom.len()));
+    patch.extend(number(target.len()));
+    // XOR $FF at $10, the terminator skipping $11, XOR $0F at $12
+    patch.extend(number(0x10));
+    patch.extend_from_slice(&[0xFF, 0x00]);
+    patch.extend(number(0));
+    patch.extend_from_slice(&[0x0F, 0x00]);
+    patch.extend(number(target.len() - 1 - 0x14));
+    patch.extend_from_slice(&[0x42, 0x00]);
+    let patch = with_footer(patch, &rom, &target);
+    assert_eq!(apply(&rom, &patch).unwrap(), target);
+
+    let mut other = rom.clone();
+    other[0] ^= 1;
+    assert_eq!(
+        apply(&other, &patch),
+        Err(PatchError::Source {
+            expected: crc32(&rom),
+            actual: crc32(&other),
+        })
+    );
+    let mut damaged = patch.clone();
+    damaged[10] ^= 1;
+    assert_eq!(apply(&rom, &damaged), Err(PatchError::Damaged));
+}
+
+#[test]
+fn applies_bps() {
+    let rom = rom();
+    let mut target = rom[..0x100].to_vec();
+    target.extend_from_slice(b"ABABAB");
+    target.extend_from_slice(&rom[0x106..]);
+
+    let mut patch = b"BPS1".to_vec();
+    patch.extend(number(rom.len()));
+    patch.extend(number(target.len()));
+    patch.extend(number(4));
+    patch.extend_from_slice(b"meta");
+    // Source read of $100 bytes
+    patch.extend(number((0x100 - 1) << 2));
+    // Target read of "AB"
+    patch.extend(number((2 - 1) << 2 | 1));
+    patch.extend_from_slice(b"AB");
+    // Target copy of 4 bytes from $100, repeating "AB"
+    patch.extend(number((4 - 1) << 2 | 3));
+    patch.extend(number(0x100 << 1));
+    // Source copy of the rest from $106
+    patch.extend(number((rom.len() - 0x106 - 1) << 2 | 2));
+    patch.extend(number(0x106 << 1));
+    let patch = with_footer(patch, &rom, &target);
+    assert_eq!(apply(&rom, &patch).unwrap(), target);
+
+    // A patch whose target CRC doesn't match what it produces
+    let body = patch[..patch.len() - 12].to_vec();
+    let patch = with_footer(body, &rom, &rom);
+    assert_eq!(
+        apply(&rom, &patch),
+        Err(PatchError::Target {
+            expected: crc32(&rom),
+            actual: crc32(&target),
+        })
+    );
+}
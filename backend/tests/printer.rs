@@ -0,0 +1,96 @@
+//! Sends Game Boy Printer packets the way games do and checks the replies and the PNG printed
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use backend::link::{Printer, SerialLink};
+
+const INIT: u8 = 0x01;
+const PRINT: u8 = 0x02;
+const DATA: u8 = 0x04;
+const STATUS: u8 = 0x0F;
+
+const DEVICE_ID: u8 = 0x81;
+const CHECKSUM_ERROR: u8 = 0x01;
+const BUSY: u8 = 0x02;
+const IMAGE_FULL: u8 = 0x04;
+const UNPROCESSED: u8 = 0x08;
+
+// 2 rows of 20 tiles
+const PACKET_DATA_SIZE: usize = 640;
+
+/// Sends a packet, returns the device ID and status replied after it
+fn send(printer: &mut Printer, command: u8, compressed: bool, data: &[u8]) -> (u8, u8) {
+    let mut packet = vec![0x88, 0x33, command, compressed as u8];
+    packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    packet.extend_from_slice(data);
+    let checksum = packet[2..]
+        .iter()
+        .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+    packet.extend_from_slice(&checksum.to_le_bytes());
+    for byte in packet {
+        assert_eq!(printer.exchange(byte), 0x00);
+    }
+    (printer.exchange(0x00), printer.exchange(0x00))
+}
+
+fn output_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("gb-emu-printer-{}", name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn png_size(png: &[u8]) -> (u32, u32) {
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    (width, height)
+}
+
+#[test]
+fn print_compressed_image() {
+    let directory = output_directory("print");
+    let mut printer = Printer::new(directory.clone());
+
+    assert_eq!(send(&mut printer, INIT, false, &[]), (DEVICE_ID, 0));
+    // Stripes: runs of 64 bytes of 0x55 then 0xAA
+    let mut compressed = Vec::new();
+    for _ in 0..PACKET_DATA_SIZE / 128 {
+        compressed.extend_from_slice(&[0xBE, 0x55, 0xBE, 0xAA]);
+    }
+    let (_, status) = send(&mut printer, DATA, true, &compressed);
+    assert_eq!(status, UNPROCESSED);
+    let (_, status) = send(&mut printer, DATA, false, &[]);
+    assert_eq!(status, UNPROCESSED | IMAGE_FULL);
+
+    // 1 sheet, 1 margin unit before and after, default palette, default exposure
+    let (_, status) = send(&mut printer, PRINT, false, &[0x01, 0x11, 0xE4, 0x40]);
+    assert_eq!(status & BUSY, BUSY);
+    assert_eq!(printer.printed(), 1);
+    let mut status = BUSY;
+    for _ in 0..16 {
+        status = send(&mut printer, STATUS, false, &[]).1;
+        if status & BUSY == 0 {
+            break;
+        }
+    }
+    assert_eq!(status, 0);
+
+    let png = fs::read(directory.join("print_001.png")).unwrap();
+    assert_eq!(png_size(&png), (160, 8 + 16 + 8));
+}
+
+#[test]
+fn checksum_error() {
+    let mut printer = Printer::new(output_directory("checksum"));
+
+    let packet = [0x88, 0x33, DATA, 0x00, 0x01, 0x00, 0x42, 0x00, 0x00];
+    for byte in packet {
+        printer.exchange(byte);
+    }
+    assert_eq!(printer.exchange(0x00), DEVICE_ID);
+    assert_eq!(printer.exchange(0x00), CHECKSUM_ERROR);
+
+    assert_eq!(send(&mut printer, INIT, false, &[]), (DEVICE_ID, 0));
+}
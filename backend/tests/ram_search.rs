@@ -0,0 +1,67 @@
+//! Narrows down RAM locations between snapshots
+
+use backend::debug::debugger::Debugger;
+use backend::debug::ram_search::{Filter, Location, RamSearch, Region, Size};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+/// Loops forever, with 8 KiB of cartridge RAM
+fn idle_rom() -> Vec<u8> {
+    // JR -2, MBC1+RAM with 8 KiB of RAM
+    RomBuilder::new(&[0x18, 0xFE]).cartridge(0x02, 0x02).build()
+}
+
+fn locations(search: &RamSearch) -> Vec<Location> {
+    search.candidates().iter().map(|c| c.location).collect()
+}
+
+#[test]
+fn finds_bytes() {
+    let mut debugger = Debugger::new(System::new(None, idle_rom()).unwrap());
+    assert_eq!(debugger.filter_search(Filter::Changed), None);
+    // WRAM, HRAM and cartridge RAM
+    assert_eq!(debugger.start_search(Size::Byte), 0x2000 + 0x7F + 0x2000);
+
+    for value in [0x10, 0x11, 0x0F] {
+        debugger.poke(0xC123, value);
+        debugger.poke(0xFF90, value);
+        let filter = match value {
+            0x10 => Filter::Changed,
+            0x11 => Filter::Increased,
+            _ => Filter::Decreased,
+        };
+        assert!(debugger.filter_search(filter).unwrap() >= 2);
+    }
+    assert_eq!(debugger.filter_search(Filter::Equal), Some(2));
+    debugger.poke(0xFF90, 0x42);
+    assert_eq!(debugger.filter_search(Filter::Value(0x0F)), Some(1));
+    let search = debugger.ram_search().unwrap();
+    let candidate = search.candidates()[0];
+    assert_eq!(
+        candidate.location,
+        Location {
+            region: Region::Wram,
+            offset: 0x123,
+        }
+    );
+    assert_eq!((candidate.previous, candidate.value), (0x0F, 0x0F));
+}
+
+#[test]
+fn finds_little_endian_words() {
+    let mut debugger = Debugger::new(System::new(None, idle_rom()).unwrap());
+    // Enables the cartridge RAM
+    debugger.poke(0x0000, 0x0A);
+    debugger.start_search(Size::Word);
+    debugger.poke(0xA800, 0x34);
+    debugger.poke(0xA801, 0x12);
+    debugger.filter_search(Filter::Changed);
+    assert_eq!(debugger.filter_search(Filter::Value(0x1234)), Some(1));
+    let search = debugger.ram_search().unwrap();
+    let location = locations(search)[0];
+    assert_eq!(location.to_string(), "$A800 (bank 0)");
+    assert_eq!(location.bank(), Some(0));
+}
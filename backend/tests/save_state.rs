@@ -0,0 +1,64 @@
+//! Saves and restores the state of a running system
+
+use backend::system::{Model, System};
+use backend::util::state::StateError;
+
+mod common;
+
+use common::RomBuilder;
+
+const CYCLES: u64 = 200_000;
+
+/// Counts in BC, with the LCD on and the timer running
+fn counter_rom(title: u8) -> Vec<u8> {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x05, // LD A, $05
+        0xE0, 0x07, // LDH (TAC), A
+        0x03,       // Loop: INC BC
+        0x18, 0xFD, // JR Loop
+    ];
+    RomBuilder::new(&code).title(&[title]).build()
+}
+
+fn run(system: &mut System, cycles: u64) {
+    let end = system.cycles() + cycles;
+    while system.cycles() < end {
+        system.step();
+    }
+}
+
+#[test]
+fn restored_system_runs_the_same() {
+    let mut system = System::new(None, counter_rom(0)).unwrap();
+    run(&mut system, CYCLES);
+    let state = system.save_state();
+
+    run(&mut system, CYCLES);
+    let expected = system.save_state();
+    system.load_state(&state).unwrap();
+    assert!(system.save_state() == state);
+    run(&mut system, CYCLES);
+    assert!(system.save_state() == expected);
+
+    let mut other = System::new(None, counter_rom(0)).unwrap();
+    other.load_state(&state).unwrap();
+    run(&mut other, CYCLES);
+    assert!(other.save_state() == expected);
+}
+
+#[test]
+fn rejects_other_states() {
+    let state = System::new(None, counter_rom(0)).unwrap().save_state();
+
+    let mut system = System::new(None, counter_rom(1)).unwrap();
+    assert_eq!(system.load_state(&state), Err(StateError::OtherSystem));
+    let mut system = System::with_model(Model::Cgb, None, counter_rom(0)).unwrap();
+    assert_eq!(system.load_state(&state), Err(StateError::OtherSystem));
+    let mut system = System::new(None, counter_rom(0)).unwrap();
+    assert_eq!(system.load_state(b"not a state"), Err(StateError::NotAState));
+    assert_eq!(
+        system.load_state(&state[..state.len() / 2]),
+        Err(StateError::Truncated)
+    );
+}
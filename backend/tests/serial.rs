@@ -0,0 +1,105 @@
+//! Transfers with the external clock: the serial port waits for the peer to clock them
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use backend::debug::debugger::Debugger;
+use backend::link::{SerialLink, IDLE_BYTE};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+const SB: u16 = 0xFF01;
+const SC: u16 = 0xFF02;
+const IF: u16 = 0xFF0F;
+const SERIAL_INTERRUPT: u8 = 0x08;
+// Way longer than a transfer with the normal clock
+const CYCLES: u64 = 100_000;
+
+/// Peer clocking a transfer with `byte` once polled `polls` times, keeping the bytes it
+/// was offered
+struct ExternalClock {
+    polls: u32,
+    byte: u8,
+    offered: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SerialLink for ExternalClock {
+    fn exchange(&mut self, _: u8) -> u8 {
+        panic!("The peer drives the clock");
+    }
+
+    fn poll(&mut self, byte: u8) -> Option<u8> {
+        self.offered.borrow_mut().push(byte);
+        self.polls = self.polls.saturating_sub(1);
+        (self.polls == 0).then_some(self.byte)
+    }
+}
+
+/// Starts a transfer of $5A with SC, then loops
+fn transfer(sc: u8, link: Option<Box<dyn SerialLink>>) -> Debugger {
+    #[rustfmt::skip]
+    let code = [
+        0x3E, 0x5A, // LD A, $5A
+        0xE0, 0x01, // LDH (SB), A
+        0x3E, sc,   // LD A, sc
+        0xE0, 0x02, // LDH (SC), A
+        0x18, 0xFE, // JR -2
+    ];
+    let mut system = System::new(None, RomBuilder::new(&code).build()).unwrap();
+    if let Some(link) = link {
+        system.set_serial_link(link);
+    }
+    Debugger::new(system)
+}
+
+fn run(debugger: &mut Debugger, cycles: u64) {
+    let end = debugger.system().cycles() + cycles;
+    while debugger.system().cycles() < end {
+        debugger.step();
+    }
+}
+
+#[test]
+fn waits_for_the_peer_clock() {
+    let offered = Rc::new(RefCell::new(Vec::new()));
+    let link = ExternalClock {
+        polls: 1000,
+        byte: 0xC3,
+        offered: offered.clone(),
+    };
+    let mut debugger = transfer(0x80, Some(Box::new(link)));
+    // Up to the SC write
+    for _ in 0..6 {
+        debugger.step();
+    }
+    assert_eq!(debugger.peek(SC) & 0x80, 0x80);
+    assert_eq!(debugger.peek(IF) & SERIAL_INTERRUPT, 0);
+    assert!(offered.borrow().iter().all(|&byte| byte == 0x5A));
+
+    run(&mut debugger, CYCLES);
+    assert_eq!(offered.borrow().len(), 1000);
+    assert_eq!(debugger.peek(SB), 0xC3);
+    assert_eq!(debugger.peek(SC) & 0x80, 0);
+    assert_eq!(debugger.peek(IF) & SERIAL_INTERRUPT, SERIAL_INTERRUPT);
+    // Only the bytes sent with the internal clock are logged
+    assert_eq!(debugger.system().serial_output(), "");
+}
+
+#[test]
+fn waits_forever_without_peer() {
+    let mut debugger = transfer(0x80, None);
+    run(&mut debugger, CYCLES);
+    assert_eq!(debugger.peek(SB), 0x5A);
+    assert_eq!(debugger.peek(SC) & 0x80, 0x80);
+    assert_eq!(debugger.peek(IF) & SERIAL_INTERRUPT, 0);
+
+    // With the internal clock, the idle line is shifted in
+    let mut debugger = transfer(0x81, None);
+    run(&mut debugger, CYCLES);
+    assert_eq!(debugger.peek(SB), IDLE_BYTE);
+    assert_eq!(debugger.peek(SC) & 0x80, 0);
+    assert_eq!(debugger.peek(IF) & SERIAL_INTERRUPT, SERIAL_INTERRUPT);
+}
@@ -0,0 +1,326 @@
+//! Runs test ROMs headlessly and prints a results table.
+//!
+//! ROMs (`.gb`/`.gbc`) are searched recursively in the directory given by `GB_TEST_ROMS`,
+//! the test is skipped when it is not set. The way a ROM reports its result depends on
+//! its path:
+//! - `mooneye`: `LD B,B` with B, C, D, E, H, L set to the Fibonacci sequence 3, 5, 8, 13,
+//!   21, 34 on success (0x42 on failure)
+//! - `acid2`: `LD B,B` once the screen is drawn, the screenshot hash is compared to the one
+//!   listed for the ROM in `screenshot_hashes.txt` (`<file name> <hash>` per line)
+//! - anything else is treated as blargg: "Passed"/"Failed" on the serial port, or the
+//!   0xA000 status byte once the DE B0 61 signature is written at 0xA001
+//!
+//! CGB only ROMs run on a CGB, the others on a DMG. ROMs for a mapper that isn't emulated
+//! yet are skipped.
+//!
+//! Other variables: `GB_TEST_FILTER` only runs ROMs whose path contains the given text,
+//! `GB_TEST_TIMEOUT` is the emulated time limit per ROM in seconds (default 30).
+//!
+//! Run with `cargo test --release --test test_roms -- --nocapture`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use backend::debug::debugger::{Debugger, Register};
+use backend::system::{CartridgeError, Model, System};
+
+const CLOCK_SPEED: u64 = 4_194_304;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+// Serial output and cartridge RAM are only checked every so many instructions
+const POLL_INTERVAL: u64 = 4096;
+const LD_B_B: u8 = 0x40;
+const FIBONACCI: [u16; 6] = [3, 5, 8, 13, 21, 34];
+const A000_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const HASHES_FILE: &str = "screenshot_hashes.txt";
+const CGB_FLAG: usize = 0x143;
+const CGB_ONLY: u8 = 0xC0;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Suite {
+    Blargg,
+    Mooneye,
+    Acid2,
+}
+
+impl Suite {
+    fn of(path: &Path) -> Suite {
+        let path = path.to_string_lossy().to_lowercase();
+        if path.contains("acid2") {
+            Suite::Acid2
+        } else if path.contains("mooneye") {
+            Suite::Mooneye
+        } else {
+            Suite::Blargg
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Suite::Blargg => "blargg",
+            Suite::Mooneye => "mooneye",
+            Suite::Acid2 => "acid2",
+        }
+    }
+}
+
+enum Outcome {
+    Pass,
+    Fail(String),
+    Timeout,
+    Skipped(String),
+    Panic(String),
+}
+
+impl Outcome {
+    fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Outcome::Fail(_) | Outcome::Timeout | Outcome::Panic(_)
+        )
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "PASS",
+            Outcome::Fail(_) => "FAIL",
+            Outcome::Timeout => "TIMEOUT",
+            Outcome::Skipped(_) => "SKIP",
+            Outcome::Panic(_) => "PANIC",
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            Outcome::Fail(detail) | Outcome::Skipped(detail) | Outcome::Panic(detail) => detail,
+            Outcome::Pass | Outcome::Timeout => "",
+        }
+    }
+}
+
+fn find_roms(directory: &Path, roms: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => panic!("Unable to read {}: {}", directory.display(), e),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_roms(&path, roms);
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("gb") | Some("gbc")
+        ) {
+            roms.push(path);
+        }
+    }
+}
+
+fn load_hashes(directory: &Path) -> HashMap<String, u64> {
+    let content = match fs::read_to_string(directory.join(HASHES_FILE)) {
+        Ok(content) => content,
+        Err(_) => return HashMap::new(),
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+            Some((name.to_string(), hash))
+        })
+        .collect()
+}
+
+/// FNV-1a, stable across platforms and runs
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+fn check_fibonacci(debugger: &Debugger) -> Outcome {
+    let registers = [
+        Register::B,
+        Register::C,
+        Register::D,
+        Register::E,
+        Register::H,
+        Register::L,
+    ]
+    .map(|register| debugger.read_register(register));
+    if registers == FIBONACCI {
+        Outcome::Pass
+    } else {
+        Outcome::Fail(format!(
+            "B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X}",
+            registers[0], registers[1], registers[2], registers[3], registers[4], registers[5]
+        ))
+    }
+}
+
+fn check_screenshot(debugger: &Debugger, name: &str, hashes: &HashMap<String, u64>) -> Outcome {
+    let actual = hash(&debugger.system().framebuffer());
+    match hashes.get(name) {
+        Some(expected) if *expected == actual => Outcome::Pass,
+        Some(expected) => Outcome::Fail(format!(
+            "screenshot hash {:016x}, expected {:016x}",
+            actual, expected
+        )),
+        None => Outcome::Skipped(format!(
+            "screenshot hash {:016x}, no reference in {}",
+            actual, HASHES_FILE
+        )),
+    }
+}
+
+/// Result reported through the serial port or cartridge RAM, if any yet
+fn check_blargg(debugger: &Debugger) -> Option<Outcome> {
+    let serial = debugger.system().serial_output();
+    if serial.contains("Passed") {
+        return Some(Outcome::Pass);
+    }
+    if serial.contains("Failed") {
+        return Some(Outcome::Fail(serial.trim().replace('\n', " | ")));
+    }
+    let signature = [0xA001, 0xA002, 0xA003].map(|address| debugger.peek(address));
+    let status = debugger.peek(0xA000);
+    if signature != A000_SIGNATURE || status == 0x80 {
+        return None;
+    }
+    if status == 0 {
+        return Some(Outcome::Pass);
+    }
+    let text: String = (0xA004..0xBFFF)
+        .map(|address| debugger.peek(address))
+        .take_while(|byte| *byte != 0)
+        .map(|byte| byte as char)
+        .collect();
+    Some(Outcome::Fail(format!(
+        "status {:02X}: {}",
+        status,
+        text.trim().replace('\n', " | ")
+    )))
+}
+
+fn run(
+    rom: Vec<u8>,
+    suite: Suite,
+    name: &str,
+    hashes: &HashMap<String, u64>,
+    timeout: u64,
+) -> Outcome {
+    let model = match rom.get(CGB_FLAG) {
+        Some(&CGB_ONLY) => Model::Cgb,
+        _ => Model::Dmg,
+    };
+    let system = match System::with_model(model, None, rom) {
+        Ok(system) => system,
+        // Reported with the mapper, e.g. "unsupported cartridge type $13 (MBC3)"
+        Err(e @ CartridgeError::Unsupported(_)) => return Outcome::Skipped(e.to_string()),
+        Err(e) => return Outcome::Fail(e.to_string()),
+    };
+    let mut debugger = Debugger::new(system);
+    let mut executed: u64 = 0;
+    while debugger.system().cycles() < timeout {
+        if suite != Suite::Blargg && debugger.peek(debugger.pc()) == LD_B_B {
+            return match suite {
+                Suite::Mooneye => check_fibonacci(&debugger),
+                _ => check_screenshot(&debugger, name, hashes),
+            };
+        }
+        debugger.step();
+        executed += 1;
+        if suite == Suite::Blargg && executed.is_multiple_of(POLL_INTERVAL) {
+            if let Some(outcome) = check_blargg(&debugger) {
+                return outcome;
+            }
+        }
+    }
+    Outcome::Timeout
+}
+
+#[test]
+fn test_roms() {
+    let directory = match std::env::var_os("GB_TEST_ROMS") {
+        Some(directory) => PathBuf::from(directory),
+        None => {
+            println!("GB_TEST_ROMS is not set, skipping test ROMs");
+            return;
+        }
+    };
+    let filter = std::env::var("GB_TEST_FILTER").unwrap_or_default();
+    let timeout = std::env::var("GB_TEST_TIMEOUT")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+        * CLOCK_SPEED;
+    let hashes = load_hashes(&directory);
+
+    let mut roms = Vec::new();
+    find_roms(&directory, &mut roms);
+    roms.retain(|rom| rom.to_string_lossy().contains(&filter));
+    roms.sort();
+
+    let mut results = Vec::new();
+    for path in roms.iter() {
+        let relative = path
+            .strip_prefix(&directory)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let suite = Suite::of(path);
+        let outcome = match fs::read(path) {
+            Ok(rom) => panic::catch_unwind(AssertUnwindSafe(|| {
+                run(rom, suite, &name, &hashes, timeout)
+            }))
+            .unwrap_or_else(|e| {
+                let message = e
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or_default();
+                Outcome::Panic(message)
+            }),
+            Err(e) => Outcome::Fail(format!("unable to read: {}", e)),
+        };
+        results.push((relative, suite, outcome));
+    }
+
+    let width = results
+        .iter()
+        .map(|(rom, _, _)| rom.len())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "\n{:<7}  {:<7}  {:<width$}  Detail",
+        "Result",
+        "Suite",
+        "ROM",
+        width = width
+    );
+    for (rom, suite, outcome) in results.iter() {
+        println!(
+            "{:<7}  {:<7}  {:<width$}  {}",
+            outcome.label(),
+            suite.name(),
+            rom,
+            outcome.detail(),
+            width = width
+        );
+    }
+    let passed = results
+        .iter()
+        .filter(|(_, _, o)| matches!(o, Outcome::Pass))
+        .count();
+    let failed = results.iter().filter(|(_, _, o)| o.is_failure()).count();
+    println!(
+        "\n{} passed, {} failed, {} skipped",
+        passed,
+        failed,
+        results.len() - passed - failed
+    );
+    assert_eq!(failed, 0, "{} test ROM(s) failed", failed);
+}
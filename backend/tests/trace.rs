@@ -0,0 +1,103 @@
+//! Logs the executed instructions in the Gameboy Doctor format
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use backend::debug::debugger::{Debugger, StopReason, WatchKind};
+use backend::system::System;
+
+mod common;
+
+use common::RomBuilder;
+
+/// Keeps what the system writes readable by the test
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[test]
+fn logs_instructions_before_they_execute() {
+    #[rustfmt::skip]
+    let code = [
+        0x01, 0x23, 0x01, // LD BC, $0123
+        0x11, 0x67, 0x45, // LD DE, $4567
+        0x21, 0xAB, 0x89, // LD HL, $89AB
+        0x31, 0xFE, 0xCF, // LD SP, $CFFE
+        0xC5,             // PUSH BC
+        0xF1,             // POP AF
+        0x76,             // HALT, for good without interrupts enabled
+    ];
+    let mut system = System::new(None, RomBuilder::new(&code).build()).unwrap();
+    let buffer = SharedBuffer::default();
+    system.set_trace(Some(Box::new(buffer.clone())));
+    for _ in 0..20 {
+        system.step();
+    }
+    let lines = buffer.lines();
+    // Nothing is logged while halted
+    let pcs: Vec<&str> = lines
+        .iter()
+        .flat_map(|line| line.split(' ').nth(9))
+        .collect();
+    assert_eq!(
+        pcs,
+        [
+            "PC:0100", "PC:0101", "PC:0150", "PC:0153", "PC:0156", "PC:0159", "PC:015C", "PC:015D",
+            "PC:015E"
+        ]
+    );
+    assert!(lines[0].ends_with(" SP:FFFE PC:0100 PCMEM:00,C3,50,01"));
+    assert!(lines[2].ends_with(" SP:FFFE PC:0150 PCMEM:01,23,01,11"));
+    // The low nibble of F always reads 0
+    assert_eq!(
+        lines[8],
+        "A:01 F:20 B:01 C:23 D:45 E:67 H:89 L:AB SP:CFFE PC:015E PCMEM:76,00,00,00"
+    );
+}
+
+#[test]
+fn stops_logging() {
+    let mut system = System::new(None, RomBuilder::new(&[]).build()).unwrap();
+    let buffer = SharedBuffer::default();
+    system.set_trace(Some(Box::new(buffer.clone())));
+    system.step();
+    system.set_trace(None);
+    assert!(!system.tracing());
+    system.step();
+    assert_eq!(buffer.lines().len(), 1);
+}
+
+#[test]
+fn does_not_trip_read_watchpoints() {
+    let system = System::new(None, RomBuilder::new(&[0x18, 0xFE]).build()).unwrap();
+    let mut debugger = Debugger::new(system);
+    let buffer = SharedBuffer::default();
+    debugger
+        .system_mut()
+        .set_trace(Some(Box::new(buffer.clone())));
+    // Only in the PCMEM of JP $0150, past its operands
+    debugger.add_watchpoint(0x104, WatchKind::Read);
+    assert!(matches!(debugger.resume(Some(10)), StopReason::StepLimit));
+    assert_eq!(buffer.lines().len(), 10);
+}
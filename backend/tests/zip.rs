@@ -0,0 +1,97 @@
+//! Reads deflate streams, zip archives and gzip files
+
+use backend::util::crc32::crc32;
+use backend::util::gzip::{Gzip, GzipError};
+use backend::util::inflate::inflate;
+use backend::util::zip::{Archive, ZipError};
+
+mod common;
+
+use common::stored_zip;
+
+#[test]
+fn inflates_fixed_and_dynamic_blocks() {
+    let fixed = [0x73, 0x4F, 0xCC, 0x4D, 0x55, 0x70, 0xCA, 0xAF, 0x04, 0x00];
+    assert_eq!(inflate(&fixed).unwrap(), b"Game Boy");
+
+    // 100 pseudo random characters, compressed with a dynamic Huffman code
+    let mut expected = Vec::new();
+    let mut seed: u32 = 1;
+    for _ in 0..100 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345) & 0x7FFF_FFFF;
+        expected.push(b"UDLR.......|"[(seed >> 16) as usize % 12]);
+    }
+    #[rustfmt::skip]
+    let dynamic = [
+        0x25, 0x8C, 0xB1, 0x0D, 0x00, 0x40, 0x08, 0x02, 0x37, 0xBA, 0x29, 0x28, 0xAD, 0x48,
+        0xDC, 0xC6, 0xE1, 0x5F, 0x7C, 0x0A, 0x3C, 0x91, 0x58, 0xAC, 0x06, 0x37, 0x9A, 0xEE,
+        0x10, 0x14, 0x63, 0x07, 0x38, 0x1E, 0x44, 0x2F, 0x3A, 0x4B, 0xFD, 0x7E, 0xA4, 0x73,
+        0xF7, 0xEF, 0x65, 0x48, 0x7B, 0xD2, 0xD1, 0xA5, 0xFB, 0xB4, 0x8C, 0x1E,
+    ];
+    assert_eq!(inflate(&dynamic).unwrap(), expected);
+
+    assert_eq!(inflate(&fixed[..5]), None);
+    assert_eq!(inflate(&dynamic[..30]), None);
+}
+
+#[test]
+fn reads_zip_entries() {
+    let zip = stored_zip(&[("dir/", b""), ("dir/Game.gb", b"ROM"), ("notes.txt", b"hi")]);
+    let archive = Archive::new(&zip).unwrap();
+    let names: Vec<_> = archive.entries().iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["dir/", "dir/Game.gb", "notes.txt"]);
+    assert!(archive.entries()[0].is_directory());
+    assert_eq!(
+        archive.read(archive.find("DIR/game.gb").unwrap()).unwrap(),
+        b"ROM"
+    );
+
+    let mut corrupt = zip.clone();
+    // The stored data of the first file
+    let position = corrupt.windows(3).position(|w| w == b"ROM").unwrap();
+    corrupt[position] = b'X';
+    let archive = Archive::new(&corrupt).unwrap();
+    assert_eq!(
+        archive.read(archive.find("dir/Game.gb").unwrap()),
+        Err(ZipError::Checksum(String::from("dir/Game.gb")))
+    );
+    assert!(matches!(Archive::new(b"not a zip"), Err(ZipError::NotAZip)));
+    assert!(matches!(
+        Archive::new(&zip[zip.len() / 2..]),
+        Err(ZipError::Corrupt)
+    ));
+}
+
+#[test]
+fn reads_gzip_files() {
+    // "Game Boy" compressed with a fixed Huffman code
+    let deflated = [0x73, 0x4F, 0xCC, 0x4D, 0x55, 0x70, 0xCA, 0xAF, 0x04, 0x00];
+    let gzip = |flags: u8, fields: &[u8]| {
+        let mut data = vec![0x1F, 0x8B, 0x08, flags, 0, 0, 0, 0, 0x00, 0x03];
+        data.extend_from_slice(fields);
+        data.extend_from_slice(&deflated);
+        data.extend_from_slice(&crc32(b"Game Boy").to_le_bytes());
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data
+    };
+
+    let file = Gzip::read(&gzip(0, &[])).unwrap();
+    assert_eq!(file.name, None);
+    assert_eq!(file.data, b"Game Boy");
+
+    // Extra field, name and comment
+    let fields = b"\x02\x00XYgame.gb\x00comment\x00";
+    let file = Gzip::read(&gzip(0x1C, fields)).unwrap();
+    assert_eq!(file.name.as_deref(), Some("game.gb"));
+    assert_eq!(file.data, b"Game Boy");
+
+    let mut corrupt = gzip(0, &[]);
+    let crc = corrupt.len() - 8;
+    corrupt[crc] ^= 1;
+    assert_eq!(Gzip::read(&corrupt).err(), Some(GzipError::Checksum));
+    assert_eq!(
+        Gzip::read(&gzip(0, &[])[..15]).err(),
+        Some(GzipError::Corrupt)
+    );
+    assert_eq!(Gzip::read(b"not a gzip").err(), Some(GzipError::NotAGzip));
+}
@@ -0,0 +1,362 @@
+use std::path::PathBuf;
+
+use backend::headless::RunOptions;
+use backend::system::Model;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+pub const MAX_SCALE: u32 = 8;
+// Of the patches applied by default, next to the ROM
+const PATCH_EXTENSIONS: [&str; 3] = ["ips", "ups", "bps"];
+
+/// What is plugged in the serial port
+#[derive(Clone)]
+pub enum Link {
+    Stdout,
+    /// Game Boy Printer saving its prints in the directory
+    Printer(PathBuf),
+    Listen(u16),
+    Connect(u16),
+}
+
+/// Side of a netplay session
+#[derive(Clone)]
+pub enum Netplay {
+    Listen(u16),
+    Connect(u16),
+}
+
+pub struct Config {
+    pub rom: PathBuf,
+    /// Of the zip archive
+    pub entry: Option<String>,
+    pub patch: Option<PathBuf>,
+    pub boot_rom: Option<PathBuf>,
+    pub model: Model,
+    /// Where battery backed cartridge RAM is saved, the ROM directory by default
+    pub save_dir: PathBuf,
+    pub scale: u32,
+    pub audio: bool,
+    pub headless: bool,
+    pub debug: bool,
+    pub gdb_port: Option<u16>,
+    pub trace: Option<PathBuf>,
+    /// Cheat codes to apply, `<rom name>.cht` next to the ROM if there is one
+    pub cheats: Option<PathBuf>,
+    pub link: Option<Link>,
+    pub netplay: Option<Netplay>,
+    pub input_delay: u8,
+    /// Movie to play back instead of running freely
+    pub movie: Option<PathBuf>,
+    pub run: RunOptions,
+    pub screenshot: Option<PathBuf>,
+    pub serial_log: Option<PathBuf>,
+}
+
+fn parse_address(value: &str) -> Result<u16, String> {
+    let digits = value
+        .strip_prefix('$')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+    u16::from_str_radix(digits, 16).map_err(|e| format!("invalid address {}: {}", value, e))
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    port.parse()
+        .map_err(|e| format!("invalid port {}: {}", port, e))
+}
+
+fn parse_link(value: &str) -> Result<Link, String> {
+    match value.split_once(':') {
+        None if value == "stdout" => Ok(Link::Stdout),
+        None if value == "printer" => Ok(Link::Printer(PathBuf::from("."))),
+        Some(("printer", directory)) => Ok(Link::Printer(PathBuf::from(directory))),
+        Some(("listen", port)) => parse_port(port).map(Link::Listen),
+        Some(("connect", port)) => parse_port(port).map(Link::Connect),
+        _ => Err(String::from(
+            "expected stdout, printer[:DIR], listen:PORT or connect:PORT",
+        )),
+    }
+}
+
+fn parse_netplay(value: &str) -> Result<Netplay, String> {
+    match value.split_once(':') {
+        Some(("listen", port)) => parse_port(port).map(Netplay::Listen),
+        Some(("connect", port)) => parse_port(port).map(Netplay::Connect),
+        _ => Err(String::from("expected listen:PORT or connect:PORT")),
+    }
+}
+
+fn command() -> Command {
+    Command::new("gb-emu")
+        .about("Gameboy emulator")
+        .arg(
+            Arg::new("rom")
+                .help("Cartridge ROM to run, or a .zip or .gz archive holding it")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("boot-rom")
+                .long("boot-rom")
+                .short('b')
+                .value_name("FILE")
+                .help("Boot ROM to run before the cartridge")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("entry")
+                .long("entry")
+                .value_name("NAME")
+                .help("File of the zip archive to run [default: the first .gb or .gbc file]"),
+        )
+        .arg(
+            Arg::new("patch")
+                .long("patch")
+                .short('p')
+                .value_name("FILE")
+                .help(
+                    "IPS, UPS or BPS patch to apply to the ROM [default: <rom name>.ips, .ups \
+                     or .bps next to the ROM]",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("model")
+                .long("model")
+                .short('m')
+                .value_name("MODEL")
+                .help("Hardware to emulate")
+                .value_parser(["dmg0", "dmg", "mgb", "sgb", "sgb2", "cgb", "agb"])
+                .ignore_case(true)
+                .default_value("dmg"),
+        )
+        .arg(
+            Arg::new("save-dir")
+                .long("save-dir")
+                .value_name("DIR")
+                .help("Directory of the battery saves [default: the ROM directory]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("scale")
+                .long("scale")
+                .short('s')
+                .value_name("N")
+                .help(
+                    "Window size as a multiple of the screen resolution, ignored until there \
+                     is a window",
+                )
+                .value_parser(value_parser!(u32).range(1..=MAX_SCALE as i64))
+                .default_value("3"),
+        )
+        .arg(
+            Arg::new("no-audio")
+                .long("no-audio")
+                .help("Disable sound output, ignored until there is audio output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("debug")
+                .long("debug")
+                .short('d')
+                .help("Start the debugger REPL")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["headless", "gdb"]),
+        )
+        .arg(
+            Arg::new("gdb")
+                .long("gdb")
+                .value_name("PORT")
+                .help("Serve the GDB remote protocol on localhost")
+                .value_parser(value_parser!(u16))
+                .conflicts_with("headless"),
+        )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .value_name("FILE")
+                .help("Log executed instructions in the Gameboy Doctor format")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("cheats")
+                .long("cheats")
+                .value_name("FILE")
+                .help(
+                    "Game Genie and GameShark codes to apply, one per line followed by an \
+                     optional description [default: <rom name>.cht next to the ROM]",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("link")
+                .long("link")
+                .value_name("LINK")
+                .help(
+                    "Plug in the serial port: stdout prints the bytes sent, printer[:DIR] saves \
+                     Game Boy Printer prints in DIR, listen:PORT and connect:PORT link to \
+                     another emulator over localhost",
+                )
+                .value_parser(parse_link),
+        )
+        .next_help_heading("Netplay")
+        .arg(
+            Arg::new("netplay")
+                .long("netplay")
+                .value_name("SIDE")
+                .help(
+                    "Play linked with another emulator over localhost: listen:PORT as player 1, \
+                     connect:PORT as player 2",
+                )
+                .value_parser(parse_netplay)
+                .conflicts_with_all(["boot-rom", "debug", "gdb", "link"]),
+        )
+        .arg(
+            Arg::new("input-delay")
+                .long("input-delay")
+                .value_name("FRAMES")
+                .help("Frames between an input and its effect, set by player 1")
+                .value_parser(value_parser!(u8))
+                .default_value("2")
+                .requires("netplay"),
+        )
+        .next_help_heading("Movies")
+        .arg(
+            Arg::new("play")
+                .long("play")
+                .value_name("MOVIE")
+                .help(
+                    "Play back the inputs of MOVIE from its start, until its end, without \
+                     cheats, also reads BizHawk .bk2 and VisualBoyAdvance .vbm movies. Movies \
+                     are recorded with the backend::movie API",
+                )
+                .value_parser(value_parser!(PathBuf))
+                .requires("headless")
+                .conflicts_with_all(["link", "netplay", "cheats"]),
+        )
+        .next_help_heading("Headless")
+        .arg(
+            Arg::new("headless")
+                .long("headless")
+                .help("Run without window nor sound until a limit or a stop condition")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("frames")
+                .long("frames")
+                .value_name("N")
+                .help("Run at most N frames")
+                .value_parser(value_parser!(u64))
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("cycles")
+                .long("cycles")
+                .value_name("N")
+                .help("Run at most N clock cycles")
+                .value_parser(value_parser!(u64))
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("stop-pc")
+                .long("stop-pc")
+                .value_name("ADDRESS")
+                .help("Stop before executing the instruction at ADDRESS (hex)")
+                .value_parser(parse_address)
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("stop-serial")
+                .long("stop-serial")
+                .value_name("TEXT")
+                .help("Stop once the serial output contains TEXT")
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("stop-loop")
+                .long("stop-loop")
+                .help("Stop on a JR -2 infinite loop or an illegal opcode locking up the CPU")
+                .action(ArgAction::SetTrue)
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("screenshot")
+                .long("screenshot")
+                .value_name("FILE")
+                .help("Save the last frame as PNG on exit")
+                .value_parser(value_parser!(PathBuf))
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("serial-log")
+                .long("serial-log")
+                .value_name("FILE")
+                .help("Save the serial output on exit")
+                .value_parser(value_parser!(PathBuf))
+                .requires("headless"),
+        )
+        .after_help(
+            "Headless exit status: 0 when stopped on a condition (or a limit when no condition \
+             is given), 1 when a limit was reached before any condition, 2 on errors",
+        )
+}
+
+impl Config {
+    pub fn parse() -> Config {
+        Config::from_matches(command().get_matches())
+    }
+
+    fn from_matches(matches: ArgMatches) -> Config {
+        let rom = matches.get_one::<PathBuf>("rom").unwrap().clone();
+        let save_dir = match matches.get_one::<PathBuf>("save-dir") {
+            Some(dir) => dir.clone(),
+            None => rom.parent().map(PathBuf::from).unwrap_or_default(),
+        };
+        let patch = match matches.get_one::<PathBuf>("patch") {
+            Some(path) => Some(path.clone()),
+            None => PATCH_EXTENSIONS
+                .iter()
+                .map(|extension| rom.with_extension(extension))
+                .find(|path| path.exists()),
+        };
+        let cheats = match matches.get_one::<PathBuf>("cheats") {
+            Some(path) => Some(path.clone()),
+            // Cheats would change what the inputs of a movie do
+            None if matches.contains_id("play") => None,
+            None => Some(rom.with_extension("cht")).filter(|path| path.exists()),
+        };
+        let model = matches
+            .get_one::<String>("model")
+            .unwrap()
+            .parse()
+            .expect("model values are checked by clap");
+        Config {
+            entry: matches.get_one::<String>("entry").cloned(),
+            patch,
+            boot_rom: matches.get_one::<PathBuf>("boot-rom").cloned(),
+            model,
+            save_dir,
+            scale: *matches.get_one::<u32>("scale").unwrap(),
+            audio: !matches.get_flag("no-audio"),
+            headless: matches.get_flag("headless"),
+            debug: matches.get_flag("debug"),
+            gdb_port: matches.get_one::<u16>("gdb").copied(),
+            trace: matches.get_one::<PathBuf>("trace").cloned(),
+            cheats,
+            link: matches.get_one::<Link>("link").cloned(),
+            netplay: matches.get_one::<Netplay>("netplay").cloned(),
+            input_delay: *matches.get_one::<u8>("input-delay").unwrap(),
+            movie: matches.get_one::<PathBuf>("play").cloned(),
+            run: RunOptions {
+                max_frames: matches.get_one::<u64>("frames").copied(),
+                max_cycles: matches.get_one::<u64>("cycles").copied(),
+                stop_pc: matches.get_one::<u16>("stop-pc").copied(),
+                stop_serial: matches.get_one::<String>("stop-serial").cloned(),
+                stop_on_loop: matches.get_flag("stop-loop"),
+            },
+            screenshot: matches.get_one::<PathBuf>("screenshot").cloned(),
+            serial_log: matches.get_one::<PathBuf>("serial-log").cloned(),
+            rom,
+        }
+    }
+}
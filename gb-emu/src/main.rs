@@ -1,3 +1,359 @@
+mod cli;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind};
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use backend::debug::{debugger::Debugger, gdb, repl};
+use backend::headless;
+use backend::link::{Printer, SerialLink, StdoutLink, TcpLink};
+use backend::movie::Movie;
+use backend::netplay::{Console, NetplayError, Session};
+use backend::patch;
+use backend::system::{Buttons, System};
+use backend::util::gzip::Gzip;
+use backend::util::zip::Archive;
+use signal_hook::consts::SIGINT;
+
+use cli::{Config, Link, Netplay};
+
+const EXIT_LIMIT_REACHED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+fn read_file(path: &Path, what: &str) -> Vec<u8> {
+    match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Unable to read {} {}: {}", what, path.display(), e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Extracts the ROM of a zip archive (the first `.gb` or `.gbc` file unless an entry is
+/// chosen) or of a gzip file, returns it with the name of the ROM file inside
+fn extract_rom(config: &Config, data: Vec<u8>) -> Result<(Vec<u8>, PathBuf), String> {
+    let extension = config.rom.extension().unwrap_or_default();
+    if extension.eq_ignore_ascii_case("zip") {
+        let archive = Archive::new(&data).map_err(|e| e.to_string())?;
+        let entry = match &config.entry {
+            Some(name) => archive
+                .find(name)
+                .ok_or_else(|| format!("no {} in the archive", name))?,
+            None => archive
+                .entries()
+                .iter()
+                .find(|entry| {
+                    let extension = Path::new(&entry.name).extension().unwrap_or_default();
+                    !entry.is_directory()
+                        && (extension.eq_ignore_ascii_case("gb")
+                            || extension.eq_ignore_ascii_case("gbc"))
+                })
+                .ok_or("no .gb or .gbc file in the archive")?,
+        };
+        let rom = archive.read(entry).map_err(|e| e.to_string())?;
+        return Ok((rom, PathBuf::from(&entry.name)));
+    }
+    if config.entry.is_some() {
+        return Err(String::from("--entry only applies to zip archives"));
+    }
+    if extension.eq_ignore_ascii_case("gz") {
+        let gzip = Gzip::read(&data).map_err(|e| e.to_string())?;
+        // Without a stored name, the file is named after the ROM with .gz appended
+        let name = match gzip.name {
+            Some(name) => PathBuf::from(name),
+            None => config.rom.with_extension(""),
+        };
+        return Ok((gzip.data, name));
+    }
+    Ok((data, config.rom.clone()))
+}
+
+/// Reads the ROM, from its archive if any, and applies its patch if any. Returns it with the
+/// name of the ROM file, inside the archive if any.
+fn load_rom(config: &Config) -> (Vec<u8>, PathBuf) {
+    let data = read_file(&config.rom, "ROM");
+    let (rom, name) = match extract_rom(config, data) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("Unable to read ROM {}: {}", config.rom.display(), e);
+            process::exit(EXIT_ERROR);
+        }
+    };
+    let Some(path) = &config.patch else {
+        return (rom, name);
+    };
+    match patch::apply(&rom, &read_file(path, "patch")) {
+        Ok(rom) => (rom, name),
+        Err(e) => {
+            eprintln!("Unable to apply patch {}: {}", path.display(), e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+fn open_link(link: &Link) -> Box<dyn SerialLink> {
+    let result = match link {
+        Link::Stdout => return Box::new(StdoutLink),
+        Link::Printer(directory) => return Box::new(Printer::new(directory.clone())),
+        Link::Listen(port) => TcpLink::listen(*port),
+        Link::Connect(port) => TcpLink::connect(*port),
+    };
+    match result {
+        Ok(link) => Box::new(link),
+        Err(e) => {
+            eprintln!("Unable to open the link cable connection: {}", e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Adds the cheat codes of the file at `path`, one per line followed by an optional
+/// description, `#` starting a comment
+fn load_cheats(system: &mut System, path: &Path) {
+    let text = String::from_utf8_lossy(&read_file(path, "cheats")).into_owned();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some(code) = line.split_whitespace().next() else {
+            continue;
+        };
+        if let Err(e) = system.add_cheat(code) {
+            eprintln!("{}:{}: {}", path.display(), number + 1, e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Battery save named after the ROM file `rom_name`
+fn save_path(config: &Config, rom_name: &Path) -> PathBuf {
+    let name = rom_name.file_stem().unwrap_or_default();
+    config.save_dir.join(name).with_extension("sav")
+}
+
+/// Writes the battery backed RAM, if any, returns whether it succeeded
+fn write_save(system: &System, path: &Path) -> bool {
+    let Some(ram) = system.save_ram() else {
+        return true;
+    };
+    let result = match path.parent() {
+        Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(path, ram)),
+        None => fs::write(path, ram),
+    };
+    if let Err(e) = result {
+        eprintln!("Unable to write save {}: {}", path.display(), e);
+        return false;
+    }
+    true
+}
+
+/// Saves the screenshot and the serial log asked for, returns the exit status
+fn write_outputs(system: &System, config: &Config) -> i32 {
+    let mut status = 0;
+    if let Some(path) = &config.screenshot {
+        if let Err(e) = fs::write(path, system.screenshot()) {
+            eprintln!("Unable to write screenshot {}: {}", path.display(), e);
+            status = EXIT_ERROR;
+        }
+    }
+    if let Some(path) = &config.serial_log {
+        if let Err(e) = fs::write(path, system.serial_output()) {
+            eprintln!("Unable to write serial log {}: {}", path.display(), e);
+            status = EXIT_ERROR;
+        }
+    }
+    status
+}
+
+/// Runs `system` until the headless limits or stop conditions, returns the exit status
+fn run_headless(mut system: System, config: &Config, save: &Path) -> i32 {
+    let reason = headless::run(&mut system, &config.run);
+    // Flushes the trace
+    system.set_trace(None);
+    println!("Stopped: {}", reason);
+    println!("{}", headless::registers(&system));
+    let mut status = write_outputs(&system, config);
+    if !write_save(&system, save) {
+        status = EXIT_ERROR;
+    }
+    if status == 0 && reason.is_limit_reached(&config.run) {
+        status = EXIT_LIMIT_REACHED;
+    }
+    status
+}
+
+/// Plays back the movie at `path` on `system` until its end or the frame limit, returns the
+/// exit status. The battery save isn't written, the movie brings its own.
+fn run_movie(mut system: System, path: &Path, config: &Config) -> i32 {
+    let movie = match Movie::import(&read_file(path, "movie"), &mut system) {
+        Ok(movie) => movie,
+        Err(e) => {
+            eprintln!("Unable to read movie {}: {}", path.display(), e);
+            return EXIT_ERROR;
+        }
+    };
+    if let Err(e) = movie.rewind(&mut system) {
+        eprintln!("Unable to play movie {}: {}", path.display(), e);
+        return EXIT_ERROR;
+    }
+    while config
+        .run
+        .max_frames
+        .is_none_or(|limit| movie.frame(&system) < limit)
+        && movie.play_frame(&mut system)
+    {}
+    system.set_trace(None);
+    println!(
+        "Played {} of {} frames, {} rerecords",
+        movie.frame(&system),
+        movie.len(),
+        movie.rerecords()
+    );
+    println!("{}", headless::registers(&system));
+    write_outputs(&system, config)
+}
+
+/// Plays a netplay session until the frame limit or the other player leaves, returns the exit
+/// status
+fn run_netplay(netplay: &Netplay, config: &Config, rom: Vec<u8>, save: &Path) -> i32 {
+    let console = Console {
+        model: config.model,
+        rom,
+        save: if save.exists() {
+            read_file(save, "save")
+        } else {
+            Vec::new()
+        },
+    };
+    let session = match netplay {
+        Netplay::Listen(port) => Session::listen(*port, console, config.input_delay),
+        Netplay::Connect(port) => Session::connect(*port, console),
+    };
+    let mut session = match session {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Unable to start the netplay session: {}", e);
+            return EXIT_ERROR;
+        }
+    };
+    println!(
+        "Playing as player {} with {} frames of input delay",
+        session.player() + 1,
+        session.input_delay()
+    );
+    let mut status = loop {
+        if config
+            .run
+            .max_frames
+            .is_some_and(|limit| session.frame() >= limit)
+        {
+            break 0;
+        }
+        // No input device yet, the local player doesn't press anything
+        match session.run_frame(Buttons::NONE) {
+            Ok(()) => {}
+            Err(NetplayError::Io(e))
+                if matches!(
+                    e.kind(),
+                    ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset
+                ) =>
+            {
+                println!("The other player left");
+                break 0;
+            }
+            Err(e) => {
+                eprintln!("Netplay stopped: {}", e);
+                break EXIT_ERROR;
+            }
+        }
+    };
+    session.close();
+    println!("{} frames played", session.frame());
+    if let Some(path) = &config.screenshot {
+        if let Err(e) = fs::write(path, session.system().screenshot()) {
+            eprintln!("Unable to write screenshot {}: {}", path.display(), e);
+            status = EXIT_ERROR;
+        }
+    }
+    if !write_save(session.system(), save) {
+        status = EXIT_ERROR;
+    }
+    status
+}
+
 fn main() {
-    println!("Hello world !");
-}
\ No newline at end of file
+    let config = Config::parse();
+    let (rom, rom_name) = load_rom(&config);
+    let save = save_path(&config, &rom_name);
+    if let Some(netplay) = &config.netplay {
+        process::exit(run_netplay(netplay, &config, rom, &save));
+    }
+    let boot_rom = config
+        .boot_rom
+        .as_ref()
+        .map(|path| read_file(path, "boot ROM"));
+    let mut system = match System::with_model(config.model, boot_rom, rom) {
+        Ok(system) => system,
+        Err(e) => {
+            eprintln!("Unable to load ROM {}: {}", config.rom.display(), e);
+            process::exit(EXIT_ERROR);
+        }
+    };
+
+    if system.save_ram().is_some() && save.exists() {
+        system.load_save_ram(&read_file(&save, "save"));
+    }
+    if let Some(path) = &config.cheats {
+        load_cheats(&mut system, path);
+    }
+    if let Some(link) = &config.link {
+        system.set_serial_link(open_link(link));
+    }
+    if let Some(path) = &config.trace {
+        match File::create(path) {
+            Ok(file) => system.set_trace(Some(Box::new(BufWriter::new(file)))),
+            Err(e) => {
+                eprintln!("Unable to create trace file {}: {}", path.display(), e);
+                process::exit(EXIT_ERROR);
+            }
+        }
+    }
+
+    if let Some(path) = &config.movie {
+        process::exit(run_movie(system, path, &config));
+    }
+
+    if config.debug || config.gdb_port.is_some() {
+        let mut debugger = Debugger::new(system);
+        // Ctrl-C stops a `continue` rather than the emulator
+        if let Err(e) = signal_hook::flag::register(SIGINT, debugger.interrupt_flag()) {
+            eprintln!("Unable to handle Ctrl-C: {}", e);
+        }
+        let result = match config.gdb_port {
+            Some(port) => TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+                .and_then(|listener| gdb::serve(&mut debugger, listener)),
+            None => repl::run(&mut debugger, io::stdin().lock(), io::stdout()),
+        };
+        if let Err(e) = result {
+            eprintln!("Debugger error: {}", e);
+        }
+        debugger.system_mut().set_trace(None);
+        let status = if write_save(debugger.system(), &save) {
+            0
+        } else {
+            EXIT_ERROR
+        };
+        process::exit(status);
+    }
+
+    if !config.headless {
+        eprintln!(
+            "No window nor audio output yet, running {} headless (scale x{}, audio {})",
+            config.model,
+            config.scale,
+            if config.audio { "on" } else { "off" }
+        );
+    }
+    process::exit(run_headless(system, &config, &save));
+}